@@ -2,7 +2,11 @@
 #![deny(warnings)]
 #![deny(rust_2018_idioms)]
 #![deny(clippy::all, clippy::pedantic, clippy::nursery)]
-#![allow(clippy::too_many_arguments)]
+#![allow(
+    clippy::too_many_arguments,
+    clippy::fn_params_excessive_bools,
+    clippy::large_enum_variant
+)]
 
 use clap::{Parser, Subcommand};
 use hex::FromHex;
@@ -11,11 +15,19 @@ use std::{
     env, fs,
     io::Write,
     path::{Path, PathBuf},
+    process::{Command, Stdio},
     sync::Arc,
+    thread,
+};
+use zk_sca_guest_abi_utils::{
+    BuildOptions, BundleOptions, bundle_directory, compute_root_from_reader,
+    is_cargo_dependency_file,
 };
 use zk_sca_prover::{Prover, ProverError, program_id_digest};
 use zk_sca_types::{
-    LicensePolicy, PackageManager, PackageManagerSpec, PermittedDependencies, SourceBundle, Version,
+    ArtifactBundle, ArtifactKind, BundleKind, FeatureSelection, ImageAttestation, ImageFile,
+    LicensePolicy, LinkPolicy, MemberScope, MerkleFormat, OverridePolicy, PackageManager,
+    PackageManagerSpec, PermittedDependencies, RustEdition, SourceBundle, ToolchainPolicy, Version,
 };
 use zk_sca_verifier::{DecodedJournal, decode_journal, verify_receipt};
 
@@ -32,12 +44,29 @@ struct Cli {
 
 #[derive(Subcommand)]
 enum Cmd {
-    /// Generate a receipt for a source .tar.gz archive
+    /// Generate a receipt for a source .tar.gz archive, a published .crate file,
+    /// a binary built with `cargo auditable`, a container image or a Git commit
     Prove {
-        /// Path to the source .tar.gz archive
+        /// Path to the source .tar.gz archive, .crate file, binary, image or Git repository
         #[clap(short = 'a', long = "archive")]
         archive: PathBuf,
 
+        /// Analyze --archive as a binary built with `cargo auditable`
+        #[clap(long = "auditable-binary")]
+        auditable_binary: bool,
+
+        /// Analyze --archive as an OCI image layout directory or `docker save` tarball
+        #[clap(long = "container-image", conflicts_with = "auditable_binary")]
+        container_image: bool,
+
+        /// Analyze this commit of the Git repository at --archive (e.g., HEAD)
+        #[clap(
+            long = "git-commit",
+            value_name = "REV",
+            conflicts_with_all = ["auditable_binary", "container_image"]
+        )]
+        git_commit: Option<String>,
+
         /// Package manager used to resolve archive dependencies (e.g., Cargo)
         #[clap(short = 'm', long = "package-manager")]
         package_manager: String,
@@ -54,11 +83,76 @@ enum Cmd {
         #[clap(long = "allowed-licenses")]
         allowed_licenses: Vec<String>,
 
+        /// Only audit packages reachable from these workspace members (repeat flag)
+        #[clap(long = "member")]
+        members: Vec<String>,
+
+        /// Do not audit packages reachable only through dev-dependencies
+        #[clap(long = "exclude-dev-deps")]
+        exclude_dev_deps: bool,
+
+        /// Only audit optional dependencies these features activate (repeat flag)
+        #[clap(long = "features")]
+        features: Vec<String>,
+
+        /// Do not activate the `default` feature of the audited members
+        #[clap(long = "no-default-features")]
+        no_default_features: bool,
+
+        /// Resolve features even when no `--features` are given
+        #[clap(long = "resolve-features")]
+        resolve_features: bool,
+
+        /// Reject `[patch]`, `[replace]` and source replacement
+        #[clap(long = "forbid-overrides")]
+        forbid_overrides: bool,
+
+        /// Only accept `[patch]` and `[replace]` entries for these packages (repeat flag)
+        #[clap(long = "permit-override", conflicts_with = "forbid_overrides")]
+        permitted_overrides: Vec<String>,
+
+        /// Check vendored crates against their `.cargo-checksum.json` and Cargo.lock
+        #[clap(long = "verify-vendored-sources")]
+        verify_vendored_sources: bool,
+
+        /// Read dependency files stored as symbolic or hard links from their targets
+        #[clap(long = "resolve-links")]
+        resolve_links: bool,
+
+        /// Merkle tree format to hash the archive in (v1, v2 or file-records)
+        #[clap(long = "merkle-format", default_value = "v1")]
+        merkle_format: MerkleFormat,
+
+        /// Send the guest one multiproof of the archive's leaves instead of a path per leaf
+        #[clap(long = "multiproof")]
+        multiproof: bool,
+
+        /// Hash the archive on every available core
+        #[clap(long = "parallel-hashing")]
+        parallel_hashing: bool,
+
+        /// Require the rust-toolchain file to pin at least this release (semver)
+        #[clap(long = "min-toolchain")]
+        min_toolchain: Option<String>,
+
+        /// Require the rust-toolchain file to pin at most this release (semver)
+        #[clap(long = "max-toolchain")]
+        max_toolchain: Option<String>,
+
+        /// Require every workspace member to use at least this edition (e.g., 2021)
+        #[clap(long = "min-edition")]
+        min_edition: Option<String>,
+
+        /// Require every workspace member to use at most this edition
+        #[clap(long = "max-edition")]
+        max_edition: Option<String>,
+
         /// Run in RISC0 dev mode (no proof generated)
         #[clap(long = "dev-mode")]
         dev_mode: bool,
 
-        /// Log cycle counts during proving
+        /// Log cycle counts during proving, and the cycle each stage of the
+        /// analysis ends at
         #[clap(long = "cycle-report")]
         cycle_report: bool,
 
@@ -67,6 +161,40 @@ enum Cmd {
         output: Option<PathBuf>,
     },
 
+    /// Pack a directory into a canonical .tar.gz archive that reproduces its Merkle root
+    Bundle {
+        /// Path to the directory to pack
+        #[clap(short = 'd', long = "dir")]
+        dir: PathBuf,
+
+        /// Leave out files that the directory's .gitignore files ignore
+        #[clap(long = "gitignore")]
+        gitignore: bool,
+
+        /// Path to write the archive (defaults to `<directory name>.tar.gz`)
+        #[clap(long = "output")]
+        output: Option<PathBuf>,
+    },
+
+    /// Print the Merkle root a receipt for a .tar.gz archive or .crate file commits to
+    Hash {
+        /// Path to the .tar.gz archive, .crate file or directory (bundled as `bundle` does)
+        #[clap(short = 'a', long = "archive")]
+        archive: PathBuf,
+
+        /// Leave out files that a directory's .gitignore files ignore
+        #[clap(long = "gitignore")]
+        gitignore: bool,
+
+        /// Merkle tree format to hash the archive in (v1, v2 or file-records)
+        #[clap(long = "merkle-format", default_value = "v1")]
+        merkle_format: MerkleFormat,
+
+        /// Hash the archive on every available core
+        #[clap(long = "parallel-hashing")]
+        parallel_hashing: bool,
+    },
+
     /// Verify an existing receipt and optionally print its journal in JSON
     Verify {
         /// Path to the receipt file
@@ -80,6 +208,14 @@ enum Cmd {
         /// Print the journal contents in JSON format if verification succeeds
         #[clap(short = 'j', long = "print-journal")]
         print_journal: bool,
+
+        /// Also check that the receipt commits to this .tar.gz archive, .crate file or directory
+        #[clap(short = 'a', long = "archive")]
+        archive: Option<PathBuf>,
+
+        /// Leave out files that the --archive directory's .gitignore files ignore
+        #[clap(long = "gitignore", requires = "archive")]
+        gitignore: bool,
     },
 }
 
@@ -91,51 +227,206 @@ fn main() -> Result<(), DynError> {
     match cli.cmd {
         Cmd::Prove {
             archive,
+            auditable_binary,
+            container_image,
+            git_commit,
             package_manager,
             package_manager_version,
             permitted_deps,
             allowed_licenses,
+            members,
+            exclude_dev_deps,
+            features,
+            no_default_features,
+            resolve_features,
+            forbid_overrides,
+            permitted_overrides,
+            verify_vendored_sources,
+            resolve_links,
+            merkle_format,
+            multiproof,
+            parallel_hashing,
+            min_toolchain,
+            max_toolchain,
+            min_edition,
+            max_edition,
             dev_mode,
             cycle_report,
             output,
         } => prove_cmd(
             &archive,
+            auditable_binary,
+            container_image,
+            git_commit.as_deref(),
             &package_manager,
             &package_manager_version,
             &permitted_deps,
             &allowed_licenses,
+            members,
+            exclude_dev_deps,
+            feature_selection(features, no_default_features, resolve_features)?.as_ref(),
+            override_policy(forbid_overrides, permitted_overrides)?.as_ref(),
+            verify_vendored_sources,
+            link_policy(resolve_links),
+            merkle_format,
+            multiproof,
+            parallel_hashing,
+            toolchain_policy(
+                min_toolchain.as_deref(),
+                max_toolchain.as_deref(),
+                min_edition.as_deref(),
+                max_edition.as_deref(),
+            )?
+            .as_ref(),
             dev_mode,
             cycle_report,
             output,
         ),
+        Cmd::Bundle {
+            dir,
+            gitignore,
+            output,
+        } => bundle_cmd(&dir, gitignore, output),
+        Cmd::Hash {
+            archive,
+            gitignore,
+            merkle_format,
+            parallel_hashing,
+        } => {
+            let options = BuildOptions::default()
+                .with_merkle_format(merkle_format)
+                .with_parallel_hashing(parallel_hashing);
+            println!(
+                "Root hash: {}",
+                hex::encode(archive_root(&archive, gitignore, options)?)
+            );
+            Ok(())
+        }
         Cmd::Verify {
             receipt,
             program_id,
             print_journal,
-        } => verify_cmd(&receipt, &program_id, print_journal),
+            archive,
+            gitignore,
+        } => verify_cmd(
+            &receipt,
+            &program_id,
+            print_journal,
+            archive.as_deref(),
+            gitignore,
+        ),
+    }
+}
+
+/// Feature resolution is opt-in: any of the three flags turns it on.
+fn feature_selection(
+    features: Vec<String>,
+    no_default_features: bool,
+    resolve_features: bool,
+) -> Result<Option<FeatureSelection>, DynError> {
+    if features.is_empty() && !no_default_features && !resolve_features {
+        return Ok(None);
+    }
+    Ok(Some(FeatureSelection::try_new(
+        features,
+        !no_default_features,
+    )?))
+}
+
+const fn link_policy(resolve_links: bool) -> LinkPolicy {
+    if resolve_links {
+        LinkPolicy::Resolve
+    } else {
+        LinkPolicy::Reject
     }
 }
 
+fn override_policy(
+    forbid_overrides: bool,
+    permitted_overrides: Vec<String>,
+) -> Result<Option<OverridePolicy>, DynError> {
+    if forbid_overrides {
+        return Ok(Some(OverridePolicy::Forbid));
+    }
+    if permitted_overrides.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(OverridePolicy::restrict(permitted_overrides)?))
+}
+
+/// A toolchain policy applies as soon as any bound is given.
+fn toolchain_policy(
+    min_toolchain: Option<&str>,
+    max_toolchain: Option<&str>,
+    min_edition: Option<&str>,
+    max_edition: Option<&str>,
+) -> Result<Option<ToolchainPolicy>, DynError> {
+    if min_toolchain.is_none()
+        && max_toolchain.is_none()
+        && min_edition.is_none()
+        && max_edition.is_none()
+    {
+        return Ok(None);
+    }
+    let version = |v: &str| Version::parse(v).map_err(|e| format!("Invalid semver '{v}': {e}"));
+    Ok(Some(ToolchainPolicy::try_new(
+        min_toolchain.map(version).transpose()?,
+        max_toolchain.map(version).transpose()?,
+        min_edition.map(str::parse::<RustEdition>).transpose()?,
+        max_edition.map(str::parse::<RustEdition>).transpose()?,
+    )?))
+}
+
+/// Where `prove` writes the receipt for `archive` by default: the current
+/// directory, under the archive's name less its `.tar.gz`, or less its one
+/// extension if it has a `single_extension`, or in full for a repository.
+fn default_output_path(archive: &Path, repository: bool, single_extension: bool) -> PathBuf {
+    let fname = archive.file_name().expect("archive needs a filename");
+    // `<name>-<version>.crate` keeps the dots of its version.
+    let base = if repository {
+        Some(fname)
+    } else if single_extension {
+        Path::new(fname).file_stem()
+    } else {
+        Path::new(fname)
+            .file_stem()
+            .and_then(|s| Path::new(s).file_stem())
+    }
+    .expect("valid UTF-8 filename");
+    let mut fname = base.to_os_string();
+    fname.push(".zk-sca.bin");
+    env::current_dir()
+        .expect("cannot read current directory")
+        .join(fname)
+}
+
 fn prove_cmd(
     archive: &PathBuf,
+    auditable_binary: bool,
+    container_image: bool,
+    git_commit: Option<&str>,
     pm_name: &str,
     pm_version: &str,
     permitted_deps_path: &PathBuf,
     allowed_licenses: &[String],
+    members: Vec<String>,
+    exclude_dev_deps: bool,
+    feature_selection: Option<&FeatureSelection>,
+    override_policy: Option<&OverridePolicy>,
+    verify_vendored_sources: bool,
+    link_policy: LinkPolicy,
+    merkle_format: MerkleFormat,
+    multiproof: bool,
+    parallel_hashing: bool,
+    toolchain_policy: Option<&ToolchainPolicy>,
     dev_mode: bool,
     cycle_report: bool,
     output: Option<PathBuf>,
 ) -> Result<(), DynError> {
+    let is_crate_package = archive.extension().is_some_and(|ext| ext == "crate");
     let output_path = output.unwrap_or_else(|| {
-        let fname = archive.file_name().expect("archive needs a filename");
-        let base = Path::new(fname)
-            .file_stem()
-            .and_then(|s| Path::new(s).file_stem())
-            .expect("valid UTF-8 filename");
-        env::current_dir()
-            .expect("cannot read current directory")
-            .join(base)
-            .with_extension("zk-sca.bin")
+        let single_extension = is_crate_package || auditable_binary || container_image;
+        default_output_path(archive, git_commit.is_some(), single_extension)
     });
 
     let manager = match pm_name.to_lowercase().as_str() {
@@ -146,7 +437,13 @@ fn prove_cmd(
     let manager_version =
         Version::parse(pm_version).map_err(|e| format!("Invalid semver '{pm_version}': {e}"))?;
 
-    let tar_bytes = Arc::<[u8]>::from(fs::read(archive)?);
+    let tar_bytes = if let Some(rev) = git_commit {
+        Arc::<[u8]>::from(git_objects(archive, rev)?)
+    } else if archive.is_dir() {
+        Arc::<[u8]>::from(pack_directory(archive)?)
+    } else {
+        Arc::<[u8]>::from(fs::read(archive)?)
+    };
     let deps_raw = fs::read_to_string(permitted_deps_path)?;
     let permitted_dependencies: PermittedDependencies = serde_json::from_str(&deps_raw)?;
 
@@ -157,15 +454,57 @@ fn prove_cmd(
         Some(serde_json::from_str::<LicensePolicy>(&json)?)
     };
 
-    let bundle = SourceBundle::new(tar_bytes, PackageManagerSpec::new(manager, manager_version));
+    let member_scope = if !members.is_empty() {
+        Some(MemberScope::try_new(members)?.with_dev_dependencies_excluded(exclude_dev_deps))
+    } else if exclude_dev_deps {
+        Some(MemberScope::workspace().with_dev_dependencies_excluded(true))
+    } else {
+        None
+    };
+
+    let spec = PackageManagerSpec::new(manager, manager_version);
+    let artifact = if auditable_binary {
+        Some(ArtifactKind::AuditableBinary)
+    } else if container_image {
+        Some(ArtifactKind::ContainerImage)
+    } else if git_commit.is_some() {
+        Some(ArtifactKind::GitTree)
+    } else {
+        None
+    };
+    let prover = if let Some(kind) = artifact {
+        Prover::new().with_artifact(ArtifactBundle::new(tar_bytes, spec, kind))
+    } else {
+        let mut bundle = SourceBundle::new(tar_bytes, spec);
+        if is_crate_package {
+            bundle = bundle.with_kind(BundleKind::CratePackage);
+        }
+        Prover::new().with_bundle(bundle)
+    };
 
-    let mut prover = Prover::new()
-        .with_bundle(bundle)
-        .with_permitted_deps(&permitted_dependencies);
+    let mut prover = prover
+        .with_permitted_deps(&permitted_dependencies)
+        .with_vendored_sources_verified(verify_vendored_sources)
+        .with_link_policy(link_policy)
+        .with_merkle_format(merkle_format)
+        .with_multiproof(multiproof)
+        .with_parallel_hashing(parallel_hashing);
 
     if let Some(policy) = &license_policy {
         prover = prover.with_license_policy(policy);
     }
+    if let Some(scope) = &member_scope {
+        prover = prover.with_member_scope(scope);
+    }
+    if let Some(selection) = feature_selection {
+        prover = prover.with_feature_selection(selection);
+    }
+    if let Some(policy) = override_policy {
+        prover = prover.with_override_policy(policy);
+    }
+    if let Some(policy) = toolchain_policy {
+        prover = prover.with_toolchain_policy(policy);
+    }
     if dev_mode {
         prover = prover.with_dev_mode(true);
     }
@@ -192,6 +531,124 @@ fn prove_cmd(
     Ok(())
 }
 
+fn bundle_cmd(dir: &Path, gitignore: bool, output: Option<PathBuf>) -> Result<(), DynError> {
+    let output_path = if let Some(path) = output {
+        path
+    } else {
+        let dir = dir.canonicalize()?;
+        let mut fname = dir
+            .file_name()
+            .ok_or("cannot name the archive of the root directory; pass --output")?
+            .to_os_string();
+        fname.push(".tar.gz");
+        env::current_dir()?.join(fname)
+    };
+
+    let bytes = bundle_directory(dir, BundleOptions::default().with_gitignore(gitignore))?;
+    fs::write(&output_path, bytes)?;
+
+    println!("Bundle written to '{}'", output_path.display());
+    Ok(())
+}
+
+/// The `format` Merkle root of a tarball or `.crate` file, or of the
+/// canonical bundle of a directory.
+fn archive_root(
+    archive: &Path,
+    gitignore: bool,
+    options: BuildOptions,
+) -> Result<[u8; 32], DynError> {
+    if archive.is_dir() {
+        let bytes = bundle_directory(archive, BundleOptions::default().with_gitignore(gitignore))?;
+        let open = || Ok(bytes.as_slice());
+        return Ok(compute_root_from_reader(
+            open,
+            BundleKind::SourceTree,
+            options,
+        )?);
+    }
+    let kind = if archive.extension().is_some_and(|ext| ext == "crate") {
+        BundleKind::CratePackage
+    } else {
+        BundleKind::SourceTree
+    };
+    // Stream the file rather than reading it whole, as it may be large.
+    Ok(compute_root_from_reader(
+        || fs::File::open(archive),
+        kind,
+        options,
+    )?)
+}
+
+/// Pack an OCI image layout directory into a TAR, as `docker save` would.
+fn pack_directory(dir: &Path) -> Result<Vec<u8>, DynError> {
+    let mut builder = tar::Builder::new(Vec::new());
+    builder.follow_symlinks(false);
+    builder.append_dir_all(".", dir)?;
+    Ok(builder.into_inner()?)
+}
+
+/// Export a commit's objects as `git cat-file --batch` prints them: the
+/// commit, every tree, and the blob of every Cargo dependency file.
+fn git_objects(repo: &Path, rev: &str) -> Result<Vec<u8>, DynError> {
+    let git = |args: &[&str]| -> Result<Vec<u8>, DynError> {
+        let out = Command::new("git")
+            .arg("-C")
+            .arg(repo)
+            .args(args)
+            .output()?;
+        if !out.status.success() {
+            return Err(format!(
+                "git {} failed: {}",
+                args.join(" "),
+                String::from_utf8_lossy(&out.stderr).trim()
+            )
+            .into());
+        }
+        Ok(out.stdout)
+    };
+
+    // `^0` peels tags to the commit they point at.
+    let commit = String::from_utf8(git(&["rev-parse", "--verify", &format!("{rev}^0")])?)?
+        .trim()
+        .to_owned();
+    if commit.len() != 40 {
+        return Err("only SHA-1 repositories are supported".into());
+    }
+    // `<commit>:` names the commit's root tree.
+    let mut ids = format!("{commit}\n{commit}:\n");
+    for entry in git(&["ls-tree", "-r", "-t", "-z", "--full-tree", &commit])?.split(|&b| b == 0) {
+        // `<mode> <type> <id>\t<path>`
+        let entry = String::from_utf8_lossy(entry);
+        let Some((meta, path)) = entry.split_once('\t') else {
+            continue;
+        };
+        let fields: Vec<&str> = meta.split(' ').collect();
+        if let [_, kind, id] = fields[..] {
+            if kind == "tree" || (kind == "blob" && is_cargo_dependency_file(path)) {
+                ids.push_str(id);
+                ids.push('\n');
+            }
+        }
+    }
+
+    let mut child = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(["cat-file", "--batch"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let writer = thread::spawn(move || stdin.write_all(ids.as_bytes()));
+    let out = child.wait_with_output()?;
+    writer.join().expect("writer thread panicked")?;
+    if !out.status.success() {
+        return Err("git cat-file failed".into());
+    }
+    Ok(out.stdout)
+}
+
 fn parse_program_id(hex_str: &str) -> Result<Digest, DynError> {
     let bytes = <Vec<u8>>::from_hex(hex_str).map_err(|e| format!("invalid --program-id: {e}"))?;
     if bytes.len() != 32 {
@@ -206,10 +663,27 @@ fn parse_program_id(hex_str: &str) -> Result<Digest, DynError> {
     Ok(Digest::from(arr))
 }
 
+fn image_json(image: &ImageAttestation) -> serde_json::Value {
+    let files = |files: &[ImageFile]| {
+        files
+            .iter()
+            .map(|f| serde_json::json!({ "layer": f.layer(), "path": f.path() }))
+            .collect::<Vec<_>>()
+    };
+    serde_json::json!({
+        "manifest_digest": format!("sha256:{}", hex::encode(image.manifest_digest())),
+        "layer_roots": image.layer_roots().iter().map(hex::encode).collect::<Vec<_>>(),
+        "lockfiles": files(image.lockfiles()),
+        "binaries": files(image.binaries()),
+    })
+}
+
 fn verify_cmd(
     receipt_path: &PathBuf,
     program_id: &str,
     print_journal: bool,
+    archive: Option<&Path>,
+    gitignore: bool,
 ) -> Result<(), DynError> {
     let data = fs::read(receipt_path)?;
     let receipt: Receipt = bincode::deserialize(&data)?;
@@ -217,12 +691,46 @@ fn verify_cmd(
     let image_id = parse_program_id(program_id)?;
     verify_receipt(&receipt, image_id)?;
 
+    if let Some(archive) = archive {
+        let decoded = decode_journal(&receipt.journal)?;
+        let (Some(expected), Some(format)) = (decoded.root_hash, decoded.merkle_format) else {
+            return Err("the receipt commits to no archive root".into());
+        };
+        let options = BuildOptions::default().with_merkle_format(format);
+        let root = archive_root(archive, gitignore, options)?;
+        if root != expected {
+            return Err(format!(
+                "'{}' has root {}, but the receipt commits to {}",
+                archive.display(),
+                hex::encode(root),
+                hex::encode(expected)
+            )
+            .into());
+        }
+    }
+
     if print_journal {
         let decoded: DecodedJournal = decode_journal(&receipt.journal)?;
         let output = serde_json::json!({
-            "root_hash": hex::encode(decoded.root_hash),
+            "root_hash": decoded.root_hash.map(hex::encode),
+            "merkle_format": decoded.merkle_format,
             "license_policy": decoded.license_policy,
             "permitted_dependencies": decoded.permitted_deps,
+            "member_scope": decoded.member_scope,
+            "feature_selection": decoded.feature_selection,
+            "override_policy": decoded.override_policy,
+            "verify_vendored_sources": decoded.verify_vendored_sources,
+            "link_policy": decoded.link_policy,
+            "package_manager_range": decoded.package_manager_range,
+            "toolchain_policy": decoded.toolchain_policy,
+            "toolchain": decoded.toolchain,
+            "crate_checksum": decoded.crate_checksum.map(hex::encode),
+            "binary_hash": decoded.binary_hash.map(hex::encode),
+            "image": decoded.image.as_ref().map(image_json),
+            "git_commit": decoded.git_commit.as_ref().map(|commit| serde_json::json!({
+                "id": hex::encode(commit.id()),
+                "tree": hex::encode(commit.tree()),
+            })),
         });
         println!("{}", serde_json::to_string_pretty(&output)?);
     } else {
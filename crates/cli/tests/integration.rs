@@ -1,5 +1,5 @@
 use std::{
-    env,
+    env, fs,
     path::{Path, PathBuf},
     process::Command,
 };
@@ -26,6 +26,39 @@ fn run_prove(archive: &Path, metadata: &Path, receipt: &Path) -> std::process::O
         .expect("spawn zk-sca-cli")
 }
 
+fn run_bundle(dir: &Path, output: &Path, gitignore: bool) -> Vec<u8> {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_zk-sca-cli"));
+    cmd.arg("bundle")
+        .arg("--dir")
+        .arg(dir)
+        .arg("--output")
+        .arg(output);
+    if gitignore {
+        cmd.arg("--gitignore");
+    }
+    let out = cmd.output().expect("spawn zk-sca-cli");
+    assert!(
+        out.status.success(),
+        "bundle failed\nstderr: {}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    fs::read(output).expect("read bundle")
+}
+
+/// A fresh copy of a fixture source directory, with new timestamps.
+fn copy_dir(from: &Path, to: &Path) {
+    fs::create_dir_all(to).unwrap();
+    for entry in fs::read_dir(from).unwrap() {
+        let entry = entry.unwrap();
+        let target = to.join(entry.file_name());
+        if entry.file_type().unwrap().is_dir() {
+            copy_dir(&entry.path(), &target);
+        } else {
+            fs::copy(entry.path(), target).unwrap();
+        }
+    }
+}
+
 #[test]
 fn safe_archive_succeeds() {
     let fx = fixtures();
@@ -87,3 +120,108 @@ fn safe_archive_only_allow_apache2_fails() {
         String::from_utf8_lossy(&out.stderr)
     );
 }
+
+#[test]
+fn bundle_is_reproducible() {
+    let source = fixtures().join("../fixtures_sources/safe");
+    let scratch = env::temp_dir().join(format!("zk-sca-bundle-{}", std::process::id()));
+    let copy = scratch.join("copy");
+    copy_dir(&source, &copy);
+
+    let original = run_bundle(&source, &scratch.join("original.tar.gz"), false);
+    let copied = run_bundle(&copy, &scratch.join("copied.tar.gz"), false);
+    fs::remove_dir_all(&scratch).unwrap();
+    assert_eq!(original, copied, "bundles of identical directories differ");
+}
+
+#[test]
+fn bundle_respects_gitignore() {
+    let source = fixtures().join("../fixtures_sources/safe");
+    let scratch = env::temp_dir().join(format!("zk-sca-gitignore-{}", std::process::id()));
+    let clean = scratch.join("clean");
+    let built = scratch.join("built");
+    for dir in [&clean, &built] {
+        copy_dir(&source, dir);
+        fs::write(dir.join(".gitignore"), "/target\n").unwrap();
+    }
+    fs::create_dir_all(built.join("target")).unwrap();
+    fs::write(built.join("target/Cargo.lock"), "ignored").unwrap();
+
+    let clean_bundle = run_bundle(&clean, &scratch.join("clean.tar.gz"), true);
+    let built_bundle = run_bundle(&built, &scratch.join("built.tar.gz"), true);
+    let unfiltered = run_bundle(&built, &scratch.join("unfiltered.tar.gz"), false);
+    fs::remove_dir_all(&scratch).unwrap();
+    assert_eq!(clean_bundle, built_bundle, "ignored files were bundled");
+    assert_ne!(built_bundle, unfiltered, "--gitignore had no effect");
+}
+
+/// The root that `valid-receipt.bin` commits to, that of `safe.tar.gz`.
+const SAFE_ROOT: &str = "9d2cedfa0abfd69a31863b7152460f1c51fa240bf86d374cb6b99d9168a18ecb";
+const VALID_RECEIPT_PROGRAM_ID: &str =
+    "41d8e8bc920aec3d54c9e0179ab3ea7ba374428e3f2987a691f75f9019a51613";
+
+fn run_verify_archive(archive: &Path) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_zk-sca-cli"))
+        .arg("verify")
+        .arg("-r")
+        .arg(fixtures().join("valid-receipt.bin"))
+        .arg("-i")
+        .arg(VALID_RECEIPT_PROGRAM_ID)
+        .arg("--archive")
+        .arg(archive)
+        .output()
+        .expect("spawn zk-sca-cli")
+}
+
+#[test]
+fn hash_prints_archive_root() {
+    let out = Command::new(env!("CARGO_BIN_EXE_zk-sca-cli"))
+        .arg("hash")
+        .arg("-a")
+        .arg(fixtures().join("safe.tar.gz"))
+        .output()
+        .expect("spawn zk-sca-cli");
+    assert!(out.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&out.stdout).trim(),
+        format!("Root hash: {SAFE_ROOT}")
+    );
+}
+
+#[test]
+fn hash_with_parallel_hashing_prints_same_root() {
+    let out = Command::new(env!("CARGO_BIN_EXE_zk-sca-cli"))
+        .arg("hash")
+        .arg("-a")
+        .arg(fixtures().join("safe.tar.gz"))
+        .arg("--parallel-hashing")
+        .output()
+        .expect("spawn zk-sca-cli");
+    assert!(out.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&out.stdout).trim(),
+        format!("Root hash: {SAFE_ROOT}")
+    );
+}
+
+#[test]
+fn verify_accepts_committed_archive() {
+    let out = run_verify_archive(&fixtures().join("safe.tar.gz"));
+    assert!(
+        out.status.success(),
+        "expected success but got {}\nstderr: {}",
+        out.status,
+        String::from_utf8_lossy(&out.stderr)
+    );
+}
+
+#[test]
+fn verify_rejects_other_archive() {
+    let out = run_verify_archive(&fixtures().join("vuln.tar.gz"));
+    assert!(
+        !out.status.success(),
+        "expected failure but CLI returned success!\nstdout: {}",
+        String::from_utf8_lossy(&out.stdout)
+    );
+    assert!(String::from_utf8_lossy(&out.stderr).contains(SAFE_ROOT));
+}
@@ -0,0 +1,105 @@
+use crate::merkle_builder::BuildError;
+use flate2::{Compression, GzBuilder};
+use ignore::WalkBuilder;
+use std::{
+    fs,
+    io::{Error as IoError, Write},
+    path::Path,
+};
+use tar::{Builder, EntryType, Header};
+
+/// How [`bundle_directory`] selects the files of a directory.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BundleOptions {
+    /// Leave out what the directory's `.gitignore` files ignore. Global and
+    /// per-repository excludes are not read, as they differ between machines.
+    pub gitignore: bool,
+}
+
+impl BundleOptions {
+    #[must_use]
+    pub const fn with_gitignore(mut self, enabled: bool) -> Self {
+        self.gitignore = enabled;
+        self
+    }
+}
+
+/// Packs a directory into a canonical gzipped USTAR archive, so that any
+/// copy of the same files yields the same bytes and Merkle root.
+///
+/// * Entries are the directory's regular files and symbolic links, named by
+///   their UTF-8 path relative to it and sorted by that name. Directories
+///   get no entries of their own, and `.git` is always left out.
+/// * Metadata is normalized: files have mode `0644`, or `0755` if any
+///   execute bit is set, and every entry has owner `0:0`, no owner names and
+///   modification time `0`.
+/// * The gzip header carries no name or time.
+pub fn bundle_directory(dir: &Path, options: BundleOptions) -> Result<Vec<u8>, BuildError> {
+    let mut paths = Vec::new();
+    let walker = WalkBuilder::new(dir)
+        .standard_filters(false)
+        .git_ignore(options.gitignore)
+        .require_git(false)
+        .filter_entry(|entry| entry.file_name() != ".git")
+        .build();
+    for entry in walker {
+        let entry = entry.map_err(IoError::other)?;
+        if entry.file_type().is_some_and(|t| t.is_dir()) {
+            continue;
+        }
+        let relative = entry
+            .path()
+            .strip_prefix(dir)
+            .map_err(IoError::other)?
+            .to_str()
+            .ok_or_else(|| BuildError::UnsupportedPath(entry.path().display().to_string()))?
+            .replace(std::path::MAIN_SEPARATOR, "/");
+        paths.push((relative, entry.into_path()));
+    }
+    paths.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+
+    let mut builder = Builder::new(Vec::new());
+    for (name, path) in paths {
+        let metadata = fs::symlink_metadata(&path)?;
+        let mut header = Header::new_ustar();
+        header.set_uid(0);
+        header.set_gid(0);
+        header.set_mtime(0);
+        if metadata.file_type().is_symlink() {
+            header.set_entry_type(EntryType::Symlink);
+            header.set_mode(0o777);
+            header.set_size(0);
+            builder.append_link(&mut header, &name, fs::read_link(&path)?)?;
+        } else if metadata.is_file() {
+            header.set_entry_type(EntryType::Regular);
+            header.set_mode(if is_executable(&metadata) {
+                0o755
+            } else {
+                0o644
+            });
+            header.set_size(metadata.len());
+            builder.append_data(&mut header, &name, fs::File::open(&path)?)?;
+        } else {
+            return Err(BuildError::UnsupportedPath(name));
+        }
+    }
+    let tar = builder.into_inner()?;
+
+    let mut encoder = GzBuilder::new()
+        .mtime(0)
+        .write(Vec::new(), Compression::default());
+    encoder.write_all(&tar)?;
+    Ok(encoder.finish()?)
+}
+
+#[cfg(unix)]
+fn is_executable(metadata: &fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode() & 0o111 != 0
+}
+
+#[cfg(not(unix))]
+const fn is_executable(_metadata: &fs::Metadata) -> bool {
+    false
+}
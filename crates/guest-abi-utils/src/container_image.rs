@@ -0,0 +1,119 @@
+use crate::{merkle_builder::BuildError, to_hex};
+use flate2::read::GzDecoder;
+use risc0_zkvm::sha::{Impl, Sha256};
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    io::{Cursor, Read},
+};
+use tar::Archive;
+use zk_sca_guest_abi::ContainerImage;
+
+const INDEX_MEDIA_TYPES: [&str; 2] = [
+    "application/vnd.oci.image.index.v1+json",
+    "application/vnd.docker.distribution.manifest.list.v2+json",
+];
+
+fn unsupported(detail: impl Into<String>) -> BuildError {
+    BuildError::UnsupportedImage(detail.into())
+}
+
+/// Reads the one image of an OCI image layout packed as a TAR, optionally
+/// gzip-compressed, as Docker 25 and later write with `docker save`.
+///
+/// Every blob read must match its digest. Layers are decompressed if
+/// gzip-compressed; zstd layers are not supported.
+pub fn read_container_image(bundle: &[u8]) -> Result<ContainerImage, BuildError> {
+    let mut data = Vec::new();
+    if bundle.starts_with(&[0x1f, 0x8b]) {
+        GzDecoder::new(bundle).read_to_end(&mut data)?;
+    } else {
+        data.extend_from_slice(bundle);
+    }
+
+    let mut files: HashMap<String, Vec<u8>> = HashMap::new();
+    for entry in Archive::new(Cursor::new(data)).entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let path = entry.path()?.to_string_lossy().into_owned();
+        let mut bytes = Vec::new();
+        entry.read_to_end(&mut bytes)?;
+        files.insert(path.trim_start_matches("./").to_owned(), bytes);
+    }
+
+    let index = files.get("index.json").ok_or_else(|| {
+        unsupported("no `index.json`; is it an OCI image layout, as Docker 25 and later save?")
+    })?;
+    let index = parse_json(index, "index.json")?;
+    let manifests = index["manifests"].as_array().map_or(&[][..], Vec::as_slice);
+    let [descriptor] = manifests else {
+        return Err(unsupported(format!(
+            "`index.json` lists {} images; save exactly one",
+            manifests.len()
+        )));
+    };
+    if let Some(media_type) = descriptor["mediaType"]
+        .as_str()
+        .filter(|t| INDEX_MEDIA_TYPES.contains(t))
+    {
+        return Err(unsupported(format!(
+            "`index.json` names a multi-platform index (`{media_type}`); save a single platform"
+        )));
+    }
+
+    let manifest = read_blob(&files, &descriptor["digest"])?;
+    let parsed = parse_json(&manifest, "the image manifest")?;
+    let config = read_blob(&files, &parsed["config"]["digest"])?;
+    let layers = parsed["layers"]
+        .as_array()
+        .ok_or_else(|| unsupported("the image manifest lists no layers"))?
+        .iter()
+        .map(|layer| {
+            let blob = read_blob(&files, &layer["digest"])?;
+            let media_type = layer["mediaType"].as_str().unwrap_or_default();
+            if media_type.ends_with("+zstd") {
+                return Err(unsupported(format!(
+                    "layer media type `{media_type}` is not supported"
+                )));
+            }
+            if media_type.ends_with("+gzip") || media_type.ends_with(".tar.gzip") {
+                let mut tar = Vec::new();
+                GzDecoder::new(blob.as_slice()).read_to_end(&mut tar)?;
+                Ok(tar)
+            } else {
+                Ok(blob)
+            }
+        })
+        .collect::<Result<_, BuildError>>()?;
+
+    Ok(ContainerImage {
+        manifest,
+        config,
+        layers,
+    })
+}
+
+fn parse_json(bytes: &[u8], what: &str) -> Result<Value, BuildError> {
+    serde_json::from_slice(bytes).map_err(|e| unsupported(format!("malformed {what}: {e}")))
+}
+
+/// The blob that `digest` names, checked against it.
+fn read_blob(files: &HashMap<String, Vec<u8>>, digest: &Value) -> Result<Vec<u8>, BuildError> {
+    let digest = digest
+        .as_str()
+        .ok_or_else(|| unsupported("descriptor has no digest"))?;
+    let hex = digest
+        .strip_prefix("sha256:")
+        .ok_or_else(|| unsupported(format!("unsupported digest `{digest}`")))?;
+    let blob = files
+        .get(&format!("blobs/sha256/{hex}"))
+        .ok_or_else(|| unsupported(format!("blob `{digest}` is missing")))?;
+    if to_hex(Impl::hash_bytes(blob).as_bytes()) != hex {
+        return Err(unsupported(format!(
+            "blob `{digest}` does not match its digest"
+        )));
+    }
+    Ok(blob.clone())
+}
@@ -0,0 +1,15 @@
+/// Returns `true` for the files that decide how Cargo resolves dependencies.
+///
+/// These are every `Cargo.toml`, every `Cargo.lock`, every `.cargo/config.toml`
+/// (or its legacy extension-less `.cargo/config`), and every
+/// `rust-toolchain.toml` (or legacy `rust-toolchain`), which pins the Cargo
+/// release that reads them.
+#[must_use]
+pub fn is_cargo_dependency_file(path: &str) -> bool {
+    let mut components = path.rsplit('/');
+    match components.next() {
+        Some("Cargo.toml" | "Cargo.lock" | "rust-toolchain.toml" | "rust-toolchain") => true,
+        Some("config.toml" | "config") => components.next() == Some(".cargo"),
+        _ => false,
+    }
+}
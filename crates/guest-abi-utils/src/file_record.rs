@@ -0,0 +1,82 @@
+use crate::{ArchivePath, TarEntryKind, TarHeader};
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::str;
+
+/// Encodes `header` as the header of a file record.
+///
+/// The encoding is the entry's typeflag, its size and the length of its
+/// normalized name as little-endian `u64`s, the name, and for links the link
+/// name as stored. Entries that PAX or GNU long-name extensions describe
+/// encode the same as those that need none, so a record commits to the
+/// entry, not to how the archive spelled it.
+#[must_use]
+pub fn encode_file_header(header: &TarHeader) -> Vec<u8> {
+    let name = header.name.as_str();
+    let link_name = header.link_name.as_deref().unwrap_or_default();
+    let mut bytes = Vec::with_capacity(17 + name.len() + link_name.len());
+    bytes.push(match header.kind {
+        TarEntryKind::File => b'0',
+        TarEntryKind::HardLink => b'1',
+        TarEntryKind::Symlink => b'2',
+        TarEntryKind::Directory => b'5',
+    });
+    bytes.extend_from_slice(&(header.size as u64).to_le_bytes());
+    bytes.extend_from_slice(&(name.len() as u64).to_le_bytes());
+    bytes.extend_from_slice(name.as_bytes());
+    bytes.extend_from_slice(link_name.as_bytes());
+    bytes
+}
+
+/// Decodes the header of a file record, which must be in the form that
+/// [`encode_file_header`] gives it.
+pub fn decode_file_header(bytes: &[u8]) -> Result<TarHeader, String> {
+    let truncated = || "truncated file record header".to_string();
+    let (&typeflag, rest) = bytes.split_first().ok_or_else(truncated)?;
+    let (size, rest) = rest.split_first_chunk::<8>().ok_or_else(truncated)?;
+    let (name_len, rest) = rest.split_first_chunk::<8>().ok_or_else(truncated)?;
+    let name_len = usize::try_from(u64::from_le_bytes(*name_len)).map_err(|_| truncated())?;
+    if rest.len() < name_len {
+        return Err(truncated());
+    }
+    let (name, link_name) = rest.split_at(name_len);
+
+    let kind = match typeflag {
+        b'0' => TarEntryKind::File,
+        b'1' => TarEntryKind::HardLink,
+        b'2' => TarEntryKind::Symlink,
+        b'5' => TarEntryKind::Directory,
+        other => return Err(format!("unsupported typeflag `{}`", other.escape_ascii())),
+    };
+    let size = usize::try_from(u64::from_le_bytes(*size))
+        .map_err(|_| "file record size overflows".to_string())?;
+    let name = str::from_utf8(name).map_err(|_| "file record name is not UTF-8".to_string())?;
+    let path = ArchivePath::parse(name)?;
+    if path.as_str() != name {
+        return Err(format!("`{name}` is not normalized"));
+    }
+    let link_name =
+        str::from_utf8(link_name).map_err(|_| format!("link name of `{name}` is not UTF-8"))?;
+    if kind != TarEntryKind::File && size != 0 {
+        return Err(format!(
+            "`{name}` declares {size} bytes but carries no data"
+        ));
+    }
+    let link_name = match kind {
+        TarEntryKind::HardLink | TarEntryKind::Symlink => Some(link_name.to_string()),
+        TarEntryKind::File | TarEntryKind::Directory if link_name.is_empty() => None,
+        TarEntryKind::File | TarEntryKind::Directory => {
+            return Err(format!("`{name}` is not a link but names `{link_name}`"));
+        }
+    };
+
+    Ok(TarHeader {
+        name: path,
+        size,
+        kind,
+        link_name,
+    })
+}
@@ -0,0 +1,83 @@
+use alloc::{format, string::String, vec::Vec};
+use core::str;
+
+/// What the mode of a Git tree entry makes it.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum GitEntryKind {
+    Tree,
+    File,
+    Symlink,
+    Submodule,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct GitTreeEntry<'a> {
+    pub kind: GitEntryKind,
+    pub name: &'a str,
+    pub id: [u8; 20],
+}
+
+/// Parses the `<mode> <name>\0<id>` entries of a Git tree object.
+///
+/// Names must be UTF-8 and a single path component other than `.` and `..`.
+pub fn parse_git_tree(body: &[u8]) -> Result<Vec<GitTreeEntry<'_>>, String> {
+    let mut entries = Vec::new();
+    let mut rest = body;
+    while !rest.is_empty() {
+        let space = rest
+            .iter()
+            .position(|&b| b == b' ')
+            .ok_or("tree entry has no mode")?;
+        let kind = match &rest[..space] {
+            b"40000" => GitEntryKind::Tree,
+            b"100644" | b"100755" | b"100664" => GitEntryKind::File,
+            b"120000" => GitEntryKind::Symlink,
+            b"160000" => GitEntryKind::Submodule,
+            mode => {
+                return Err(format!(
+                    "tree entry has unknown mode `{}`",
+                    String::from_utf8_lossy(mode)
+                ));
+            }
+        };
+        rest = &rest[space + 1..];
+        let nul = rest
+            .iter()
+            .position(|&b| b == 0)
+            .ok_or("tree entry name is not terminated")?;
+        let name = str::from_utf8(&rest[..nul]).map_err(|_| "tree entry name is not UTF-8")?;
+        if name.is_empty() || name == "." || name == ".." || name.contains('/') {
+            return Err(format!("tree entry name `{name}` is not a path component"));
+        }
+        let id = rest
+            .get(nul + 1..nul + 21)
+            .and_then(|id| id.try_into().ok())
+            .ok_or("tree entry is truncated")?;
+        entries.push(GitTreeEntry { kind, name, id });
+        rest = &rest[nul + 21..];
+    }
+    Ok(entries)
+}
+
+/// The root tree a Git commit object names on its first line.
+#[must_use]
+pub fn parse_git_commit_tree(body: &[u8]) -> Option<[u8; 20]> {
+    let line = body.strip_prefix(b"tree ")?.get(..41)?;
+    if line[40] != b'\n' {
+        return None;
+    }
+    let mut id = [0u8; 20];
+    for (byte, pair) in id.iter_mut().zip(line[..40].chunks(2)) {
+        *byte = (nibble(pair[0])? << 4) | nibble(pair[1])?;
+    }
+    Some(id)
+}
+
+/// Git writes object IDs in lowercase hex.
+const fn nibble(digit: u8) -> Option<u8> {
+    match digit {
+        b'0'..=b'9' => Some(digit - b'0'),
+        b'a'..=b'f' => Some(digit - b'a' + 10),
+        _ => None,
+    }
+}
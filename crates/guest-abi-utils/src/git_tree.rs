@@ -0,0 +1,139 @@
+use crate::merkle_builder::BuildError;
+use crate::{
+    ArchivePath, GitEntryKind, TarEntryKind, TarHeader, is_cargo_dependency_file,
+    parse_git_commit_tree, parse_git_tree, resolve_link, to_hex,
+};
+use std::collections::{HashMap, HashSet};
+use zk_sca_guest_abi::GitTree;
+
+fn invalid(detail: impl Into<String>) -> BuildError {
+    BuildError::InvalidGitObjects(detail.into())
+}
+
+/// Reads the objects of a Git commit as `git cat-file --batch` prints them,
+/// the commit first.
+///
+/// Keeps every tree reachable from the commit, the blob of every Cargo
+/// dependency file and symbolic link in them, and the blobs of the files that
+/// dependency files stored as symbolic links lead to. Objects are not checked
+/// against their IDs here; the guest does that.
+pub fn read_git_tree(bundle: &[u8]) -> Result<GitTree, BuildError> {
+    let mut objects: HashMap<&str, (&str, &[u8])> = HashMap::new();
+    let mut commit = None;
+    let mut rest = bundle;
+    while !rest.is_empty() {
+        let newline = rest
+            .iter()
+            .position(|&b| b == b'\n')
+            .ok_or_else(|| invalid("object header is not terminated"))?;
+        let header = std::str::from_utf8(&rest[..newline])
+            .map_err(|_| invalid("object header is not UTF-8"))?;
+        let [id, kind, size] = header.split(' ').collect::<Vec<_>>()[..] else {
+            return Err(invalid(format!("unexpected object header `{header}`")));
+        };
+        let size: usize = size
+            .parse()
+            .map_err(|_| invalid(format!("unexpected object header `{header}`")))?;
+        let body = rest
+            .get(newline + 1..newline + 1 + size)
+            .ok_or_else(|| invalid(format!("object `{id}` is truncated")))?;
+        if rest.get(newline + 1 + size) != Some(&b'\n') {
+            return Err(invalid(format!("object `{id}` is not terminated")));
+        }
+        rest = &rest[newline + size + 2..];
+
+        if commit.is_none() {
+            if kind != "commit" {
+                return Err(invalid("the first object is not a commit"));
+            }
+            commit = Some(body);
+        }
+        objects.insert(id, (kind, body));
+    }
+    let commit = commit.ok_or_else(|| invalid("no objects"))?;
+
+    let root =
+        parse_git_commit_tree(commit).ok_or_else(|| invalid("the commit does not name a tree"))?;
+    let mut trees = Vec::new();
+    let mut blobs = Vec::new();
+    let mut seen = HashSet::new();
+    let mut add_blob = |id: &[u8; 20]| -> Result<&[u8], BuildError> {
+        let body = object(&objects, id, "blob")?;
+        if seen.insert(*id) {
+            blobs.push(body.to_vec());
+        }
+        Ok(body)
+    };
+    let mut headers = Vec::new();
+    let mut ids = HashMap::new();
+    let mut seen_trees = HashSet::new();
+    let mut pending = vec![(root, String::new())];
+    while let Some((id, dir)) = pending.pop() {
+        if !seen_trees.insert(id) {
+            continue;
+        }
+        let tree = object(&objects, &id, "tree")?;
+        trees.push(tree.to_vec());
+        for entry in parse_git_tree(tree).map_err(invalid)? {
+            let path = format!("{dir}{}", entry.name);
+            let (kind, link_name) = match entry.kind {
+                GitEntryKind::Tree => {
+                    pending.push((entry.id, path + "/"));
+                    continue;
+                }
+                GitEntryKind::File => {
+                    if is_cargo_dependency_file(&path) {
+                        add_blob(&entry.id)?;
+                    }
+                    (TarEntryKind::File, None)
+                }
+                GitEntryKind::Symlink => {
+                    let target = std::str::from_utf8(add_blob(&entry.id)?)
+                        .map_err(|_| invalid(format!("`{path}` links to a non-UTF-8 name")))?;
+                    (TarEntryKind::Symlink, Some(target.to_owned()))
+                }
+                GitEntryKind::Submodule => continue,
+            };
+            let name = ArchivePath::parse(&path).map_err(invalid)?;
+            ids.insert(name.clone(), entry.id);
+            headers.push(TarHeader {
+                name,
+                size: 0,
+                kind,
+                link_name,
+            });
+        }
+    }
+
+    let entries: HashMap<&ArchivePath, &TarHeader> = headers
+        .iter()
+        .map(|header| (&header.name, header))
+        .collect();
+    for header in &headers {
+        if header.kind == TarEntryKind::Symlink && is_cargo_dependency_file(&header.name) {
+            // Links that lead nowhere are left for the guest to reject.
+            if let Ok(target) = resolve_link(header, |path| entries.get(path).copied()) {
+                add_blob(&ids[&target.name])?;
+            }
+        }
+    }
+
+    Ok(GitTree {
+        commit: commit.to_vec(),
+        trees,
+        blobs,
+    })
+}
+
+fn object<'a>(
+    objects: &HashMap<&str, (&str, &'a [u8])>,
+    id: &[u8; 20],
+    kind: &str,
+) -> Result<&'a [u8], BuildError> {
+    let hex = to_hex(id);
+    match objects.get(hex.as_str()) {
+        Some(&(found, body)) if found == kind => Ok(body),
+        Some(&(found, _)) => Err(invalid(format!("`{hex}` is a {found}, not a {kind}"))),
+        None => Err(invalid(format!("{kind} `{hex}` is missing"))),
+    }
+}
@@ -7,13 +7,67 @@
 
 extern crate alloc;
 
+mod dependency_files;
+pub use dependency_files::is_cargo_dependency_file;
+
+mod git_object;
+pub use git_object::{GitEntryKind, GitTreeEntry, parse_git_commit_tree, parse_git_tree};
+
+mod hex;
+pub use hex::to_hex;
+
+mod path;
+pub use path::ArchivePath;
+
 mod tar;
-pub use tar::{TarHeader, block_count, parse_tar_header};
+pub use tar::{
+    RawTarMember, TarEntryKind, TarExtensionKind, TarExtensions, TarHeader, TarMember, block_count,
+    parse_pax_records, parse_tar_header, parse_tar_size, read_tar_members,
+};
+
+mod merkle_tree;
+pub use merkle_tree::{
+    TreeMetadata, leaf_hash, leaf_index, merkle_layers, node_hash, padding, record_hash, sibling,
+    tree_depth,
+};
+
+mod merkle_stream;
+pub use merkle_stream::StreamingTree;
+
+mod multiproof;
+pub use multiproof::{build_multiproof, multiproof_root};
+
+mod file_record;
+pub use file_record::{decode_file_header, encode_file_header};
+
+mod merkle_root;
+pub use merkle_root::tar_merkle_root;
 
 mod merkle_verifier;
 pub use merkle_verifier::{ValidPartialArchive, ValidatedFile, validate_merkle_archive};
 
+mod links;
+pub use links::{apply_link_policy, link_target, resolve_link};
+
+#[cfg(feature = "std")]
+mod bundle;
+#[cfg(feature = "std")]
+pub use bundle::{BundleOptions, bundle_directory};
+
+#[cfg(feature = "std")]
+mod container_image;
+#[cfg(feature = "std")]
+pub use container_image::read_container_image;
+
+#[cfg(feature = "std")]
+mod git_tree;
+#[cfg(feature = "std")]
+pub use git_tree::read_git_tree;
+
 #[cfg(feature = "std")]
 mod merkle_builder;
 #[cfg(feature = "std")]
-pub use merkle_builder::build_merkle_archive;
+pub use merkle_builder::{
+    BuildOptions, build_merkle_archive, build_merkle_archive_from_reader,
+    build_merkle_archive_with, compute_root, compute_root_from_reader,
+};
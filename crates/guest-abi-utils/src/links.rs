@@ -0,0 +1,131 @@
+use crate::{ArchivePath, TarEntryKind, TarHeader, ValidPartialArchive, ValidatedFile};
+use alloc::{format, string::String, vec::Vec};
+use hashbrown::HashMap;
+use zk_sca_guest_abi::{LinkPolicy, ScaError};
+
+/// Most links followed to resolve one path, as Linux's `MAXSYMLINKS`.
+const MAX_LINKS: usize = 40;
+
+/// The archive path that a link entry names: a symbolic link's target is
+/// resolved against the directory holding the link, and a hard link's
+/// against the archive root. `None` if `header` names nothing.
+pub fn link_target(header: &TarHeader) -> Result<Option<ArchivePath>, String> {
+    let Some(link_name) = &header.link_name else {
+        return Ok(None);
+    };
+    let target = if header.kind == TarEntryKind::Symlink {
+        header.name.parent().join(link_name)
+    } else {
+        ArchivePath::parse(link_name)
+    };
+    target
+        .map(Some)
+        .map_err(|e| format!("link `{}` leaves the archive: {e}", header.name))
+}
+
+/// Follows `header` through links to the regular file it leads to, looking
+/// up the entry each link names with `lookup`.
+pub fn resolve_link<'h>(
+    header: &'h TarHeader,
+    lookup: impl Fn(&ArchivePath) -> Option<&'h TarHeader>,
+) -> Result<&'h TarHeader, String> {
+    let start = &header.name;
+    let mut current = header;
+    for _ in 0..=MAX_LINKS {
+        let Some(target) = link_target(current)? else {
+            if current.kind == TarEntryKind::File {
+                return Ok(current);
+            }
+            return Err(format!(
+                "`{start}` leads to `{}`, which is not a regular file",
+                current.name
+            ));
+        };
+        current = lookup(&target)
+            .ok_or_else(|| format!("`{start}` leads to `{target}`, which is not in the archive"))?;
+    }
+    Err(format!(
+        "`{start}` goes through more than {MAX_LINKS} links"
+    ))
+}
+
+/// Holds the materialized files of `archive` to `policy`.
+///
+/// A file beneath a symbolic link is always rejected. A file that is itself
+/// a link is rejected, or under [`LinkPolicy::Resolve`] replaced by the
+/// regular file it leads to, which must be materialized as well.
+pub fn apply_link_policy(
+    mut archive: ValidPartialArchive,
+    policy: LinkPolicy,
+) -> Result<ValidPartialArchive, (ScaError, String)> {
+    let replacements = resolve_files(&archive, policy)?;
+    for (index, file) in replacements {
+        archive.files[index] = file;
+    }
+    Ok(archive)
+}
+
+/// The files of `archive` to replace under `policy`, by position.
+fn resolve_files(
+    archive: &ValidPartialArchive,
+    policy: LinkPolicy,
+) -> Result<Vec<(usize, ValidatedFile)>, (ScaError, String)> {
+    let linked = |detail| (ScaError::LinkedDependencyFile, detail);
+    let entries: HashMap<&ArchivePath, &TarHeader> =
+        archive.headers.iter().map(|h| (&h.name, h)).collect();
+    let contents: HashMap<&ArchivePath, &[u8]> = archive
+        .files
+        .iter()
+        .map(|vf| (&vf.header.name, vf.bytes.as_slice()))
+        .collect();
+
+    let mut replacements = Vec::new();
+    for (index, vf) in archive.files.iter().enumerate() {
+        let name = &vf.header.name;
+        let mut dir = name.parent();
+        while !dir.is_root() {
+            if entries
+                .get(&dir)
+                .is_some_and(|h| h.kind == TarEntryKind::Symlink)
+            {
+                return Err(linked(format!(
+                    "`{name}` is beneath the symbolic link `{dir}`"
+                )));
+            }
+            dir = dir.parent();
+        }
+
+        let what = match vf.header.kind {
+            TarEntryKind::HardLink => "hard link",
+            TarEntryKind::Symlink => "symbolic link",
+            TarEntryKind::File | TarEntryKind::Directory => continue,
+        };
+        if !policy.resolves_links() {
+            return Err(linked(format!(
+                "`{name}` is a {what}, which the link policy rejects"
+            )));
+        }
+        let target = resolve_link(&vf.header, |path| entries.get(path).copied()).map_err(linked)?;
+        let bytes = contents.get(&target.name).ok_or_else(|| {
+            (
+                ScaError::InvalidMerkleArchive,
+                format!(
+                    "`{}`, which `{name}` leads to, is not materialized",
+                    target.name
+                ),
+            )
+        })?;
+        let header = TarHeader {
+            name: name.clone(),
+            ..target.clone()
+        };
+        replacements.push((
+            index,
+            ValidatedFile {
+                header,
+                bytes: bytes.to_vec().into(),
+            },
+        ));
+    }
+    Ok(replacements)
+}
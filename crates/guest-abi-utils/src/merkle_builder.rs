@@ -1,200 +1,784 @@
 #![allow(clippy::missing_panics_doc)]
 
+use crate::{
+    ArchivePath, StreamingTree, TarEntryKind, TarExtensions, TarHeader, TarMember, TreeMetadata,
+    block_count, build_multiproof, encode_file_header, is_cargo_dependency_file, leaf_hash,
+    merkle_tree::sha256, record_hash, resolve_link,
+};
 use flate2::read::GzDecoder;
-use risc0_zkvm::sha::{Digest, Impl, Sha256};
-use std::io::{Cursor, Error as IoError, ErrorKind as IoErrorKind, Read};
-use tar::Archive;
+use risc0_zkvm::sha::rust_crypto::{Digest as _, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Error as IoError, ErrorKind, Read};
+use std::{iter, num::NonZeroUsize, thread};
 use thiserror::Error;
 use zk_sca_guest_abi::{
-    MerkleLeaf, MerklePathNode, PackageManager, PartialMerkleArchive, SourceBundle,
+    BundleKind, FileRecord, MerkleFormat, MerkleLeaf, MerkleMultiproof, MerklePathNode,
+    PackageManager, PackageManagerSpec, PartialMerkleArchive, SourceBundle,
 };
 
+/// How many leaf blocks are hashed at a time.
+const BLOCK_BATCH: usize = 4096;
+
+/// How many bytes of entry contents are hashed at a time. Larger entries are
+/// hashed on their own as they are read.
+const CONTENT_BATCH: usize = 16 << 20;
+
 #[derive(Debug, Error)]
 pub enum BuildError {
     #[error("I/O error: {0}")]
     Io(#[from] IoError),
-    #[error("unsupported TAR format: not USTAR")]
-    UnsupportedTarFormat,
+    #[error("invalid TAR archive: {0}")]
+    InvalidTar(String),
     #[error("unsupported package manager")]
     UnsupportedPackageManager,
+    #[error("unsupported entry in .crate package: {0}")]
+    UnsupportedCrateEntry(String),
+    #[error("unsupported container image: {0}")]
+    UnsupportedImage(String),
+    #[error("invalid Git objects: {0}")]
+    InvalidGitObjects(String),
+    #[error("cannot bundle `{0}`: only UTF-8 named files and symbolic links are supported")]
+    UnsupportedPath(String),
+    #[error("invalid entry path: {0}")]
+    InvalidPath(String),
+    #[error("the archive changed while it was read")]
+    ArchiveChanged,
 }
 
-fn tar_err<E: std::fmt::Display>(ctx: &str, err: E) -> IoError {
-    IoError::new(IoErrorKind::InvalidData, format!("{ctx}: {err}"))
+/// Which files besides the dependency files get their data blocks included,
+/// and how the tree is hashed.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BuildOptions {
+    /// Include every file of every directory that holds a `.cargo-checksum.json`,
+    /// as written by `cargo vendor`.
+    pub vendored_sources: bool,
+    pub merkle_format: MerkleFormat,
+    /// Authenticate the included leaves with one multiproof instead of a path
+    /// each.
+    pub multiproof: bool,
+    /// Hash leaves on every available core rather than on the calling thread.
+    pub parallel_hashing: bool,
 }
 
-fn ensure_ustar(data: &[u8]) -> Result<(), BuildError> {
-    let mut archive = Archive::new(Cursor::new(data));
-    let mut entries = archive.entries().map_err(|e| tar_err("TAR error", e))?;
-    let entry = entries
-        .next()
-        .transpose()
-        .map_err(|e| tar_err("TAR entry error", e))?
-        .ok_or_else(|| IoError::new(IoErrorKind::InvalidData, "no entries in archive"))?;
+impl BuildOptions {
+    #[must_use]
+    pub const fn with_vendored_sources(mut self, enabled: bool) -> Self {
+        self.vendored_sources = enabled;
+        self
+    }
 
-    if entry.header().as_ustar().is_some() {
-        Ok(())
-    } else {
-        Err(BuildError::UnsupportedTarFormat)
+    #[must_use]
+    pub const fn with_merkle_format(mut self, format: MerkleFormat) -> Self {
+        self.merkle_format = format;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_multiproof(mut self, enabled: bool) -> Self {
+        self.multiproof = enabled;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_parallel_hashing(mut self, enabled: bool) -> Self {
+        self.parallel_hashing = enabled;
+        self
     }
 }
 
-/// Creates a [`PartialMerkleArchive`] from a gzipped USTAR archive.
+/// The entries among `members`, extension entries left out.
+fn entries(members: &[TarMember]) -> impl Iterator<Item = &TarHeader> {
+    members.iter().filter_map(|member| match member {
+        TarMember::Entry(header) => Some(header),
+        TarMember::Extension { .. } => None,
+    })
+}
+
+/// Lists the directories, with a trailing `/`, that hold a `.cargo-checksum.json`.
+fn find_vendored_dirs(members: &[TarMember]) -> Vec<String> {
+    entries(members)
+        .filter_map(|header| header.name.strip_suffix("/.cargo-checksum.json"))
+        .map(|dir| format!("{dir}/"))
+        .collect()
+}
+
+/// Lists the regular files that wanted links lead to, so that the guest can
+/// read them in place of the links. Links that lead nowhere are left for the
+/// guest to reject.
+fn find_link_targets(
+    members: &[TarMember],
+    want_file: impl Fn(&TarHeader) -> Result<bool, BuildError>,
+) -> Result<HashSet<ArchivePath>, BuildError> {
+    let entries: HashMap<&ArchivePath, &TarHeader> = entries(members)
+        .map(|header| (&header.name, header))
+        .collect();
+    let mut targets = HashSet::new();
+    for header in entries.values() {
+        if header.link_name.is_some() && want_file(header)? {
+            if let Ok(target) = resolve_link(header, |path| entries.get(path).copied()) {
+                targets.insert(target.name.clone());
+            }
+        }
+    }
+    Ok(targets)
+}
+
+/// Creates a [`PartialMerkleArchive`] from a gzipped USTAR, PAX or GNU TAR archive.
 ///
-/// * Decompresses the bytes and verifies the USTAR format.
-/// * Treats each 512-byte block as a leaf; leaf 0 stores the header count.
-/// * Builds a SHA-256 Merkle tree, duplicating the final hash when a level is odd.
+/// * Decompresses the bytes and validates every header as the guest does,
+///   applying PAX extended headers and GNU long names to the entry after them.
+/// * Treats each 512-byte block as a leaf; leaf 0 stores the header count,
+///   the headers of extension entries included, and in the v2 format also
+///   the format version, hash algorithm and block count.
+/// * Builds a SHA-256 Merkle tree in the [`MerkleFormat`] the options name,
+///   v1 by default.
 /// * Returns a partial tree containing only what SCA needs: the count leaf,
-///   every header leaf, and the data-block leaves for manifests and lockfiles.
-#[allow(clippy::too_many_lines)]
+///   every header leaf, the data-block leaves of extension entries, and those
+///   of manifests, lockfiles and Cargo configuration files, or of the files
+///   they lead to if they are links.
+///
+/// In the [`MerkleFormat::FileRecords`] format, each entry is instead one
+/// leaf, its record, and the dependency files are included whole.
 pub fn build_merkle_archive(src_bundle: &SourceBundle) -> Result<PartialMerkleArchive, BuildError> {
-    let mut decoder = GzDecoder::new(src_bundle.tar_gz());
-    let mut data = Vec::new();
-    decoder.read_to_end(&mut data)?;
+    build_merkle_archive_with(src_bundle, BuildOptions::default())
+}
+
+/// Like [`build_merkle_archive`], also including the data blocks that `options` select.
+pub fn build_merkle_archive_with(
+    src_bundle: &SourceBundle,
+    options: BuildOptions,
+) -> Result<PartialMerkleArchive, BuildError> {
+    build_merkle_archive_from_reader(
+        || Ok(src_bundle.tar_gz()),
+        src_bundle.resolved_with(),
+        src_bundle.kind(),
+        options,
+    )
+}
 
-    ensure_ustar(&data)?;
+/// Like [`build_merkle_archive_with`], for the gzipped archive that each
+/// reader `open` returns holds, as a bundle of `kind`.
+///
+/// The archive is read twice, a block at a time: once for its headers, which
+/// decide what is included, and once to hash it. Only what is included is
+/// kept, so memory grows with the number of entries and the size of the
+/// dependency files rather than with the size of the archive.
+pub fn build_merkle_archive_from_reader<R: Read>(
+    mut open: impl FnMut() -> Result<R, IoError>,
+    resolved_with: &PackageManagerSpec,
+    kind: BundleKind,
+    options: BuildOptions,
+) -> Result<PartialMerkleArchive, BuildError> {
+    let members = scan_members(open_tar(&mut open)?)?;
+    check_entries(&members, kind)?;
 
-    let mut archive = Archive::new(Cursor::new(data));
+    let vendored_dirs = if options.vendored_sources {
+        find_vendored_dirs(&members)
+    } else {
+        Vec::new()
+    };
 
-    let want_dep = move |hdr: &tar::Header| {
-        let name = hdr
-            .path()
-            .map(|p| p.to_string_lossy().into_owned())
-            .unwrap_or_default();
-        Ok(match src_bundle.resolved_with().manager() {
+    let want_file = move |hdr: &TarHeader| {
+        Ok(match resolved_with.manager() {
             PackageManager::Cargo => {
-                name == "Cargo.toml"
-                    || name.ends_with("/Cargo.toml")
-                    || name == "Cargo.lock"
-                    || name.ends_with("/Cargo.lock")
+                is_cargo_dependency_file(&hdr.name)
+                    || (hdr.kind == TarEntryKind::File
+                        && vendored_dirs
+                            .iter()
+                            .any(|dir| hdr.name.starts_with(dir.as_str())))
             }
             _ => return Err(BuildError::UnsupportedPackageManager),
         })
     };
+    let link_targets = find_link_targets(&members, &want_file)?;
+    let wanted = entries(&members)
+        .map(|hdr| Ok(link_targets.contains(&hdr.name) || want_file(hdr)?))
+        .collect::<Result<Vec<bool>, BuildError>>()?;
 
-    // Collect raw 512-byte blocks.
-    let mut raw_blocks: Vec<[u8; 512]> = Vec::new();
-    raw_blocks.push([0u8; 512]);
-    let mut header_indices: Vec<usize> = Vec::new();
-    let mut dep_raw_indices: Vec<usize> = Vec::new();
-    let mut dep_header_indices: Vec<usize> = Vec::new();
-
-    for entry_res in archive.entries().map_err(|e| tar_err("TAR error", e))? {
-        let mut entry = entry_res.map_err(|e| tar_err("TAR entry error", e))?;
-        let header = entry.header().clone();
-        let is_dep_hdr = want_dep(&header)?;
-
-        let hdr_raw_idx = raw_blocks.len();
-        raw_blocks.push(*header.as_bytes());
-        header_indices.push(hdr_raw_idx);
-
-        let hdr_leaf_pos = header_indices.len() - 1;
-        if is_dep_hdr {
-            // Record header-leaf position for dependency file.
-            dep_header_indices.push(hdr_leaf_pos);
-        }
-
-        let mut buf = [0u8; 512];
-        loop {
-            let n = entry.read(&mut buf)?;
-            if n == 0 {
-                break;
-            }
-
-            let mut block = buf;
-            if n < 512 {
-                block[n..].fill(0);
-            }
-
-            let data_raw_idx = raw_blocks.len();
-            raw_blocks.push(block);
-
-            // Record every dependency file data-block.
-            if is_dep_hdr {
-                dep_raw_indices.push(data_raw_idx);
-            }
-        }
+    let stream = open_tar(&mut open)?;
+    if options.merkle_format == MerkleFormat::FileRecords {
+        build_file_record_archive(stream, &members, &wanted, resolved_with, options)
+    } else {
+        build_block_archive(stream, &members, &wanted, resolved_with, options)
     }
+}
 
-    // Build count-leaf.
-    let mut count_blk = [0u8; 512];
-    let count_str = header_indices.len().to_string();
-    count_blk[..count_str.len()].copy_from_slice(count_str.as_bytes());
-    raw_blocks[0] = count_blk;
-
-    // Hash all leaves.
-    let leaf_hashes: Vec<[u8; 32]> = raw_blocks
-        .iter()
-        .map(|blk| {
-            let d: Digest = *Impl::hash_bytes(blk);
-            *AsRef::<[u8; 32]>::as_ref(&d)
-        })
+/// Builds the block tree of the archive `stream` reads, whose members are
+/// `members`, with every header and the data blocks of extension entries and
+/// of the entries that are `wanted`.
+fn build_block_archive<R: Read>(
+    stream: TarStream<R>,
+    members: &[TarMember],
+    wanted: &[bool],
+    resolved_with: &PackageManagerSpec,
+    options: BuildOptions,
+) -> Result<PartialMerkleArchive, BuildError> {
+    let format = options.merkle_format;
+    let layout = BlockLayout::new(members, wanted);
+    let mut tracked: Vec<usize> = iter::once(0)
+        .chain(layout.header_indices.iter().copied())
+        .chain(layout.ext_indices.iter().copied())
+        .chain(layout.dep_indices.iter().copied())
         .collect();
+    tracked.sort_unstable();
 
-    // Build Merkle tree layers.
-    let mut layers = vec![leaf_hashes];
-    while layers.last().unwrap().len() > 1 {
-        let prev = layers.last().unwrap();
-        let mut next = Vec::new();
-        for pair in prev.chunks(2) {
-            let left = pair[0];
-            let right = *pair.get(1).unwrap_or(&left); // Duplicate last hash when node count is odd.
-            let mut combined = [0u8; 64];
-            combined[..32].copy_from_slice(&left);
-            combined[32..].copy_from_slice(&right);
-            let pd: Digest = *Impl::hash_bytes(&combined);
-            next.push(*AsRef::<[u8; 32]>::as_ref(&pd));
-        }
-        layers.push(next);
-    }
-    let root_hash = layers.last().unwrap()[0];
-
-    // Generate Merkle proofs for each leaf.
-    let proofs: Vec<Vec<MerklePathNode>> = (0..raw_blocks.len())
-        .map(|mut idx| {
-            let mut path = Vec::new();
-            for level in &layers[..layers.len() - 1] {
-                let is_left = idx % 2 == 0;
-                let sibling = if is_left {
-                    *level.get(idx + 1).unwrap_or(&level[idx])
-                } else {
-                    level[idx - 1]
-                };
-                path.push(MerklePathNode {
-                    sibling_hash: sibling,
-                    is_left_child: is_left,
-                });
-                idx /= 2; // Ascend one level
-            }
-            path
-        })
+    let tree = hash_blocks(
+        stream,
+        members,
+        format,
+        layout.leaf_count,
+        &tracked,
+        options.parallel_hashing,
+    )?;
+    let (paths, multiproof) =
+        prove_leaves(&tracked, tree.paths, layout.leaf_count, options.multiproof);
+    let mut leaves: HashMap<usize, MerkleLeaf> = tracked
+        .into_iter()
+        .zip(tree.blocks.into_iter().zip(paths))
+        .map(|(idx, (data, path))| (idx, MerkleLeaf { data, path }))
         .collect();
+    let mut take = |indices: &[usize]| -> Vec<MerkleLeaf> {
+        indices
+            .iter()
+            .map(|idx| leaves.remove(idx).expect("included leaves are tracked"))
+            .collect()
+    };
+
+    Ok(PartialMerkleArchive {
+        resolved_with: resolved_with.clone(),
+        format,
+        root_hash: tree.root,
+        count_leaf: take(&[0]).remove(0),
+        header_leaves: take(&layout.header_indices),
+        extension_leaves: take(&layout.ext_indices),
+        dependency_file_leaves: take(&layout.dep_indices),
+        dependency_file_header_indices: layout.dep_header_indices,
+        file_records: Vec::new(),
+        dependency_file_contents: Vec::new(),
+        multiproof,
+    })
+}
+
+/// Builds the file-record tree of the archive `stream` reads, whose members
+/// are `members`, with every record and the contents of the entries that are
+/// `wanted`.
+fn build_file_record_archive<R: Read>(
+    stream: TarStream<R>,
+    members: &[TarMember],
+    wanted: &[bool],
+    resolved_with: &PackageManagerSpec,
+    options: BuildOptions,
+) -> Result<PartialMerkleArchive, BuildError> {
+    let format = MerkleFormat::FileRecords;
+    let tree = hash_records(stream, members, Some(wanted), options.parallel_hashing)?;
+    let record_count = tree.records.len();
+    let tracked: Vec<usize> = (0..=record_count).collect();
+    let (paths, multiproof) =
+        prove_leaves(&tracked, tree.paths, record_count + 1, options.multiproof);
+    let mut paths = paths.into_iter();
 
     let count_leaf = MerkleLeaf {
-        data: count_blk,
-        path: proofs[0].clone(),
+        data: TreeMetadata::leaf(format, record_count, record_count + 1),
+        path: paths.next().expect("the metadata leaf is tracked"),
     };
-    let header_leaves = header_indices
+    let file_records = tree
+        .records
         .into_iter()
-        .map(|i| MerkleLeaf {
-            data: raw_blocks[i],
-            path: proofs[i].clone(),
-        })
-        .collect();
-    let dependency_file_leaves = dep_raw_indices
-        .into_iter()
-        .map(|i| MerkleLeaf {
-            data: raw_blocks[i],
-            path: proofs[i].clone(),
+        .zip(paths)
+        .map(|((header, content_hash), path)| FileRecord {
+            header: header.into(),
+            content_hash,
+            path,
         })
         .collect();
+    let dep_header_indices = (0..wanted.len()).filter(|&idx| wanted[idx]).collect();
 
     Ok(PartialMerkleArchive {
-        resolved_with: src_bundle.resolved_with().clone(),
-        root_hash,
+        resolved_with: resolved_with.clone(),
+        format,
+        root_hash: tree.root,
         count_leaf,
-        header_leaves,
-        dependency_file_leaves,
+        header_leaves: Vec::new(),
+        extension_leaves: Vec::new(),
+        dependency_file_leaves: Vec::new(),
         dependency_file_header_indices: dep_header_indices,
+        file_records,
+        dependency_file_contents: tree.contents.into_iter().map(Into::into).collect(),
+        multiproof,
+    })
+}
+
+/// With `multiproof`, trades the `paths` of the leaves at `tracked`, of a
+/// tree of `leaf_count` leaves, for one multiproof of them all.
+fn prove_leaves(
+    tracked: &[usize],
+    paths: Vec<Vec<MerklePathNode>>,
+    leaf_count: usize,
+    multiproof: bool,
+) -> (Vec<Vec<MerklePathNode>>, Option<MerkleMultiproof>) {
+    if !multiproof {
+        return (paths, None);
+    }
+    let leaves: Vec<(usize, &[MerklePathNode])> = tracked
+        .iter()
+        .copied()
+        .zip(paths.iter().map(Vec::as_slice))
+        .collect();
+    let proof = build_multiproof(leaf_count, &leaves);
+    (vec![Vec::new(); tracked.len()], Some(proof))
+}
+
+/// Computes the Merkle root that a receipt for the gzipped tarball `tar_gz`
+/// commits to, as [`build_merkle_archive`] does for a bundle of that `kind`
+/// hashed in `format`.
+///
+/// Anyone holding the tarball can compare this with a journal's `root_hash`.
+pub fn compute_root(
+    tar_gz: &[u8],
+    kind: BundleKind,
+    format: MerkleFormat,
+) -> Result<[u8; 32], BuildError> {
+    let options = BuildOptions::default().with_merkle_format(format);
+    compute_root_from_reader(|| Ok(tar_gz), kind, options)
+}
+
+/// Like [`compute_root`], for the gzipped archive that each reader `open`
+/// returns holds, hashed as `options` say. Only the root is kept.
+pub fn compute_root_from_reader<R: Read>(
+    mut open: impl FnMut() -> Result<R, IoError>,
+    kind: BundleKind,
+    options: BuildOptions,
+) -> Result<[u8; 32], BuildError> {
+    let members = scan_members(open_tar(&mut open)?)?;
+    check_entries(&members, kind)?;
+    let stream = open_tar(&mut open)?;
+    let format = options.merkle_format;
+    if format == MerkleFormat::FileRecords {
+        return Ok(hash_records(stream, &members, None, options.parallel_hashing)?.root);
+    }
+    let leaf_count = BlockLayout::new(&members, &[]).leaf_count;
+    let tree = hash_blocks(
+        stream,
+        &members,
+        format,
+        leaf_count,
+        &[],
+        options.parallel_hashing,
+    )?;
+    Ok(tree.root)
+}
+
+/// Opens the TAR that `open` gives gzipped.
+fn open_tar<R: Read>(
+    open: &mut impl FnMut() -> Result<R, IoError>,
+) -> Result<TarStream<GzDecoder<R>>, BuildError> {
+    Ok(TarStream {
+        reader: GzDecoder::new(open()?),
+        extensions: TarExtensions::default(),
+    })
+}
+
+/// Reads the members of a TAR as [`crate::read_tar_members`] does, a block
+/// at a time.
+struct TarStream<R> {
+    reader: R,
+    extensions: TarExtensions,
+}
+
+impl<R: Read> TarStream<R> {
+    /// The next member and its header block, or `None` past the last. Its
+    /// data must be read with [`Self::read_data`] before the next member.
+    fn next_header(&mut self) -> Result<Option<([u8; 512], TarMember)>, BuildError> {
+        let mut header = [0u8; 512];
+        if read_full(&mut self.reader, &mut header)? < header.len()
+            || header.iter().all(|&b| b == 0)
+        {
+            return Ok(None);
+        }
+        let member = self
+            .extensions
+            .parse_header(&header)
+            .map_err(BuildError::InvalidTar)?;
+        Ok(Some((header, member)))
+    }
+
+    /// Passes the data of `member` to `chunk` up to 512 bytes at a time, and
+    /// skips the padding of its last block. The data of extension entries is
+    /// also applied to the entry after them.
+    fn read_data(
+        &mut self,
+        member: &TarMember,
+        mut chunk: impl FnMut(&[u8]),
+    ) -> Result<(), BuildError> {
+        let mut extension = Vec::new();
+        let mut block = [0u8; 512];
+        let mut remaining = member.size();
+        while remaining > 0 {
+            let len = remaining.min(block.len());
+            if read_full(&mut self.reader, &mut block[..len])? < len {
+                return Err(BuildError::InvalidTar(
+                    "entry runs past the end of the archive".to_owned(),
+                ));
+            }
+            chunk(&block[..len]);
+            if let TarMember::Extension { .. } = member {
+                extension.extend_from_slice(&block[..len]);
+            }
+            remaining -= len;
+        }
+        let padding = block_count(member.size()) * 512 - member.size();
+        read_full(&mut self.reader, &mut block[..padding])?;
+        if let TarMember::Extension { kind, .. } = member {
+            self.extensions
+                .read(*kind, &extension)
+                .map_err(BuildError::InvalidTar)?;
+        }
+        Ok(())
+    }
+
+    /// Rejects extensions left over at the end, and reads the rest of the
+    /// stream so that a corrupt gzip trailer is still reported.
+    fn finish(mut self) -> Result<(), BuildError> {
+        self.extensions.finish().map_err(BuildError::InvalidTar)?;
+        io::copy(&mut self.reader, &mut io::sink())?;
+        Ok(())
+    }
+}
+
+/// Fills as much of `buf` as `reader` holds, returning how much that is.
+fn read_full(reader: &mut impl Read, buf: &mut [u8]) -> Result<usize, IoError> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+/// Reads the members of the TAR that `stream` reads, discarding the data of
+/// entries.
+fn scan_members<R: Read>(mut stream: TarStream<R>) -> Result<Vec<TarMember>, BuildError> {
+    let mut members = Vec::new();
+    while let Some((_, member)) = stream.next_header()? {
+        stream.read_data(&member, |_| {})?;
+        members.push(member);
+    }
+    stream.finish()?;
+    Ok(members)
+}
+
+/// Rejects what the guest would reject of the entries of a bundle of `kind`.
+fn check_entries(members: &[TarMember], kind: BundleKind) -> Result<(), BuildError> {
+    let mut paths = HashSet::new();
+    for header in entries(members) {
+        // The guest unpacks a `.crate` package as Cargo does, which only
+        // extracts files and directories.
+        if kind == BundleKind::CratePackage
+            && !matches!(header.kind, TarEntryKind::File | TarEntryKind::Directory)
+        {
+            return Err(BuildError::UnsupportedCrateEntry(header.name.to_string()));
+        }
+        // The guest rejects entries that normalize to the same path.
+        if !paths.insert(&header.name) {
+            return Err(BuildError::InvalidPath(format!(
+                "`{}` appears twice",
+                header.name
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Where the leaves of a block tree lie: the headers, the data blocks of
+/// extension entries and those of the entries that are wanted.
+struct BlockLayout {
+    header_indices: Vec<usize>,
+    ext_indices: Vec<usize>,
+    dep_indices: Vec<usize>,
+    /// Positions among the headers of those of wanted entries.
+    dep_header_indices: Vec<usize>,
+    leaf_count: usize,
+}
+
+impl BlockLayout {
+    /// Lays out `members` as [`crate::tar_merkle_root`] does, after the
+    /// metadata leaf. An entry is wanted if its place in `wanted` says so.
+    fn new(members: &[TarMember], wanted: &[bool]) -> Self {
+        let mut layout = Self {
+            header_indices: Vec::new(),
+            ext_indices: Vec::new(),
+            dep_indices: Vec::new(),
+            dep_header_indices: Vec::new(),
+            leaf_count: 1,
+        };
+        let mut wanted = wanted.iter();
+        for (header_idx, member) in members.iter().enumerate() {
+            layout.header_indices.push(layout.leaf_count);
+            let data = layout.leaf_count + 1..layout.leaf_count + 1 + block_count(member.size());
+            match member {
+                TarMember::Entry(_) => {
+                    if wanted.next() == Some(&true) {
+                        layout.dep_header_indices.push(header_idx);
+                        layout.dep_indices.extend(data.clone());
+                    }
+                }
+                TarMember::Extension { .. } => layout.ext_indices.extend(data.clone()),
+            }
+            layout.leaf_count = data.end;
+        }
+        layout
+    }
+}
+
+/// A block tree, with the blocks and paths of its tracked leaves.
+struct BlockTree {
+    root: [u8; 32],
+    blocks: Vec<[u8; 512]>,
+    paths: Vec<Vec<MerklePathNode>>,
+}
+
+/// Hashes the blocks of the TAR that `stream` reads, whose members must be
+/// `members`, into a `format` tree of `leaf_count` leaves, keeping the blocks
+/// and paths of the leaves at `tracked`.
+fn hash_blocks<R: Read>(
+    mut stream: TarStream<R>,
+    members: &[TarMember],
+    format: MerkleFormat,
+    leaf_count: usize,
+    tracked: &[usize],
+    parallel: bool,
+) -> Result<BlockTree, BuildError> {
+    let mut sink = BlockSink {
+        format,
+        parallel,
+        tree: StreamingTree::new(format, tracked.to_vec()),
+        tracked,
+        batch: Vec::with_capacity(BLOCK_BATCH),
+        next_idx: 0,
+        kept: Vec::with_capacity(tracked.len()),
+    };
+    sink.push(&TreeMetadata::leaf(format, members.len(), leaf_count));
+    let mut expected = members.iter();
+    while let Some((header, member)) = stream.next_header()? {
+        if expected.next() != Some(&member) {
+            return Err(BuildError::ArchiveChanged);
+        }
+        sink.push(&header);
+        stream.read_data(&member, |chunk| {
+            let mut block = [0u8; 512];
+            block[..chunk.len()].copy_from_slice(chunk);
+            sink.push(&block);
+        })?;
+    }
+    stream.finish()?;
+    sink.flush();
+    if expected.next().is_some() || sink.next_idx != leaf_count {
+        return Err(BuildError::ArchiveChanged);
+    }
+    let (root, paths) = sink.tree.finish().expect("the metadata leaf was pushed");
+    Ok(BlockTree {
+        root,
+        blocks: sink.kept,
+        paths,
+    })
+}
+
+/// Hashes leaf blocks into a tree a batch at a time, keeping those at the
+/// tracked indices.
+struct BlockSink<'a> {
+    format: MerkleFormat,
+    parallel: bool,
+    tree: StreamingTree,
+    /// The tracked indices not yet reached.
+    tracked: &'a [usize],
+    batch: Vec<[u8; 512]>,
+    /// The index of the first block of the batch.
+    next_idx: usize,
+    kept: Vec<[u8; 512]>,
+}
+
+impl BlockSink<'_> {
+    fn push(&mut self, block: &[u8; 512]) {
+        self.batch.push(*block);
+        if self.batch.len() == BLOCK_BATCH {
+            self.flush();
+        }
+    }
+
+    fn flush(&mut self) {
+        let format = self.format;
+        let hashes = hash_all(&self.batch, self.parallel, |block| leaf_hash(format, block));
+        for (block, hash) in self.batch.drain(..).zip(hashes) {
+            if self.tracked.first() == Some(&self.next_idx) {
+                self.kept.push(block);
+                self.tracked = &self.tracked[1..];
+            }
+            self.tree.push(hash);
+            self.next_idx += 1;
+        }
+    }
+}
+
+/// A file-record tree, with the records, their paths and the contents of
+/// the wanted entries when it was hashed for an archive rather than a root.
+struct RecordTree {
+    root: [u8; 32],
+    records: Vec<(Vec<u8>, [u8; 32])>,
+    paths: Vec<Vec<MerklePathNode>>,
+    contents: Vec<Vec<u8>>,
+}
+
+/// Hashes the entries of the TAR that `stream` reads, whose members must be
+/// `members`, into a file-record tree. With `wanted`, every record and path
+/// is kept, and the contents of the entries that are wanted.
+fn hash_records<R: Read>(
+    mut stream: TarStream<R>,
+    members: &[TarMember],
+    wanted: Option<&[bool]>,
+    parallel: bool,
+) -> Result<RecordTree, BuildError> {
+    let format = MerkleFormat::FileRecords;
+    let record_count = entries(members).count();
+    let tracked = if wanted.is_some() {
+        (0..=record_count).collect()
+    } else {
+        Vec::new()
+    };
+    let mut sink = RecordSink {
+        parallel,
+        keep: wanted.is_some(),
+        tree: StreamingTree::new(format, tracked),
+        batch: Vec::new(),
+        batch_bytes: 0,
+        records: Vec::new(),
+    };
+    let metadata = TreeMetadata::leaf(format, record_count, record_count + 1);
+    sink.tree.push(leaf_hash(format, &metadata));
+
+    let mut contents = Vec::new();
+    let mut expected = members.iter();
+    let mut entry_idx = 0;
+    while let Some((_, member)) = stream.next_header()? {
+        if expected.next() != Some(&member) {
+            return Err(BuildError::ArchiveChanged);
+        }
+        let TarMember::Entry(header) = &member else {
+            stream.read_data(&member, |_| {})?;
+            continue;
+        };
+        let is_wanted = wanted.is_some_and(|wanted| wanted[entry_idx]);
+        entry_idx += 1;
+        if header.size <= CONTENT_BATCH {
+            let mut data = Vec::with_capacity(header.size);
+            stream.read_data(&member, |chunk| data.extend_from_slice(chunk))?;
+            if is_wanted {
+                contents.push(data.clone());
+            }
+            sink.push(encode_file_header(header), data);
+        } else {
+            let mut hasher = Sha256::new();
+            let mut data = Vec::new();
+            stream.read_data(&member, |chunk| {
+                hasher.update(chunk);
+                if is_wanted {
+                    data.extend_from_slice(chunk);
+                }
+            })?;
+            if is_wanted {
+                contents.push(data);
+            }
+            let mut content_hash = [0u8; 32];
+            content_hash.copy_from_slice(&hasher.finalize());
+            sink.push_hashed(encode_file_header(header), content_hash);
+        }
+    }
+    stream.finish()?;
+    sink.flush();
+    if expected.next().is_some() {
+        return Err(BuildError::ArchiveChanged);
+    }
+    let (root, paths) = sink.tree.finish().expect("the metadata leaf was pushed");
+    Ok(RecordTree {
+        root,
+        records: sink.records,
+        paths,
+        contents,
+    })
+}
+
+/// Hashes the contents of entries a batch at a time, and their records into
+/// a tree.
+struct RecordSink {
+    parallel: bool,
+    /// Whether the records are kept.
+    keep: bool,
+    tree: StreamingTree,
+    /// The encoded headers and contents of entries not yet hashed.
+    batch: Vec<(Vec<u8>, Vec<u8>)>,
+    batch_bytes: usize,
+    records: Vec<(Vec<u8>, [u8; 32])>,
+}
+
+impl RecordSink {
+    fn push(&mut self, header: Vec<u8>, contents: Vec<u8>) {
+        self.batch_bytes += contents.len();
+        self.batch.push((header, contents));
+        if self.batch_bytes >= CONTENT_BATCH {
+            self.flush();
+        }
+    }
+
+    /// Adds the record of an entry whose contents are already hashed.
+    fn push_hashed(&mut self, header: Vec<u8>, content_hash: [u8; 32]) {
+        self.flush();
+        self.add(header, content_hash);
+    }
+
+    fn flush(&mut self) {
+        let hashes = hash_all(&self.batch, self.parallel, |(_, contents)| sha256(contents));
+        let batch = std::mem::take(&mut self.batch);
+        for ((header, _), content_hash) in batch.into_iter().zip(hashes) {
+            self.add(header, content_hash);
+        }
+        self.batch_bytes = 0;
+    }
+
+    fn add(&mut self, header: Vec<u8>, content_hash: [u8; 32]) {
+        self.tree.push(record_hash(&header, &content_hash));
+        if self.keep {
+            self.records.push((header, content_hash));
+        }
+    }
+}
+
+/// Hashes each of `items`, split across the available cores if `parallel`.
+fn hash_all<T: Sync>(
+    items: &[T],
+    parallel: bool,
+    hash: impl Fn(&T) -> [u8; 32] + Sync,
+) -> Vec<[u8; 32]> {
+    let threads = if parallel {
+        thread::available_parallelism().map_or(1, NonZeroUsize::get)
+    } else {
+        1
+    };
+    if threads == 1 || items.len() < 2 {
+        return items.iter().map(hash).collect();
+    }
+    let chunk_len = items.len().div_ceil(threads);
+    thread::scope(|scope| {
+        let workers: Vec<_> = items
+            .chunks(chunk_len)
+            .map(|chunk| scope.spawn(|| chunk.iter().map(&hash).collect::<Vec<_>>()))
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().expect("hashing thread panicked"))
+            .collect()
     })
 }
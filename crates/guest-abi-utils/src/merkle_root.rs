@@ -0,0 +1,67 @@
+use crate::{
+    RawTarMember, TarMember, TreeMetadata, encode_file_header, leaf_hash, merkle_layers,
+    merkle_tree::sha256, read_tar_members, record_hash,
+};
+use alloc::vec::Vec;
+use zk_sca_guest_abi::MerkleFormat;
+
+/// Computes the Merkle root that `build_merkle_archive` commits to for the
+/// uncompressed TAR `tar` when hashing with `format`, or `None` if
+/// [`read_tar_members`] rejects it.
+///
+/// Each member, extension entries included, contributes its header block and
+/// its data blocks, with the bytes past the member's size zeroed. In the
+/// file-record format, each entry instead contributes its record.
+#[must_use]
+pub fn tar_merkle_root(tar: &[u8], format: MerkleFormat) -> Option<[u8; 32]> {
+    let members = read_tar_members(tar).ok()?;
+    if format == MerkleFormat::FileRecords {
+        return file_record_layers(&file_records(&members))
+            .pop()?
+            .first()
+            .copied();
+    }
+    let mut leaves: Vec<[u8; 32]> = Vec::new();
+    leaves.push([0u8; 32]);
+    let header_count = members.len();
+
+    for member in members {
+        leaves.push(leaf_hash(format, member.header));
+        for chunk in member.data.chunks(512) {
+            let mut data = [0u8; 512];
+            data[..chunk.len()].copy_from_slice(chunk);
+            leaves.push(leaf_hash(format, &data));
+        }
+    }
+
+    let count_blk = TreeMetadata::leaf(format, header_count, leaves.len());
+    leaves[0] = leaf_hash(format, &count_blk);
+    merkle_layers(format, leaves).pop()?.first().copied()
+}
+
+/// The record of each entry among `members`: its canonical header and the
+/// SHA-256 of its contents.
+pub fn file_records(members: &[RawTarMember<'_>]) -> Vec<(Vec<u8>, [u8; 32])> {
+    members
+        .iter()
+        .filter_map(|member| match &member.member {
+            TarMember::Entry(header) => Some((encode_file_header(header), sha256(member.data))),
+            TarMember::Extension { .. } => None,
+        })
+        .collect()
+}
+
+/// Every level of the file-record tree over `records`, from the leaf hashes
+/// up to the root.
+pub fn file_record_layers(records: &[(Vec<u8>, [u8; 32])]) -> Vec<Vec<[u8; 32]>> {
+    let format = MerkleFormat::FileRecords;
+    let metadata = TreeMetadata::leaf(format, records.len(), records.len() + 1);
+    let leaves = core::iter::once(leaf_hash(format, &metadata))
+        .chain(
+            records
+                .iter()
+                .map(|(header, content_hash)| record_hash(header, content_hash)),
+        )
+        .collect();
+    merkle_layers(format, leaves)
+}
@@ -0,0 +1,98 @@
+use crate::{node_hash, padding, tree_depth};
+use alloc::{vec, vec::Vec};
+use zk_sca_guest_abi::{MerkleFormat, MerklePathNode};
+
+/// Builds a Merkle tree from its leaf hashes as they arrive, keeping only a
+/// pending node per level and the paths of the leaves it tracks.
+///
+/// Its root and paths are those that [`crate::merkle_layers`] and
+/// [`crate::sibling`] give for the same leaves.
+#[derive(Debug)]
+pub struct StreamingTree {
+    format: MerkleFormat,
+    /// The indices of the leaves whose paths are kept, in ascending order.
+    tracked: Vec<usize>,
+    paths: Vec<Vec<MerklePathNode>>,
+    /// The left node of each level that still waits for its sibling.
+    pending: Vec<Option<[u8; 32]>>,
+    leaf_count: usize,
+}
+
+impl StreamingTree {
+    /// A tree of no leaves yet, keeping the paths of the leaves at `tracked`,
+    /// which must ascend.
+    #[must_use]
+    pub fn new(format: MerkleFormat, tracked: Vec<usize>) -> Self {
+        Self {
+            format,
+            paths: vec![Vec::new(); tracked.len()],
+            tracked,
+            pending: Vec::new(),
+            leaf_count: 0,
+        }
+    }
+
+    /// Appends the leaf hashing to `leaf`.
+    pub fn push(&mut self, leaf: [u8; 32]) {
+        self.push_node(0, self.leaf_count, leaf);
+        self.leaf_count += 1;
+    }
+
+    /// The root, and the path of each tracked leaf in the order of the
+    /// indices given, or `None` if no leaf was pushed.
+    #[must_use]
+    pub fn finish(mut self) -> Option<([u8; 32], Vec<Vec<MerklePathNode>>)> {
+        let depth = tree_depth(self.leaf_count);
+        let mut width = self.leaf_count;
+        for level in 0..depth {
+            // The last node of an odd level is paired with its padding.
+            if let Some(left) = self.pending.get_mut(level).and_then(Option::take) {
+                let right = padding(self.format, left);
+                self.pair(level, width - 1, left, right);
+            }
+            width = width.div_ceil(2);
+        }
+        let root = self.pending.get(depth).copied().flatten()?;
+        Some((root, self.paths))
+    }
+
+    /// Places `hash` as node `index` of `level`, hashing it with its left
+    /// sibling, and that with its own, as far up as they are known.
+    fn push_node(&mut self, level: usize, index: usize, hash: [u8; 32]) {
+        if self.pending.len() <= level {
+            self.pending.resize(level + 1, None);
+        }
+        if index % 2 == 0 {
+            self.pending[level] = Some(hash);
+        } else {
+            let left = self.pending[level]
+                .take()
+                .expect("a right node follows its left sibling");
+            self.pair(level, index - 1, left, hash);
+        }
+    }
+
+    /// Hashes `left`, node `index` of `level`, with `right`, after it, and
+    /// records each as the sibling of the tracked leaves below the other.
+    fn pair(&mut self, level: usize, index: usize, left: [u8; 32], right: [u8; 32]) {
+        let first = index << level;
+        let middle = (index + 1) << level;
+        let end = (index + 2) << level;
+        let start = self.tracked.partition_point(|&leaf| leaf < first);
+        let split = self.tracked.partition_point(|&leaf| leaf < middle);
+        let stop = self.tracked.partition_point(|&leaf| leaf < end);
+        for path in &mut self.paths[start..split] {
+            path.push(MerklePathNode {
+                sibling_hash: right,
+                is_left_child: true,
+            });
+        }
+        for path in &mut self.paths[split..stop] {
+            path.push(MerklePathNode {
+                sibling_hash: left,
+                is_left_child: false,
+            });
+        }
+        self.push_node(level + 1, index / 2, node_hash(self.format, &left, &right));
+    }
+}
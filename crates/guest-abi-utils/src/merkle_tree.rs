@@ -0,0 +1,239 @@
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::str;
+use risc0_zkvm::sha::{BLOCK_BYTES, Block, Impl, SHA256_INIT, Sha256};
+use zk_sca_guest_abi::{MerkleFormat, MerklePathNode};
+
+/// The first bytes of a metadata leaf.
+const METADATA_MAGIC: &[u8; 8] = b"zksca-mt";
+/// The hash algorithm ID a metadata leaf gives for SHA-256.
+const SHA256_ALGORITHM: u32 = 1;
+/// Stands in for the missing right child on an odd level of a v2 or
+/// file-record tree. It is not the hash of any leaf or node.
+const EMPTY_NODE: [u8; 32] = [0; 32];
+
+/// What leaf 0 of a Merkle tree says about the tree.
+///
+/// The metadata leaf of a v2 or file-record tree holds, in little-endian
+/// order, the magic `zksca-mt`, the format version and the hash algorithm as
+/// `u32`s, then the leaf and header counts as `u64`s; its other bytes are
+/// zero. A file-record tree has a header, that is a record, per leaf after it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TreeMetadata {
+    pub header_count: usize,
+    /// How many leaves the tree has, leaf 0 included. `None` for v1 trees,
+    /// whose leaf 0 does not say.
+    pub block_count: Option<usize>,
+}
+
+impl TreeMetadata {
+    /// Leaf 0 of a `format` tree of `block_count` leaves, of which
+    /// `header_count` are headers.
+    #[must_use]
+    pub fn leaf(format: MerkleFormat, header_count: usize, block_count: usize) -> [u8; 512] {
+        let mut block = [0u8; 512];
+        match format {
+            MerkleFormat::V1 => {
+                let count = header_count.to_string();
+                block[..count.len()].copy_from_slice(count.as_bytes());
+            }
+            MerkleFormat::V2 | MerkleFormat::FileRecords => {
+                block[..8].copy_from_slice(METADATA_MAGIC);
+                block[8..12].copy_from_slice(&format.version().to_le_bytes());
+                block[12..16].copy_from_slice(&SHA256_ALGORITHM.to_le_bytes());
+                block[16..24].copy_from_slice(&(block_count as u64).to_le_bytes());
+                block[24..32].copy_from_slice(&(header_count as u64).to_le_bytes());
+            }
+        }
+        block
+    }
+
+    /// Reads leaf 0 of a `format` tree.
+    pub fn parse(format: MerkleFormat, block: &[u8; 512]) -> Result<Self, String> {
+        match format {
+            MerkleFormat::V1 => {
+                let count = str::from_utf8(block)
+                    .map_err(|_| "Invalid UTF-8 in count_leaf".to_string())?
+                    .trim_end_matches('\0');
+                let header_count = count.parse().map_err(|_| "Bad header count")?;
+                Ok(Self {
+                    header_count,
+                    block_count: None,
+                })
+            }
+            MerkleFormat::V2 | MerkleFormat::FileRecords => {
+                let field = |range: core::ops::Range<usize>| -> Result<usize, String> {
+                    let mut bytes = [0u8; 8];
+                    bytes[..range.len()].copy_from_slice(&block[range]);
+                    usize::try_from(u64::from_le_bytes(bytes))
+                        .map_err(|_| "metadata count overflows".to_string())
+                };
+                if &block[..8] != METADATA_MAGIC {
+                    return Err("leaf 0 is not a metadata leaf".to_string());
+                }
+                let version = field(8..12)?;
+                if version != format.version() as usize {
+                    return Err(format!("metadata names format version {version}"));
+                }
+                let algorithm = field(12..16)?;
+                if algorithm != SHA256_ALGORITHM as usize {
+                    return Err(format!("metadata names unknown hash algorithm {algorithm}"));
+                }
+                if block[32..].iter().any(|&b| b != 0) {
+                    return Err("metadata has trailing bytes".to_string());
+                }
+                let (block_count, header_count) = (field(16..24)?, field(24..32)?);
+                if header_count >= block_count {
+                    return Err(format!(
+                        "metadata counts {header_count} headers in {block_count} blocks"
+                    ));
+                }
+                Ok(Self {
+                    header_count,
+                    block_count: Some(block_count),
+                })
+            }
+        }
+    }
+}
+
+/// The hash of a leaf block.
+#[must_use]
+pub fn leaf_hash(format: MerkleFormat, block: &[u8; 512]) -> [u8; 32] {
+    match format {
+        // An aligned block is hashed in place, without a copy.
+        MerkleFormat::V1 => sha256(block),
+        MerkleFormat::V2 | MerkleFormat::FileRecords => {
+            let mut message = Message::new([Block::default(); 9]);
+            message.write(&[0]);
+            message.write(block);
+            message.digest()
+        }
+    }
+}
+
+/// The hash of an interior node from those of its children.
+#[must_use]
+pub fn node_hash(format: MerkleFormat, left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut message = Message::new([Block::default(); 2]);
+    if format != MerkleFormat::V1 {
+        message.write(&[1]);
+    }
+    message.write(left);
+    message.write(right);
+    message.digest()
+}
+
+/// The hash of the leaf of a file record in a file-record tree. Its prefix
+/// differs from a block leaf's, so no record hashes like the metadata leaf.
+#[must_use]
+pub fn record_hash(header: &[u8], content_hash: &[u8; 32]) -> [u8; 32] {
+    let len = 1 + 32 + header.len();
+    let mut message = Message::new(vec![Block::default(); (len + 9).div_ceil(BLOCK_BYTES)]);
+    message.write(&[2]);
+    message.write(content_hash);
+    message.write(header);
+    message.digest()
+}
+
+/// Every level of the tree over the leaf hashes `leaves`, from the leaves up
+/// to the root.
+#[must_use]
+pub fn merkle_layers(format: MerkleFormat, leaves: Vec<[u8; 32]>) -> Vec<Vec<[u8; 32]>> {
+    let mut layers = vec![leaves];
+    while let Some(level) = layers.last().filter(|level| level.len() > 1) {
+        let next = level
+            .chunks(2)
+            .map(|pair| node_hash(format, &pair[0], &sibling(format, pair, 1)))
+            .collect();
+        layers.push(next);
+    }
+    layers
+}
+
+/// The node at `index` of `level`, or if it is past the end of an odd level,
+/// what stands in for it.
+#[must_use]
+pub fn sibling(format: MerkleFormat, level: &[[u8; 32]], index: usize) -> [u8; 32] {
+    level
+        .get(index)
+        .copied()
+        .unwrap_or_else(|| padding(format, level[index - 1]))
+}
+
+/// What stands in for the missing right sibling of `last`, the last node of
+/// an odd level.
+#[must_use]
+pub const fn padding(format: MerkleFormat, last: [u8; 32]) -> [u8; 32] {
+    match format {
+        MerkleFormat::V1 => last,
+        MerkleFormat::V2 | MerkleFormat::FileRecords => EMPTY_NODE,
+    }
+}
+
+/// Reconstruct the zero-based leaf index from a Merkle proof.
+/// Bits are consumed LSB-first; path\[0\] is depth-0.
+#[inline]
+#[must_use]
+pub fn leaf_index(path: &[MerklePathNode]) -> usize {
+    path.iter().enumerate().fold(0usize, |acc, (bit, node)| {
+        acc | (usize::from(!node.is_left_child) << bit)
+    })
+}
+
+/// How many nodes a path from a leaf of a v2 or file-record tree of
+/// `block_count` leaves up to its root has.
+#[must_use]
+pub const fn tree_depth(block_count: usize) -> usize {
+    block_count.next_power_of_two().trailing_zeros() as usize
+}
+
+pub fn sha256(bytes: &[u8]) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    out.copy_from_slice(Impl::hash_bytes(bytes).as_bytes());
+    out
+}
+
+/// A SHA-256 message written straight into word-aligned blocks, so that it
+/// is hashed by a single call to the compression function, with no copy of
+/// it made to align or pad it.
+struct Message<B> {
+    blocks: B,
+    len: usize,
+}
+
+impl<B: AsMut<[Block]>> Message<B> {
+    /// An empty message in `blocks`, which must be zeroed and have room for
+    /// the message and its padding.
+    const fn new(blocks: B) -> Self {
+        Self { blocks, len: 0 }
+    }
+
+    fn write(&mut self, mut bytes: &[u8]) {
+        let blocks = self.blocks.as_mut();
+        while !bytes.is_empty() {
+            let offset = self.len % BLOCK_BYTES;
+            let n = bytes.len().min(BLOCK_BYTES - offset);
+            blocks[self.len / BLOCK_BYTES].as_mut_bytes()[offset..offset + n]
+                .copy_from_slice(&bytes[..n]);
+            self.len += n;
+            bytes = &bytes[n..];
+        }
+    }
+
+    /// Pads the message as SHA-256 does and hashes it.
+    fn digest(mut self) -> [u8; 32] {
+        let bits = (self.len as u64 * 8).to_be_bytes();
+        let end = (self.len + 9).div_ceil(BLOCK_BYTES);
+        self.write(&[0x80]);
+        let blocks = &mut self.blocks.as_mut()[..end];
+        blocks[end - 1].as_mut_bytes()[BLOCK_BYTES - 8..].copy_from_slice(&bits);
+        let mut out = [0u8; 32];
+        out.copy_from_slice(Impl::compress_slice(&SHA256_INIT, blocks).as_bytes());
+        out
+    }
+}
@@ -1,28 +1,37 @@
 #![allow(clippy::unused_self)]
 
-use crate::{TarHeader, block_count, parse_tar_header};
+use crate::{
+    ArchivePath, TarExtensions, TarHeader, TarMember, TreeMetadata, block_count,
+    decode_file_header, leaf_hash, leaf_index, merkle_tree::sha256, multiproof_root, node_hash,
+    record_hash, tree_depth,
+};
 use alloc::{
     format,
     string::{String, ToString},
     vec::Vec,
 };
-use core::str;
+use core::cell::RefCell;
 use hashbrown::HashSet;
-use risc0_zkvm::sha::{Impl, Sha256};
-use zk_sca_guest_abi::{MerkleLeaf, MerklePathNode, PartialMerkleArchive, ScaError};
+use zk_sca_guest_abi::{
+    MerkleFormat, MerkleLeaf, MerklePathNode, PartialMerkleArchive, ScaError, SharedBytes,
+};
 
 type MRes<T> = Result<T, (ScaError, String)>;
 
+/// A node of the tree, as its index on its level and its hash.
+type Node = (usize, [u8; 32]);
+
 #[derive(Clone, Debug)]
 pub struct ValidatedFile {
     pub header: TarHeader,
     /// Raw contents (block-verified, depadded).
-    pub bytes: Vec<u8>,
+    pub bytes: SharedBytes,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct ValidPartialArchive {
-    /// All tar headers (authenticated and complete, in original order).
+    /// All tar entries (authenticated and complete, in original order), with
+    /// their PAX and GNU long-name extensions applied.
     pub headers: Vec<TarHeader>,
     /// Authenticated, fully-materialized dependency files (header + depadded bytes)
     /// in the order specified by `dependency_file_header_indices`.
@@ -49,63 +58,186 @@ pub fn validate_merkle_archive(archive: &PartialMerkleArchive) -> MRes<ValidPart
     Verifier {
         archive,
         root: &archive.root_hash,
+        format: archive.format,
+        block_count: None,
+        proven: RefCell::default(),
+        verified: RefCell::default(),
     }
     .verify()
 }
 
-/// Reconstruct the zero-based leaf index from a Merkle proof.
-/// Bits are consumed LSB-first; path\[0\] is depth-0.
-#[inline]
-fn reconstruct_leaf_index(path: &[MerklePathNode]) -> usize {
-    path.iter().enumerate().fold(0usize, |acc, (bit, node)| {
-        acc | (usize::from(!node.is_left_child) << bit)
-    })
-}
-
 struct Verifier<'a> {
     archive: &'a PartialMerkleArchive,
     root: &'a [u8; 32],
+    format: MerkleFormat,
+    /// The leaf count that a v2 metadata leaf commits to, once it is read.
+    block_count: Option<usize>,
+    /// With a multiproof, each leaf read so far.
+    proven: RefCell<Vec<Node>>,
+    /// Without one, the nodes of the last path climbed, nearest the root
+    /// first.
+    verified: RefCell<Vec<Option<Node>>>,
 }
 
 impl Verifier<'_> {
-    fn verify(self) -> MRes<ValidPartialArchive> {
+    fn verify(mut self) -> MRes<ValidPartialArchive> {
         let header_count = self.ensure_count_leaf_is_authentic_and_return_count()?;
-        let headers = self.ensure_header_leaves_are_authentic_and_parse(header_count)?;
+        if self.format == MerkleFormat::FileRecords {
+            let archive = self.verify_file_records(header_count)?;
+            self.ensure_multiproof_is_valid()?;
+            return Ok(archive);
+        }
+        ensure!(
+            self.archive.file_records.is_empty()
+                && self.archive.dependency_file_contents.is_empty(),
+            err!("File records in a block-level archive"),
+        );
+        let entries = self.ensure_header_leaves_are_authentic_and_parse(header_count)?;
+        let headers: Vec<TarHeader> = entries
+            .iter()
+            .filter_map(|(_, header)| header.clone())
+            .collect();
         self.ensure_header_names_are_unique(&headers)?;
 
-        let files = self.ensure_dependency_blocks_are_authentic(&headers)?;
+        let files = self.ensure_dependency_blocks_are_authentic(&entries)?;
+        self.ensure_multiproof_is_valid()?;
 
         Ok(ValidPartialArchive { headers, files })
     }
 
-    fn ensure_count_leaf_is_authentic_and_return_count(&self) -> MRes<usize> {
+    fn ensure_count_leaf_is_authentic_and_return_count(&mut self) -> MRes<usize> {
         let leaf = &self.archive.count_leaf;
-        self.verify_leaf_proof(&leaf.data, leaf)?;
-        let count_str = str::from_utf8(&leaf.data)
-            .map_err(|_| err!("Invalid UTF-8 in count_leaf"))?
-            .trim_end_matches('\0');
-        count_str
-            .parse::<usize>()
-            .map_err(|_| err!("Bad header count"))
+        let hash = leaf_hash(self.format, &leaf.data);
+        self.authenticate_leaf(hash, &leaf.path, 0, "count_leaf")?;
+        let metadata = TreeMetadata::parse(self.format, &leaf.data).map_err(|e| err!(e))?;
+        self.block_count = metadata.block_count;
+        if self.archive.multiproof.is_none() {
+            // The tree's size was unknown when the path was checked.
+            self.ensure_leaf_fits_tree(leaf.path.len(), 0)?;
+        }
+        Ok(metadata.header_count)
     }
 
+    /// Parse every header leaf, with its index, and `None` for those of
+    /// extension entries, whose data blocks are authenticated and applied to
+    /// the entry after them.
     fn ensure_header_leaves_are_authentic_and_parse(
         &self,
         expected_count: usize,
-    ) -> MRes<Vec<TarHeader>> {
+    ) -> MRes<Vec<(usize, Option<TarHeader>)>> {
         let leaves = &self.archive.header_leaves;
         self.expect_len("header proofs", leaves.len(), expected_count)?;
-        leaves
-            .iter()
-            .map(|leaf| {
-                self.verify_leaf_proof(&leaf.data, leaf)?;
-                Ok(parse_tar_header(&leaf.data))
-            })
-            .collect()
+        let mut extension_iter = self.archive.extension_leaves.iter();
+        let mut extensions = TarExtensions::default();
+        let mut entries = Vec::with_capacity(leaves.len());
+        // Each header must sit right after the data blocks of the one before,
+        // so that no data block can be passed off as a header.
+        let mut expect_idx = 1;
+        for leaf in leaves {
+            let leaf_idx = expect_idx;
+            let hash = leaf_hash(self.format, &leaf.data);
+            self.authenticate_leaf(hash, &leaf.path, leaf_idx, "Header")?;
+            let invalid_header = |e| {
+                (
+                    ScaError::InvalidTarHeader,
+                    format!("header at leaf {leaf_idx}: {e}"),
+                )
+            };
+            let member = extensions
+                .parse_header(&leaf.data)
+                .map_err(invalid_header)?;
+            expect_idx += 1 + block_count(member.size());
+            match member {
+                TarMember::Entry(header) => entries.push((leaf_idx, Some(header))),
+                TarMember::Extension { kind, size } => {
+                    let data =
+                        self.read_data_blocks(&mut extension_iter, leaf_idx, size, "Extension")?;
+                    extensions.read(kind, &data).map_err(invalid_header)?;
+                    entries.push((leaf_idx, None));
+                }
+            }
+        }
+        extensions
+            .finish()
+            .map_err(|e| (ScaError::InvalidTarHeader, e))?;
+        if let Some(block_count) = self.block_count {
+            ensure!(
+                expect_idx == block_count,
+                err!(format!(
+                    "Metadata counts {block_count} blocks but the entries span {expect_idx}"
+                )),
+            );
+        }
+        ensure!(
+            extension_iter.next().is_none(),
+            err!("Extra extension leaves")
+        );
+        Ok(entries)
+    }
+
+    /// Authenticate every file record and the contents of the dependency
+    /// files against them.
+    fn verify_file_records(&self, record_count: usize) -> MRes<ValidPartialArchive> {
+        let archive = self.archive;
+        ensure!(
+            archive.header_leaves.is_empty()
+                && archive.extension_leaves.is_empty()
+                && archive.dependency_file_leaves.is_empty(),
+            err!("Block leaves in a file-record archive"),
+        );
+        ensure!(
+            self.block_count == Some(record_count + 1),
+            err!(format!(
+                "Metadata counts {record_count} records but {:?} leaves",
+                self.block_count
+            )),
+        );
+        let records = &archive.file_records;
+        self.expect_len("file records", records.len(), record_count)?;
+
+        let mut headers = Vec::with_capacity(records.len());
+        for (idx, record) in records.iter().enumerate() {
+            let hash = record_hash(&record.header, &record.content_hash);
+            self.authenticate_leaf(hash, &record.path, idx + 1, "File record")?;
+            let header = decode_file_header(&record.header)
+                .map_err(|e| (ScaError::InvalidTarHeader, format!("record {idx}: {e}")))?;
+            headers.push(header);
+        }
+        self.ensure_header_names_are_unique(&headers)?;
+
+        let dep_indices = &archive.dependency_file_header_indices;
+        let contents = &archive.dependency_file_contents;
+        self.expect_len(
+            "dependency file contents",
+            contents.len(),
+            dep_indices.len(),
+        )?;
+        ensure!(
+            dep_indices.windows(2).all(|pair| pair[0] < pair[1]),
+            err!("Dependency files out of order"),
+        );
+        let mut files = Vec::with_capacity(dep_indices.len());
+        for (&idx, bytes) in dep_indices.iter().zip(contents) {
+            let header = headers
+                .get(idx)
+                .ok_or_else(|| err!(format!("Bad dependency header index {idx}")))?;
+            ensure!(
+                bytes.len() == header.size && sha256(bytes) == records[idx].content_hash,
+                err!(format!(
+                    "Contents of `{}` do not match its record",
+                    header.name
+                )),
+            );
+            files.push(ValidatedFile {
+                header: header.clone(),
+                bytes: bytes.clone(),
+            });
+        }
+        Ok(ValidPartialArchive { headers, files })
     }
 
     fn ensure_header_names_are_unique(&self, headers: &[TarHeader]) -> MRes<()> {
-        let mut seen: HashSet<&String> = HashSet::new();
+        let mut seen: HashSet<&ArchivePath> = HashSet::new();
         for hdr in headers {
             ensure!(
                 seen.insert(&hdr.name),
@@ -118,55 +250,35 @@ impl Verifier<'_> {
     /// Authenticate each dependency’s data blocks and return fully-materialized files.
     fn ensure_dependency_blocks_are_authentic(
         &self,
-        headers: &[TarHeader],
+        entries: &[(usize, Option<TarHeader>)],
     ) -> MRes<Vec<ValidatedFile>> {
         let leaves = &self.archive.dependency_file_leaves;
-        let header_proofs = &self.archive.header_leaves;
         let dep_indices = &self.archive.dependency_file_header_indices;
 
+        let mut headers = Vec::with_capacity(dep_indices.len());
         for &idx in dep_indices {
-            ensure!(
-                idx < headers.len(),
-                err!(format!("Bad dependency header index {idx}")),
-            );
+            let header = entries
+                .get(idx)
+                .and_then(|(leaf_idx, header)| Some((*leaf_idx, header.as_ref()?)));
+            headers.push(header.ok_or_else(|| err!(format!("Bad dependency header index {idx}")))?);
         }
 
-        let expected_blocks: usize = dep_indices
-            .iter()
-            .map(|&i| block_count(headers[i].size))
-            .sum();
+        let expected_blocks: usize = headers.iter().map(|(_, hdr)| block_count(hdr.size)).sum();
         self.expect_len("data-block proofs", leaves.len(), expected_blocks)?;
 
         let mut data_iter = leaves.iter();
         let mut files = Vec::new();
 
-        for &hdr_idx in dep_indices {
-            let hdr = &headers[hdr_idx];
-            let h_leaf = &header_proofs[hdr_idx];
-            let needed = block_count(hdr.size);
-
-            let header_leaf_index = reconstruct_leaf_index(&h_leaf.path);
-            let mut buf = Vec::with_capacity(hdr.size);
-
-            for offset in 1..=needed {
-                let leaf = data_iter.next().ok_or_else(|| err!("Missing data leaf"))?;
-                self.verify_leaf_proof(&leaf.data, leaf)?;
-
-                let actual_idx = reconstruct_leaf_index(&leaf.path);
-                let expect_idx = header_leaf_index + offset;
-                ensure!(
-                    actual_idx == expect_idx,
-                    err!(format!(
-                        "Dependency-file indices out of order: expected {expect_idx}, got {actual_idx}"
-                    )),
-                );
-                buf.extend_from_slice(&leaf.data);
-            }
-            buf.truncate(hdr.size);
-
+        for (header_leaf_index, hdr) in headers {
+            let bytes = self.read_data_blocks(
+                &mut data_iter,
+                header_leaf_index,
+                hdr.size,
+                "Dependency-file",
+            )?;
             files.push(ValidatedFile {
                 header: hdr.clone(),
-                bytes: buf,
+                bytes: bytes.into(),
             });
         }
 
@@ -174,6 +286,26 @@ impl Verifier<'_> {
         Ok(files)
     }
 
+    /// Authenticate the `size` bytes of data that follow the header at
+    /// `header_leaf_index`, taking their blocks from `blocks`.
+    fn read_data_blocks<'b>(
+        &self,
+        blocks: &mut impl Iterator<Item = &'b MerkleLeaf>,
+        header_leaf_index: usize,
+        size: usize,
+        what: &str,
+    ) -> MRes<Vec<u8>> {
+        let mut buf = Vec::with_capacity(size);
+        for offset in 1..=block_count(size) {
+            let leaf = blocks.next().ok_or_else(|| err!("Missing data leaf"))?;
+            let hash = leaf_hash(self.format, &leaf.data);
+            self.authenticate_leaf(hash, &leaf.path, header_leaf_index + offset, what)?;
+            buf.extend_from_slice(&leaf.data);
+        }
+        buf.truncate(size);
+        Ok(buf)
+    }
+
     #[inline]
     fn expect_len(&self, what: &str, got: usize, exp: usize) -> MRes<()> {
         ensure!(
@@ -183,29 +315,129 @@ impl Verifier<'_> {
         Ok(())
     }
 
-    #[inline]
-    fn verify_leaf_proof(&self, data: &[u8; 512], proof: &MerkleLeaf) -> MRes<()> {
+    /// Authenticate the leaf hashing to `hash` as leaf `index`: by its own
+    /// path, or, with a multiproof, once every leaf has been read.
+    fn authenticate_leaf(
+        &self,
+        hash: [u8; 32],
+        path: &[MerklePathNode],
+        index: usize,
+        what: &str,
+    ) -> MRes<()> {
+        if self.archive.multiproof.is_some() {
+            ensure!(path.is_empty(), err!("Merkle path alongside a multiproof"));
+            self.proven.borrow_mut().push((index, hash));
+            return Ok(());
+        }
+        ensure!(
+            path_leads_to(path, index),
+            err!(format!(
+                "{what} indices out of order: expected {index}, got {}",
+                leaf_index(path)
+            )),
+        );
+        self.ensure_leaf_fits_tree(path.len(), index)?;
         ensure!(
-            verify_merkle_proof(data, &proof.path, self.root),
+            self.climb(hash, path, index),
             err!("Merkle proof failed for a leaf block"),
         );
         Ok(())
     }
+
+    /// Whether the leaf hashing to `leaf` at `index` hashes up `path` to the
+    /// root, or to a node already found to.
+    ///
+    /// Leaves are mostly read in order, so the nodes on the last path climbed
+    /// are kept: the leaf after it usually meets that path a level or two up.
+    /// They are kept by their distance from the root and their index on their
+    /// level, which together name one node of the tree whatever the length of
+    /// the path that reaches it.
+    fn climb(&self, leaf: [u8; 32], path: &[MerklePathNode], index: usize) -> bool {
+        let mut verified = self.verified.borrow_mut();
+        if verified.len() < path.len() {
+            verified.resize(path.len(), None);
+        }
+        let mut hash = leaf;
+        for (level, node) in path.iter().enumerate() {
+            let position = index >> level;
+            let slot = &mut verified[path.len() - level - 1];
+            if let Some((_, known)) = slot.filter(|&(at, _)| at == position) {
+                return known == hash;
+            }
+            // Should the climb fail, the archive is rejected, so the node may
+            // be kept before it is known to be on the way to the root.
+            *slot = Some((position, hash));
+            hash = if node.is_left_child {
+                node_hash(self.format, &hash, &node.sibling_hash)
+            } else {
+                node_hash(self.format, &node.sibling_hash, &hash)
+            };
+        }
+        &hash == self.root
+    }
+
+    /// A multiproof must prove exactly the leaves that were read, at the
+    /// indices they were read at, in a tree of the size the metadata gives.
+    fn ensure_multiproof_is_valid(&self) -> MRes<()> {
+        let Some(proof) = &self.archive.multiproof else {
+            return Ok(());
+        };
+        let mut proven = self.proven.take();
+        proven.sort_unstable_by_key(|&(index, _)| index);
+        ensure!(
+            proof
+                .leaf_indices
+                .iter()
+                .eq(proven.iter().map(|(index, _)| index)),
+            err!("Multiproof indices do not match the leaves read"),
+        );
+        if let Some(block_count) = self.block_count {
+            ensure!(
+                proof.leaf_count == block_count,
+                err!(format!(
+                    "Multiproof over {} leaves in a tree of {block_count} blocks",
+                    proof.leaf_count
+                )),
+            );
+        }
+        let hashes: Vec<[u8; 32]> = proven.into_iter().map(|(_, hash)| hash).collect();
+        ensure!(
+            multiproof_root(self.format, proof, &hashes).as_ref() == Some(self.root),
+            err!("Multiproof failed"),
+        );
+        Ok(())
+    }
+
+    /// Once the tree's size is known, a path must climb exactly its height
+    /// and lead to one of its leaves.
+    fn ensure_leaf_fits_tree(&self, path_len: usize, index: usize) -> MRes<()> {
+        if let Some(block_count) = self.block_count {
+            ensure!(
+                path_len == tree_depth(block_count),
+                err!(format!(
+                    "Merkle path of {path_len} nodes in a tree of {block_count} blocks"
+                )),
+            );
+            ensure!(
+                index < block_count,
+                err!(format!(
+                    "Leaf {index} is past the last of {block_count} blocks"
+                )),
+            );
+        }
+        Ok(())
+    }
 }
 
-/// Check a block’s Merkle path against the archive root (SHA-256, left-duplicate rule).
-fn verify_merkle_proof(data: &[u8; 512], path: &Vec<MerklePathNode>, root_hash: &[u8; 32]) -> bool {
-    let mut current_hash = Impl::hash_bytes(data);
-    for item in path {
-        let mut combined = Vec::with_capacity(64);
-        if item.is_left_child {
-            combined.extend_from_slice(current_hash.as_bytes());
-            combined.extend_from_slice(&item.sibling_hash);
-        } else {
-            combined.extend_from_slice(&item.sibling_hash);
-            combined.extend_from_slice(current_hash.as_bytes());
-        }
-        current_hash = Impl::hash_bytes(&combined);
+/// Whether `path` turns at each level as the bits of `index` say, and has a
+/// level for each of them.
+fn path_leads_to(path: &[MerklePathNode], index: usize) -> bool {
+    let mut rest = index;
+    for node in path {
+        if node.is_left_child != (rest % 2 == 0) {
+            return false;
+        }
+        rest /= 2;
     }
-    current_hash.as_bytes() == root_hash
+    rest == 0
 }
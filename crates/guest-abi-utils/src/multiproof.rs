@@ -0,0 +1,95 @@
+use crate::{node_hash, padding};
+use alloc::vec::Vec;
+use zk_sca_guest_abi::{MerkleFormat, MerkleMultiproof, MerklePathNode};
+
+/// Builds the multiproof of the leaves of a tree of `leaf_count` leaves that
+/// `leaves` lists, by index and path, in ascending order of index.
+#[must_use]
+pub fn build_multiproof(
+    leaf_count: usize,
+    leaves: &[(usize, &[MerklePathNode])],
+) -> MerkleMultiproof {
+    let mut hashes = Vec::new();
+    // Each known node of the level, with the path of a leaf below it.
+    let mut known = leaves.to_vec();
+    let mut width = leaf_count;
+    let mut level = 0;
+    while width > 1 {
+        let mut parents = Vec::with_capacity(known.len());
+        let mut i = 0;
+        while i < known.len() {
+            let (index, path) = known[i];
+            if index % 2 == 0 && known.get(i + 1).is_some_and(|&(next, _)| next == index + 1) {
+                i += 1;
+            } else if index ^ 1 < width {
+                hashes.push(path[level].sibling_hash);
+            }
+            parents.push((index / 2, path));
+            i += 1;
+        }
+        known = parents;
+        width = width.div_ceil(2);
+        level += 1;
+    }
+    MerkleMultiproof {
+        leaf_count,
+        leaf_indices: leaves.iter().map(|&(index, _)| index).collect(),
+        hashes,
+    }
+}
+
+/// The root that `proof` leads to from `leaves`, or `None` if it is malformed.
+///
+/// `leaves` are the hashes of the proven leaves, in the order of the indices,
+/// which must ascend and lie within the tree. The proof must hold exactly the
+/// hashes needed.
+#[must_use]
+pub fn multiproof_root(
+    format: MerkleFormat,
+    proof: &MerkleMultiproof,
+    leaves: &[[u8; 32]],
+) -> Option<[u8; 32]> {
+    let indices = &proof.leaf_indices;
+    let mut width = proof.leaf_count;
+    if leaves.is_empty()
+        || indices.len() != leaves.len()
+        || !indices.windows(2).all(|pair| pair[0] < pair[1])
+        || indices.last().is_some_and(|&last| last >= width)
+    {
+        return None;
+    }
+
+    let mut nodes: Vec<(usize, [u8; 32])> = indices
+        .iter()
+        .copied()
+        .zip(leaves.iter().copied())
+        .collect();
+    let mut hashes = proof.hashes.iter();
+    while width > 1 {
+        // Each level's nodes are replaced in place by their parents, which
+        // are never more than they.
+        let mut parents = 0;
+        let mut i = 0;
+        while i < nodes.len() {
+            let (index, hash) = nodes[i];
+            let parent = if index % 2 == 1 {
+                node_hash(format, hashes.next()?, &hash)
+            } else if let Some(&(_, right)) =
+                nodes.get(i + 1).filter(|(next, _)| *next == index + 1)
+            {
+                i += 1;
+                node_hash(format, &hash, &right)
+            } else if index + 1 < width {
+                node_hash(format, &hash, hashes.next()?)
+            } else {
+                node_hash(format, &hash, &padding(format, hash))
+            };
+            nodes[parents] = (index / 2, parent);
+            parents += 1;
+            i += 1;
+        }
+        nodes.truncate(parents);
+        width = width.div_ceil(2);
+    }
+    hashes.next().is_none().then_some(nodes[0].1)
+}
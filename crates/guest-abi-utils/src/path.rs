@@ -0,0 +1,127 @@
+use alloc::{format, string::String};
+use core::{fmt, ops::Deref};
+
+/// A normalized path relative to the root of an archive: `/`-separated
+/// components, none of them empty, `.` or `..`. The root itself is empty.
+///
+/// Paths that extract to the same file normalize to the same `ArchivePath`,
+/// so `a/Cargo.toml`, `./a/Cargo.toml` and `a//Cargo.toml` all compare equal.
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ArchivePath(String);
+
+impl ArchivePath {
+    /// The root of the archive.
+    #[must_use]
+    pub const fn root() -> Self {
+        Self(String::new())
+    }
+
+    /// Normalizes the name of an archive entry. Absolute names and names
+    /// with a `..` component are rejected, as they could extract outside the
+    /// directory the archive is unpacked into.
+    pub fn parse(name: &str) -> Result<Self, String> {
+        Self::root().push(name, false)
+    }
+
+    /// Resolves `relative` against this path as a directory, as a manifest
+    /// or configuration file refers to other paths. `..` components are
+    /// followed, but may not climb above the root.
+    pub fn join(&self, relative: &str) -> Result<Self, String> {
+        self.clone().push(relative, true)
+    }
+
+    /// The directory holding this path; the root is its own parent.
+    #[must_use]
+    pub fn parent(&self) -> Self {
+        let end = self.0.rfind('/').unwrap_or(0);
+        Self(self.0[..end].into())
+    }
+
+    /// The last component of this path; empty for the root.
+    #[must_use]
+    pub fn file_name(&self) -> &str {
+        self.0.rfind('/').map_or(&self.0, |i| &self.0[i + 1..])
+    }
+
+    #[must_use]
+    pub const fn is_root(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    fn push(mut self, relative: &str, follow_parents: bool) -> Result<Self, String> {
+        if relative.starts_with('/') {
+            return Err(format!("`{relative}` is absolute"));
+        }
+        for component in relative.split('/') {
+            match component {
+                "" | "." => {}
+                ".." if !follow_parents => {
+                    return Err(format!("`{relative}` has a `..` component"));
+                }
+                ".." => {
+                    if self.is_root() {
+                        return Err(format!("`{relative}` climbs above the archive root"));
+                    }
+                    let parent = self.0.rfind('/').unwrap_or(0);
+                    self.0.truncate(parent);
+                }
+                _ => {
+                    if !self.is_root() {
+                        self.0.push('/');
+                    }
+                    self.0.push_str(component);
+                }
+            }
+        }
+        Ok(self)
+    }
+}
+
+impl Deref for ArchivePath {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for ArchivePath {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for ArchivePath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<ArchivePath> for String {
+    fn from(path: ArchivePath) -> Self {
+        path.0
+    }
+}
+
+impl PartialEq<str> for ArchivePath {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
+impl PartialEq<&str> for ArchivePath {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == *other
+    }
+}
+
+impl PartialEq<String> for ArchivePath {
+    fn eq(&self, other: &String) -> bool {
+        &self.0 == other
+    }
+}
@@ -1,32 +1,351 @@
-use alloc::string::String;
+use crate::ArchivePath;
+use alloc::{borrow::ToOwned, format, string::String, vec::Vec};
 use core::str;
 
-#[derive(Clone, Debug)]
+/// What a TAR entry holds, from its typeflag.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TarEntryKind {
+    /// A regular file: typeflag `0`, NUL or `7` (contiguous file).
+    File,
+    /// A hard link, typeflag `1`.
+    HardLink,
+    /// A symbolic link, typeflag `2`.
+    Symlink,
+    /// A directory, typeflag `5`.
+    Directory,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct TarHeader {
-    pub name: String,
+    pub name: ArchivePath,
     pub size: usize,
+    pub kind: TarEntryKind,
+    /// What a hard or symbolic link names, as stored. `None` for other kinds.
+    pub link_name: Option<String>,
 }
 
-/// Parses a 512-byte tar header block to extract the file name and file size.
-#[must_use]
-pub fn parse_tar_header(block: &[u8; 512]) -> TarHeader {
-    let name_bytes = &block[0..100];
-    let name_str = str::from_utf8(name_bytes).map_or("", |s| s.trim_end_matches('\0'));
-    let name = String::from(name_str);
-    let size = parse_octal(&block[124..136]);
-    TarHeader { name, size }
+/// An entry whose data describes the entry after it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TarExtensionKind {
+    /// A PAX extended header, typeflag `x`.
+    Pax,
+    /// A PAX global header, typeflag `g`. As the `tar` crate and Go's
+    /// `archive/tar` do, its records are not applied.
+    PaxGlobal,
+    /// A GNU long name, typeflag `L`.
+    GnuLongName,
+    /// A GNU long link name, typeflag `K`.
+    GnuLongLink,
+}
+
+/// What a header block starts.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TarMember {
+    Entry(TarHeader),
+    Extension { kind: TarExtensionKind, size: usize },
+}
+
+impl TarMember {
+    /// How many bytes of data follow the header block.
+    #[must_use]
+    pub const fn size(&self) -> usize {
+        match self {
+            Self::Entry(header) => header.size,
+            Self::Extension { size, .. } => *size,
+        }
+    }
+}
+
+/// Parses the header blocks of a TAR in order, applying the `path`, `size`
+/// and `linkpath` records of PAX extended headers and GNU long names and
+/// long link names to the entry that follows them.
+///
+/// Each header block must be valid:
+///
+/// * The checksum must match the block, summed as unsigned bytes with the
+///   checksum field read as spaces.
+/// * The magic and version must be POSIX `ustar\0` `00`, or GNU `ustar ` ` \0`.
+/// * The name, joined to the USTAR prefix or replaced by an extension, must
+///   be non-empty UTF-8 that [`ArchivePath::parse`] accepts, and is given
+///   normalized.
+/// * The size must be octal or positive base-256, and zero for entries that
+///   carry no data once extensions are applied.
+/// * Device, FIFO and sparse entries are not supported.
+#[derive(Debug, Default)]
+pub struct TarExtensions {
+    pax: Option<PaxOverrides>,
+    long_name: Option<String>,
+    long_link: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct PaxOverrides {
+    path: Option<String>,
+    size: Option<usize>,
+    link_path: Option<String>,
 }
 
-/// Parses an octal number from a byte slice.
-fn parse_octal(input: &[u8]) -> usize {
-    let mut result = 0;
-    for &b in input {
-        if b == 0 || b == b' ' {
-            continue;
+impl TarExtensions {
+    /// Parses the next header block. An extension's data must be passed to
+    /// [`Self::read`] before the next block is parsed.
+    pub fn parse_header(&mut self, block: &[u8; 512]) -> Result<TarMember, String> {
+        let stored = parse_octal(&block[148..156]).map_err(|e| format!("checksum field {e}"))?;
+        let actual: usize = block
+            .iter()
+            .enumerate()
+            .map(|(i, &b)| {
+                if (148..156).contains(&i) {
+                    32
+                } else {
+                    usize::from(b)
+                }
+            })
+            .sum();
+        if stored != actual {
+            return Err(format!(
+                "checksum is {stored:o}, but the block sums to {actual:o}"
+            ));
         }
-        result = result * 8 + (b - b'0') as usize;
+
+        // GNU headers keep other fields where USTAR has its name prefix.
+        let prefix = match (&block[257..263], &block[263..265]) {
+            (b"ustar\0", b"00") => &block[345..500],
+            (b"ustar ", b" \0") => &[][..],
+            _ => return Err("not a USTAR or GNU header".to_owned()),
+        };
+
+        let size = parse_tar_size(&block[124..136])?;
+        let kind = match block[156] {
+            0 | b'0' | b'7' => TarEntryKind::File,
+            b'1' => TarEntryKind::HardLink,
+            b'2' => TarEntryKind::Symlink,
+            b'5' => TarEntryKind::Directory,
+            flag @ (b'x' | b'g' | b'L' | b'K') => {
+                let kind = match flag {
+                    b'x' => TarExtensionKind::Pax,
+                    b'g' => TarExtensionKind::PaxGlobal,
+                    b'L' => TarExtensionKind::GnuLongName,
+                    _ => TarExtensionKind::GnuLongLink,
+                };
+                return Ok(TarMember::Extension { kind, size });
+            }
+            b'3' | b'4' | b'6' => {
+                return Err("device and FIFO entries are not supported".to_owned());
+            }
+            other => return Err(format!("unknown entry type `{}`", other.escape_ascii())),
+        };
+
+        let pax = self.pax.take().unwrap_or_default();
+        let long_name = self.long_name.take();
+        let long_link = self.long_link.take();
+        let name = if let Some(path) = pax.path.or(long_name) {
+            ArchivePath::parse(&path)?
+        } else {
+            let (prefix, name) = (c_str(prefix)?, c_str(&block[..100])?);
+            if prefix.is_empty() {
+                ArchivePath::parse(name)?
+            } else {
+                ArchivePath::parse(&format!("{prefix}/{name}"))?
+            }
+        };
+        if name.is_root() {
+            return Err("entry has no name".to_owned());
+        }
+        let size = pax.size.unwrap_or(size);
+        if kind != TarEntryKind::File && size != 0 {
+            return Err(format!(
+                "`{name}` declares {size} bytes but carries no data"
+            ));
+        }
+
+        let link_name = match kind {
+            TarEntryKind::HardLink | TarEntryKind::Symlink => {
+                let stored = || c_str(&block[157..257]).map(ToOwned::to_owned);
+                let link_name = pax.link_path.or(long_link).map_or_else(stored, Ok)?;
+                if link_name.is_empty() {
+                    return Err(format!("link `{name}` names no target"));
+                }
+                Some(link_name)
+            }
+            TarEntryKind::File | TarEntryKind::Directory => None,
+        };
+
+        Ok(TarMember::Entry(TarHeader {
+            name,
+            size,
+            kind,
+            link_name,
+        }))
+    }
+
+    /// Records what the data of an extension entry of `kind` says about the
+    /// next entry. An entry may be described by one extension of each kind.
+    pub fn read(&mut self, kind: TarExtensionKind, data: &[u8]) -> Result<(), String> {
+        match kind {
+            TarExtensionKind::Pax => {
+                if self.pax.is_some() {
+                    return Err("two PAX extended headers describe one entry".to_owned());
+                }
+                let mut pax = PaxOverrides::default();
+                for (key, value) in parse_pax_records(data)? {
+                    match key {
+                        "path" => pax.path = Some(value.to_owned()),
+                        "linkpath" => pax.link_path = Some(value.to_owned()),
+                        "size" => {
+                            pax.size = Some(
+                                value
+                                    .parse()
+                                    .map_err(|_| format!("bad PAX size `{value}`"))?,
+                            );
+                        }
+                        _ => {}
+                    }
+                }
+                self.pax = Some(pax);
+            }
+            TarExtensionKind::GnuLongName => {
+                if self.long_name.is_some() {
+                    return Err("two GNU long names describe one entry".to_owned());
+                }
+                self.long_name = Some(c_str(data)?.to_owned());
+            }
+            TarExtensionKind::GnuLongLink => {
+                if self.long_link.is_some() {
+                    return Err("two GNU long link names describe one entry".to_owned());
+                }
+                self.long_link = Some(c_str(data)?.to_owned());
+            }
+            TarExtensionKind::PaxGlobal => {}
+        }
+        Ok(())
     }
-    result
+
+    /// Ends the archive, which may not end with extensions that describe no entry.
+    pub fn finish(self) -> Result<(), String> {
+        if self.pax.is_some() || self.long_name.is_some() || self.long_link.is_some() {
+            return Err("archive ends with extensions that describe no entry".to_owned());
+        }
+        Ok(())
+    }
+}
+
+/// Parses a header block that must start an entry on its own, with no
+/// extension before it. See [`TarExtensions`] for what is validated.
+pub fn parse_tar_header(block: &[u8; 512]) -> Result<TarHeader, String> {
+    match TarExtensions::default().parse_header(block)? {
+        TarMember::Entry(header) => Ok(header),
+        TarMember::Extension { .. } => Err("header starts a PAX or GNU extension".to_owned()),
+    }
+}
+
+/// A member of an uncompressed TAR, as [`read_tar_members`] lists it.
+#[derive(Clone, Debug)]
+pub struct RawTarMember<'a> {
+    pub header: &'a [u8; 512],
+    pub member: TarMember,
+    /// The member's data, without the padding of its last block.
+    pub data: &'a [u8],
+}
+
+/// Lists the members of an uncompressed TAR, extension entries included, up
+/// to its first all-zero block or its end.
+pub fn read_tar_members(tar: &[u8]) -> Result<Vec<RawTarMember<'_>>, String> {
+    let mut extensions = TarExtensions::default();
+    let mut members = Vec::new();
+    let mut pos = 0;
+    while let Some(header) = tar
+        .get(pos..pos + 512)
+        .and_then(|block| <&[u8; 512]>::try_from(block).ok())
+    {
+        if header.iter().all(|&b| b == 0) {
+            break;
+        }
+        let member = extensions.parse_header(header)?;
+        pos += 512;
+        let data = pos
+            .checked_add(member.size())
+            .and_then(|end| tar.get(pos..end))
+            .ok_or_else(|| "entry runs past the end of the archive".to_owned())?;
+        pos += block_count(data.len()) * 512;
+        if let TarMember::Extension { kind, .. } = member {
+            extensions.read(kind, data)?;
+        }
+        members.push(RawTarMember {
+            header,
+            member,
+            data,
+        });
+    }
+    extensions.finish()?;
+    Ok(members)
+}
+
+/// The `<length> <key>=<value>\n` records of a PAX extended header.
+pub fn parse_pax_records(data: &[u8]) -> Result<Vec<(&str, &str)>, String> {
+    let text = str::from_utf8(data).map_err(|_| "PAX header is not UTF-8".to_owned())?;
+    let mut records = Vec::new();
+    let mut rest = text;
+    while !rest.is_empty() {
+        let malformed = || "malformed PAX record".to_owned();
+        let (digits, _) = rest.split_once(' ').ok_or_else(malformed)?;
+        let len: usize = digits.parse().map_err(|_| malformed())?;
+        let record = rest.get(..len).ok_or_else(malformed)?;
+        let (key, value) = record
+            .get(digits.len() + 1..len.saturating_sub(1))
+            .and_then(|kv| kv.split_once('='))
+            .filter(|_| record.ends_with('\n'))
+            .ok_or_else(malformed)?;
+        records.push((key, value));
+        rest = &rest[len..];
+    }
+    Ok(records)
+}
+
+/// The UTF-8 text of a NUL-terminated header field.
+fn c_str(field: &[u8]) -> Result<&str, String> {
+    let bytes = field.split(|&b| b == 0).next().unwrap_or_default();
+    str::from_utf8(bytes).map_err(|_| "name is not UTF-8".to_owned())
+}
+
+/// Parses the 12-byte size field of a TAR header: octal digits, or the
+/// base-256 encoding that GNU tar uses for sizes of 8 GiB and more.
+pub fn parse_tar_size(field: &[u8]) -> Result<usize, String> {
+    match field.first() {
+        Some(&b) if b & 0x80 != 0 => {
+            if b & 0x40 != 0 {
+                return Err("size is negative".to_owned());
+            }
+            field[1..]
+                .iter()
+                .try_fold(usize::from(b & 0x3f), |acc, &b| {
+                    acc.checked_mul(256)?.checked_add(usize::from(b))
+                })
+                .ok_or_else(|| "size overflows".to_owned())
+        }
+        _ => parse_octal(field).map_err(|e| format!("size field {e}")),
+    }
+}
+
+/// Parses an octal field: optional leading spaces, then digits, then only
+/// spaces and NULs. A field without digits reads as zero.
+fn parse_octal(field: &[u8]) -> Result<usize, &'static str> {
+    let start = field.iter().position(|&b| b != b' ').unwrap_or(field.len());
+    let digits = &field[start..];
+    let end = digits
+        .iter()
+        .position(|&b| b == 0 || b == b' ')
+        .unwrap_or(digits.len());
+    if digits[end..].iter().any(|&b| b != 0 && b != b' ') {
+        return Err("is not octal");
+    }
+    digits[..end].iter().try_fold(0usize, |acc, &b| {
+        if !(b'0'..=b'7').contains(&b) {
+            return Err("is not octal");
+        }
+        acc.checked_mul(8)
+            .map(|acc| acc + usize::from(b - b'0'))
+            .ok_or("overflows")
+    })
 }
 
 /// How many 512-byte blocks are needed to hold `size` bytes.
@@ -0,0 +1,81 @@
+use alloc::{sync::Arc, vec::Vec};
+use core::{fmt, ops::Deref};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Bytes that share one buffer with other views into it, so that the parts of
+/// a packed archive are borrowed from its slab rather than copied out of it.
+/// They serialize as a `Vec<u8>` does.
+#[derive(Clone, Default)]
+pub struct SharedBytes {
+    buffer: Arc<Vec<u8>>,
+    start: usize,
+    end: usize,
+}
+
+impl SharedBytes {
+    /// The bytes of `buffer` in `start..end`, which must be in bounds.
+    pub(crate) fn slice(buffer: &Arc<Vec<u8>>, start: usize, end: usize) -> Self {
+        assert!(start <= end && end <= buffer.len(), "slice out of bounds");
+        Self {
+            buffer: Arc::clone(buffer),
+            start,
+            end,
+        }
+    }
+
+    #[must_use]
+    pub fn as_slice(&self) -> &[u8] {
+        &self.buffer[self.start..self.end]
+    }
+}
+
+impl From<Vec<u8>> for SharedBytes {
+    fn from(bytes: Vec<u8>) -> Self {
+        let end = bytes.len();
+        Self {
+            buffer: Arc::new(bytes),
+            start: 0,
+            end,
+        }
+    }
+}
+
+impl Deref for SharedBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl AsRef<[u8]> for SharedBytes {
+    fn as_ref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl PartialEq for SharedBytes {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl Eq for SharedBytes {}
+
+impl fmt::Debug for SharedBytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_slice().fmt(f)
+    }
+}
+
+impl Serialize for SharedBytes {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.as_slice())
+    }
+}
+
+impl<'de> Deserialize<'de> for SharedBytes {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::deserialize(deserializer).map(Self::from)
+    }
+}
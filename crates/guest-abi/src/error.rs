@@ -17,4 +17,18 @@ pub enum ScaError {
     InvalidWorkspaceCount = 14,
     UnsupportedPackageManager = 15,
     InconsistentPackageManager = 16,
+    UnknownWorkspaceMember = 17,
+    UnknownFeature = 18,
+    ForbiddenSourceOverride = 19,
+    VendoredSourceMismatch = 20,
+    UnresolvableLockfileDependency = 21,
+    PackageManagerVersionMismatch = 22,
+    ToolchainPolicyViolation = 23,
+    InvalidCratePackage = 24,
+    InvalidAuditableBinary = 25,
+    InapplicableOption = 26,
+    InvalidContainerImage = 27,
+    InvalidGitTree = 28,
+    InvalidTarHeader = 29,
+    LinkedDependencyFile = 30,
 }
@@ -0,0 +1,19 @@
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+/// The Git objects the guest authenticates from a commit ID, without their
+/// `<type> <size>\0` object headers. Each is sent as bytes rather than a word
+/// per byte.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct GitTree {
+    /// The commit object, whose first line names the root tree.
+    #[serde(with = "crate::packed::bytes")]
+    pub commit: Vec<u8>,
+    /// Every tree reachable from the root tree, in any order.
+    #[serde(with = "crate::packed::byte_vecs")]
+    pub trees: Vec<Vec<u8>>,
+    /// The blob of every Cargo dependency file and symbolic link in those
+    /// trees, and of any file a dependency file links to, in any order.
+    #[serde(with = "crate::packed::byte_vecs")]
+    pub blobs: Vec<Vec<u8>>,
+}
@@ -1,17 +1,82 @@
-use crate::{LicensePolicy, PartialMerkleArchive, PermittedDependencies};
+use crate::{
+    ContainerImage, FeatureSelection, GitCommit, GitTree, ImageAttestation, LicensePolicy,
+    LinkPolicy, MemberScope, MerkleFormat, OverridePolicy, PackageManagerRange, PackageManagerSpec,
+    PartialMerkleArchive, PermittedDependencies, ToolchainAttestation, ToolchainPolicy,
+};
+use alloc::vec::Vec;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Deserialize, PartialEq, Serialize)]
 pub struct GuestInput {
-    /// `MerkleArchive` of only the manifest, header, and dependency blocks needed for SCA.
-    pub src_archive: PartialMerkleArchive,
+    /// What is analyzed.
+    pub source: Source,
     /// Permitted dependency metadata (name, license, min safe version), grouped by framework.
     pub permitted_deps: PermittedDependencies,
     /// Applied to each dependency. If `None`, skip all license checks.
     pub license_policy: Option<LicensePolicy>,
+    /// Workspace members to audit from, and whether their dev-dependency edges
+    /// are followed. If `None`, audit every package in every lockfile.
+    pub member_scope: Option<MemberScope>,
+    /// If set, optional dependencies not activated by these features are not audited.
+    pub feature_selection: Option<FeatureSelection>,
+    /// Restricts `[patch]`, `[replace]` and source replacement. If `None`, all are allowed.
+    pub override_policy: Option<OverridePolicy>,
+    /// If `true`, vendored crates must match their `.cargo-checksum.json` and `Cargo.lock`.
+    pub verify_vendored_sources: bool,
+    /// Whether dependency files stored as links are rejected or read from their targets.
+    pub link_policy: LinkPolicy,
+    /// Bounds on the pinned toolchain and the member editions. If `None`, any are allowed.
+    pub toolchain_policy: Option<ToolchainPolicy>,
+    /// The `.crate` file the source archive was unpacked from, if it is a published package.
+    /// Sent as bytes rather than a word per byte.
+    #[serde(with = "crate::packed::option_bytes")]
+    pub crate_package: Option<Vec<u8>>,
+}
+
+/// A source archive, or what the guest analyzes in its place.
+// Read once per proof, so the size of the archive variant does not matter.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+pub enum Source {
+    /// `MerkleArchive` of only the manifest, header, and dependency blocks needed for SCA.
+    /// Sent packed, its blocks and hashes as bytes rather than a word per byte.
+    Archive(#[serde(with = "crate::packed")] PartialMerkleArchive),
+    /// A binary built with `cargo auditable`, sent as bytes rather than a
+    /// word per byte.
+    AuditableBinary {
+        resolved_with: PackageManagerSpec,
+        #[serde(with = "crate::packed::bytes")]
+        binary: Vec<u8>,
+    },
+    /// A container image, whose layer roots are `format` Merkle roots.
+    ContainerImage {
+        resolved_with: PackageManagerSpec,
+        format: MerkleFormat,
+        image: ContainerImage,
+    },
+    /// The objects of a Git commit, whose tree is analyzed.
+    GitTree {
+        resolved_with: PackageManagerSpec,
+        tree: GitTree,
+    },
+}
+
+impl Source {
+    /// The package manager version that resolved the source's dependencies.
+    #[must_use]
+    pub const fn resolved_with(&self) -> &PackageManagerSpec {
+        match self {
+            Self::Archive(archive) => &archive.resolved_with,
+            Self::AuditableBinary { resolved_with, .. }
+            | Self::ContainerImage { resolved_with, .. }
+            | Self::GitTree { resolved_with, .. } => resolved_with,
+        }
+    }
 }
 
 pub const GUEST_OUTPUT_V0: u32 = 0;
+pub const GUEST_OUTPUT_V1: u32 = 1;
+pub const GUEST_OUTPUT_V2: u32 = 2;
 
 #[derive(Debug, Deserialize, PartialEq, Serialize)]
 pub struct GuestOutputV0 {
@@ -23,11 +88,75 @@ pub struct GuestOutputV0 {
     pub license_policy: Option<LicensePolicy>,
 }
 
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+pub struct GuestOutputV1 {
+    /// The Merkle root hash of the archive of source code under analysis.
+    pub root_hash: [u8; 32],
+    /// Per-framework list of dependencies with name, license, and minimum safe version.
+    pub permitted_deps: PermittedDependencies,
+    /// The license policy applied to the analyzed source code.
+    pub license_policy: Option<LicensePolicy>,
+    /// The workspace members that were attested. If `None`, the whole workspace was.
+    pub member_scope: Option<MemberScope>,
+}
+
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+pub struct GuestOutputV2 {
+    /// The Merkle root hash of the archive of source code under analysis.
+    /// `None` if no archive was, as for a `cargo auditable` binary, a
+    /// container image or a Git tree.
+    pub root_hash: Option<[u8; 32]>,
+    /// How `root_hash` or the image layer roots were hashed. `None` if
+    /// neither was.
+    pub merkle_format: Option<MerkleFormat>,
+    /// Per-framework list of dependencies with name, license, and minimum safe version.
+    pub permitted_deps: PermittedDependencies,
+    /// The license policy applied to the analyzed source code.
+    pub license_policy: Option<LicensePolicy>,
+    /// The workspace members that were attested, and whether packages reachable
+    /// only through dev-dependency edges were left out. If `None`, every package
+    /// of the whole workspace was.
+    pub member_scope: Option<MemberScope>,
+    /// The features the claim covers. If `None`, every optional dependency was audited.
+    pub feature_selection: Option<FeatureSelection>,
+    /// The source override policy the analyzed source code was held to.
+    pub override_policy: Option<OverridePolicy>,
+    /// Whether every locked package was shown to be vendored byte-identical to its release.
+    pub verify_vendored_sources: bool,
+    /// How dependency files stored as links were treated.
+    pub link_policy: LinkPolicy,
+    /// The package manager releases the archive is consistent with; contains
+    /// the version in `permitted_deps`' claimed spec.
+    pub package_manager_range: PackageManagerRange,
+    /// The toolchain policy the analyzed source code was held to.
+    pub toolchain_policy: Option<ToolchainPolicy>,
+    /// The toolchain channel, editions and `rust-version`s the archive declares.
+    pub toolchain: ToolchainAttestation,
+    /// SHA-256 of the analyzed `.crate` file, as recorded in the registry index.
+    /// `None` if the source was not a published package.
+    pub crate_checksum: Option<[u8; 32]>,
+    /// SHA-256 of the analyzed `cargo auditable` binary. `None` if no binary
+    /// was analyzed.
+    pub binary_hash: Option<[u8; 32]>,
+    /// The manifest digest, layer roots and audited files of the analyzed
+    /// container image. `None` if no image was analyzed.
+    pub image: Option<ImageAttestation>,
+    /// The Git commit whose tree was analyzed. `None` if the source was not
+    /// read from a Git repository.
+    pub git_commit: Option<GitCommit>,
+}
+
+// Decoded once per receipt, so the size of the newest variant does not matter.
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Deserialize, PartialEq, Serialize)]
 #[non_exhaustive]
 pub enum GuestOutput {
     #[serde(rename = "0")]
     V0(GuestOutputV0),
+    #[serde(rename = "1")]
+    V1(GuestOutputV1),
+    #[serde(rename = "2")]
+    V2(GuestOutputV2),
 }
 
 impl From<GuestOutputV0> for GuestOutput {
@@ -36,11 +165,25 @@ impl From<GuestOutputV0> for GuestOutput {
     }
 }
 
+impl From<GuestOutputV1> for GuestOutput {
+    fn from(v1: GuestOutputV1) -> Self {
+        Self::V1(v1)
+    }
+}
+
+impl From<GuestOutputV2> for GuestOutput {
+    fn from(v2: GuestOutputV2) -> Self {
+        Self::V2(v2)
+    }
+}
+
 impl GuestOutput {
     #[must_use]
     pub const fn version(&self) -> u32 {
         match self {
             Self::V0(_) => GUEST_OUTPUT_V0,
+            Self::V1(_) => GUEST_OUTPUT_V1,
+            Self::V2(_) => GUEST_OUTPUT_V2,
         }
     }
 
@@ -48,6 +191,23 @@ impl GuestOutput {
     pub const fn as_v0(&self) -> Option<&GuestOutputV0> {
         match self {
             Self::V0(inner) => Some(inner),
+            Self::V1(_) | Self::V2(_) => None,
+        }
+    }
+
+    #[must_use]
+    pub const fn as_v1(&self) -> Option<&GuestOutputV1> {
+        match self {
+            Self::V1(inner) => Some(inner),
+            Self::V0(_) | Self::V2(_) => None,
+        }
+    }
+
+    #[must_use]
+    pub const fn as_v2(&self) -> Option<&GuestOutputV2> {
+        match self {
+            Self::V2(inner) => Some(inner),
+            Self::V0(_) | Self::V1(_) => None,
         }
     }
 }
@@ -0,0 +1,17 @@
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+/// The blobs of a container image that the guest authenticates from its manifest.
+/// Each is sent as bytes rather than a word per byte.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ContainerImage {
+    /// The image manifest, whose SHA-256 is the image's manifest digest.
+    #[serde(with = "crate::packed::bytes")]
+    pub manifest: Vec<u8>,
+    /// The image configuration the manifest names, listing each layer's `diff_id`.
+    #[serde(with = "crate::packed::bytes")]
+    pub config: Vec<u8>,
+    /// Every layer as an uncompressed TAR, in manifest order.
+    #[serde(with = "crate::packed::byte_vecs")]
+    pub layers: Vec<Vec<u8>>,
+}
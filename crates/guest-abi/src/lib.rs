@@ -6,16 +6,30 @@
 
 extern crate alloc;
 
+mod bytes;
+pub use bytes::SharedBytes;
+
 mod error;
 pub use error::ScaError;
 
+mod git;
+pub use git::GitTree;
+
 mod guest;
-pub use guest::{GuestInput, GuestOutput, GuestOutputV0};
+pub use guest::{GuestInput, GuestOutput, GuestOutputV0, GuestOutputV1, GuestOutputV2, Source};
+
+mod image;
+pub use image::ContainerImage;
 
 mod merkle;
-pub use merkle::{MerkleLeaf, MerklePathNode, PartialMerkleArchive};
+pub use merkle::{FileRecord, MerkleLeaf, MerkleMultiproof, MerklePathNode, PartialMerkleArchive};
+
+mod packed;
+pub use packed::PACKED_ARCHIVE_V1;
 
 pub use zk_sca_types::{
-    Dependency, LicenseExpr, LicensePolicy, NonEmpty, PackageManager, PackageManagerSpec,
-    PermittedDependencies, SourceBundle, Version,
+    BundleKind, Dependency, FeatureSelection, GitCommit, ImageAttestation, ImageFile, LicenseExpr,
+    LicensePolicy, LinkPolicy, MemberScope, MemberToolchain, MerkleFormat, NonEmpty,
+    OverridePolicy, PackageManager, PackageManagerRange, PackageManagerSpec, PermittedDependencies,
+    RustEdition, SourceBundle, ToolchainAttestation, ToolchainPolicy, Version,
 };
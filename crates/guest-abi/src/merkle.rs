@@ -1,7 +1,8 @@
+use crate::SharedBytes;
 use alloc::vec::Vec;
 use serde::{Deserialize, Serialize};
 use serde_big_array::BigArray;
-use zk_sca_types::PackageManagerSpec;
+use zk_sca_types::{MerkleFormat, PackageManagerSpec};
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct MerklePathNode {
@@ -20,22 +21,66 @@ pub struct MerkleLeaf {
     pub path: Vec<MerklePathNode>,
 }
 
+/// Authenticates many leaves of a tree at once, in place of a path per leaf.
+///
+/// Rebuilding the tree level by level, left to right, from the proven leaves
+/// takes `hashes` in order wherever a node's sibling is not already known.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct MerkleMultiproof {
+    /// How many leaves the tree has.
+    pub leaf_count: usize,
+    /// The indices of the proven leaves, in ascending order.
+    pub leaf_indices: Vec<usize>,
+    /// The sibling hashes that the proven leaves do not determine.
+    pub hashes: Vec<[u8; 32]>,
+}
+
+/// The leaf of one entry in a [`MerkleFormat::FileRecords`] tree.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct FileRecord {
+    /// The entry's kind, size, normalized name and link target, encoded
+    /// canonically.
+    pub header: SharedBytes,
+    /// SHA-256 of the entry's contents.
+    pub content_hash: [u8; 32],
+    /// The Merkle authentication path: ordered sibling nodes from leaf to root.
+    pub path: Vec<MerklePathNode>,
+}
+
 /// Partial Merkle tree of a full TAR: each 512-B block is a leaf; all header
 /// leaves are included, only the dependency-file data-block leaves are included,
-/// and leaf 0 stores the host-asserted header count to prevent omission.
+/// and leaf 0 stores the host-asserted header count, or in later formats the
+/// tree's metadata, to prevent omission. A [`MerkleFormat::FileRecords`] tree
+/// instead has a leaf per entry and carries dependency files whole.
 #[allow(clippy::too_long_first_doc_paragraph)]
 #[derive(Debug, Deserialize, PartialEq, Serialize)]
 pub struct PartialMerkleArchive {
     /// The package manager used to resolve dependencies.
     pub resolved_with: PackageManagerSpec,
+    /// How the tree is hashed and what leaf 0 holds.
+    pub format: MerkleFormat,
     /// Root hash of the full Merkle tree (count leaf included).
     pub root_hash: [u8; 32],
-    /// Leaf at block 0 that asserts the TAR header count.
+    /// Leaf at block 0 that asserts the TAR header count, and in later formats
+    /// the format version, hash algorithm and leaf count.
     pub count_leaf: MerkleLeaf,
-    /// Leaves for every TAR header in archive order.
+    /// Leaves for every TAR header in archive order, those of PAX extended
+    /// headers and GNU long-name entries included.
     pub header_leaves: Vec<MerkleLeaf>,
+    /// Leaves for the data blocks of PAX extended headers and GNU long-name
+    /// entries in archive order.
+    pub extension_leaves: Vec<MerkleLeaf>,
     /// Leaves for the data blocks of dependency files only (e.g., Cargo.toml / Cargo.lock) in archive order.
     pub dependency_file_leaves: Vec<MerkleLeaf>,
-    /// Indices into `header_leaves` whose data blocks are in `dependency_file_leaves`.
+    /// Indices into `header_leaves`, or `file_records`, of the dependency files.
     pub dependency_file_header_indices: Vec<usize>,
+    /// A record for every entry in archive order. Only set, in place of the
+    /// leaves above, in the `FileRecords` format.
+    pub file_records: Vec<FileRecord>,
+    /// The contents of the dependency files, in the order of
+    /// `dependency_file_header_indices`. Only set in the `FileRecords` format.
+    pub dependency_file_contents: Vec<SharedBytes>,
+    /// If set, proves every leaf above at once, and each leaf's own path is
+    /// empty.
+    pub multiproof: Option<MerkleMultiproof>,
 }
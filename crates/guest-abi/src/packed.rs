@@ -0,0 +1,403 @@
+//! The packed form [`PartialMerkleArchive`] takes in a [`crate::GuestInput`].
+//!
+//! Serde sends each byte of a `[u8; 512]` or `[u8; 32]` to the guest as a word
+//! of its own, so blocks and hashes instead travel in a single byte slab that
+//! the guest reads in bulk. Record headers and file contents are then
+//! borrowed from the slab as [`SharedBytes`] rather than copied out of it.
+//! The archive is sent as its packing version, its
+//! package manager and Merkle format as serde sends them, then the slab.
+//!
+//! The slab holds, in order, with every count and length a little-endian
+//! `u64`:
+//!
+//! - the root hash;
+//! - the count leaf, then the header, extension and dependency-file leaves,
+//!   each group as its count, its blocks back to back, then their paths;
+//! - the dependency file header indices, as a count and the indices;
+//! - the file records, as a count, then for each its header's length, its
+//!   header, its content hash and its path;
+//! - the dependency file contents, as a count, then each one's length and
+//!   bytes;
+//! - a byte that is 1 if a multiproof follows, then its leaf count, its leaf
+//!   indices as a count and the indices, and its hashes as a count and the
+//!   hashes.
+//!
+//! A path is its length, a byte per 8 of its nodes whose bits, least
+//! significant first, are set for the nodes that are right children, then
+//! its sibling hashes.
+//!
+//! Other byte payloads of the input are each sent as a slab of their own,
+//! with the modules below.
+
+use crate::{
+    FileRecord, MerkleFormat, MerkleLeaf, MerkleMultiproof, MerklePathNode, PackageManagerSpec,
+    PartialMerkleArchive, SharedBytes,
+};
+use alloc::{sync::Arc, vec::Vec};
+use core::fmt;
+use serde::{
+    Deserialize, Deserializer, Serialize, Serializer,
+    de::{self, SeqAccess, Visitor},
+};
+
+/// The first, and so far only, packing of an archive.
+pub const PACKED_ARCHIVE_V1: u32 = 1;
+
+/// How many bytes a leaf block has.
+const BLOCK: usize = 512;
+
+pub fn serialize<S: Serializer>(
+    archive: &PartialMerkleArchive,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let slab = pack(archive);
+    (
+        PACKED_ARCHIVE_V1,
+        &archive.resolved_with,
+        archive.format,
+        SlabRef(&slab),
+    )
+        .serialize(serializer)
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<PartialMerkleArchive, D::Error> {
+    let (version, resolved_with, format, Slab(slab)) =
+        <(u32, PackageManagerSpec, MerkleFormat, Slab)>::deserialize(deserializer)?;
+    if version != PACKED_ARCHIVE_V1 {
+        return Err(de::Error::custom(format_args!(
+            "unknown packed archive version {version}"
+        )));
+    }
+    unpack(resolved_with, format, slab).map_err(de::Error::custom)
+}
+
+/// Sends a `Vec<u8>` as a slab.
+pub mod bytes {
+    use super::{Slab, SlabRef};
+    use alloc::vec::Vec;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        SlabRef(bytes).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        Slab::deserialize(deserializer).map(|Slab(bytes)| bytes)
+    }
+}
+
+/// Sends a `Vec<Vec<u8>>` as a sequence of slabs.
+pub mod byte_vecs {
+    use super::{Slab, SlabRef};
+    use alloc::vec::Vec;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(vecs: &[Vec<u8>], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(vecs.iter().map(|bytes| SlabRef(bytes)))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Vec<u8>>, D::Error> {
+        Vec::<Slab>::deserialize(deserializer)
+            .map(|slabs| slabs.into_iter().map(|Slab(bytes)| bytes).collect())
+    }
+}
+
+/// Sends an `Option<Vec<u8>>` as an optional slab.
+pub mod option_bytes {
+    use super::{Slab, SlabRef};
+    use alloc::vec::Vec;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    // Serde passes the field itself, not an `Option<&Vec<u8>>`.
+    #[allow(clippy::ref_option)]
+    pub fn serialize<S: Serializer>(
+        bytes: &Option<Vec<u8>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        bytes.as_deref().map(SlabRef).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Vec<u8>>, D::Error> {
+        Option::<Slab>::deserialize(deserializer).map(|slab| slab.map(|Slab(bytes)| bytes))
+    }
+}
+
+/// Bytes that serde sends as bytes rather than as a sequence of numbers.
+struct Slab(Vec<u8>);
+
+/// Borrowed bytes, sent as a [`Slab`] is.
+struct SlabRef<'a>(&'a [u8]);
+
+impl Serialize for SlabRef<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Slab {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct SlabVisitor;
+
+        impl<'de> Visitor<'de> for SlabVisitor {
+            type Value = Slab;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a byte slab")
+            }
+
+            fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<Slab, E> {
+                Ok(Slab(bytes.to_vec()))
+            }
+
+            fn visit_byte_buf<E: de::Error>(self, bytes: Vec<u8>) -> Result<Slab, E> {
+                Ok(Slab(bytes))
+            }
+
+            // Formats without a byte type send the slab as a sequence.
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Slab, A::Error> {
+                let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+                while let Some(byte) = seq.next_element()? {
+                    bytes.push(byte);
+                }
+                Ok(Slab(bytes))
+            }
+        }
+
+        deserializer.deserialize_byte_buf(SlabVisitor)
+    }
+}
+
+fn pack(archive: &PartialMerkleArchive) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(&archive.root_hash);
+    put_leaves(&mut out, core::slice::from_ref(&archive.count_leaf));
+    put_leaves(&mut out, &archive.header_leaves);
+    put_leaves(&mut out, &archive.extension_leaves);
+    put_leaves(&mut out, &archive.dependency_file_leaves);
+    put_indices(&mut out, &archive.dependency_file_header_indices);
+    put_len(&mut out, archive.file_records.len());
+    for record in &archive.file_records {
+        put_bytes(&mut out, &record.header);
+        out.extend_from_slice(&record.content_hash);
+        put_path(&mut out, &record.path);
+    }
+    put_len(&mut out, archive.dependency_file_contents.len());
+    for contents in &archive.dependency_file_contents {
+        put_bytes(&mut out, contents);
+    }
+    match &archive.multiproof {
+        None => out.push(0),
+        Some(proof) => {
+            out.push(1);
+            put_len(&mut out, proof.leaf_count);
+            put_indices(&mut out, &proof.leaf_indices);
+            put_len(&mut out, proof.hashes.len());
+            for hash in &proof.hashes {
+                out.extend_from_slice(hash);
+            }
+        }
+    }
+    out
+}
+
+fn unpack(
+    resolved_with: PackageManagerSpec,
+    format: MerkleFormat,
+    slab: Vec<u8>,
+) -> Result<PartialMerkleArchive, &'static str> {
+    let buffer = Arc::new(slab);
+    let mut slab = Reader {
+        rest: &buffer,
+        offset: 0,
+    };
+    let root_hash = slab.array()?;
+    let Ok([count_leaf]) = <[MerkleLeaf; 1]>::try_from(slab.leaves()?) else {
+        return Err("packed archive has no single count leaf");
+    };
+    let header_leaves = slab.leaves()?;
+    let extension_leaves = slab.leaves()?;
+    let dependency_file_leaves = slab.leaves()?;
+    let dependency_file_header_indices = slab.indices()?;
+    let mut file_records = Vec::new();
+    for _ in 0..slab.len()? {
+        let header = slab.shared(&buffer)?;
+        let content_hash = slab.array()?;
+        let path = slab.path()?;
+        file_records.push(FileRecord {
+            header,
+            content_hash,
+            path,
+        });
+    }
+    let mut dependency_file_contents = Vec::new();
+    for _ in 0..slab.len()? {
+        dependency_file_contents.push(slab.shared(&buffer)?);
+    }
+    let multiproof = match slab.array::<1>()? {
+        [0] => None,
+        [1] => {
+            let leaf_count = slab.len()?;
+            let leaf_indices = slab.indices()?;
+            let count = slab.len()?;
+            let hashes = slab.chunks(count, 32)?.map(to_array).collect();
+            Some(MerkleMultiproof {
+                leaf_count,
+                leaf_indices,
+                hashes,
+            })
+        }
+        _ => return Err("packed archive has a bad multiproof flag"),
+    };
+    if !slab.rest.is_empty() {
+        return Err("packed archive has trailing bytes");
+    }
+    Ok(PartialMerkleArchive {
+        resolved_with,
+        format,
+        root_hash,
+        count_leaf,
+        header_leaves,
+        extension_leaves,
+        dependency_file_leaves,
+        dependency_file_header_indices,
+        file_records,
+        dependency_file_contents,
+        multiproof,
+    })
+}
+
+fn put_len(out: &mut Vec<u8>, len: usize) {
+    out.extend_from_slice(&(len as u64).to_le_bytes());
+}
+
+fn put_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    put_len(out, bytes.len());
+    out.extend_from_slice(bytes);
+}
+
+fn put_indices(out: &mut Vec<u8>, indices: &[usize]) {
+    put_len(out, indices.len());
+    for &index in indices {
+        put_len(out, index);
+    }
+}
+
+fn put_leaves(out: &mut Vec<u8>, leaves: &[MerkleLeaf]) {
+    put_len(out, leaves.len());
+    for leaf in leaves {
+        out.extend_from_slice(&leaf.data);
+    }
+    for leaf in leaves {
+        put_path(out, &leaf.path);
+    }
+}
+
+fn put_path(out: &mut Vec<u8>, path: &[MerklePathNode]) {
+    put_len(out, path.len());
+    for nodes in path.chunks(8) {
+        let turns = nodes.iter().enumerate().fold(0u8, |turns, (bit, node)| {
+            turns | (u8::from(!node.is_left_child) << bit)
+        });
+        out.push(turns);
+    }
+    for node in path {
+        out.extend_from_slice(&node.sibling_hash);
+    }
+}
+
+fn to_array<const N: usize>(bytes: &[u8]) -> [u8; N] {
+    let mut array = [0; N];
+    array.copy_from_slice(bytes);
+    array
+}
+
+/// The part of a slab not yet unpacked, and where in the slab it starts.
+struct Reader<'a> {
+    rest: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    const fn take(&mut self, n: usize) -> Result<&'a [u8], &'static str> {
+        if n > self.rest.len() {
+            return Err("packed archive ends early");
+        }
+        let (taken, rest) = self.rest.split_at(n);
+        self.rest = rest;
+        self.offset += n;
+        Ok(taken)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], &'static str> {
+        self.take(N).map(to_array)
+    }
+
+    fn len(&mut self) -> Result<usize, &'static str> {
+        usize::try_from(u64::from_le_bytes(self.array()?))
+            .map_err(|_| "packed archive length overflows")
+    }
+
+    /// The next length-prefixed bytes, as a view into `buffer`, the slab
+    /// being read.
+    fn shared(&mut self, buffer: &Arc<Vec<u8>>) -> Result<SharedBytes, &'static str> {
+        let len = self.len()?;
+        let start = self.offset;
+        self.take(len)?;
+        Ok(SharedBytes::slice(buffer, start, start + len))
+    }
+
+    /// The next `count` runs of `size` bytes, taken at once so that a count
+    /// the slab cannot hold fails before anything is allocated for it.
+    fn chunks(
+        &mut self,
+        count: usize,
+        size: usize,
+    ) -> Result<core::slice::ChunksExact<'a, u8>, &'static str> {
+        let len = count
+            .checked_mul(size)
+            .ok_or("packed archive length overflows")?;
+        Ok(self.take(len)?.chunks_exact(size))
+    }
+
+    fn indices(&mut self) -> Result<Vec<usize>, &'static str> {
+        let count = self.len()?;
+        self.chunks(count, 8)?
+            .map(|index| {
+                usize::try_from(u64::from_le_bytes(to_array(index)))
+                    .map_err(|_| "packed archive index overflows")
+            })
+            .collect()
+    }
+
+    fn leaves(&mut self) -> Result<Vec<MerkleLeaf>, &'static str> {
+        let count = self.len()?;
+        let blocks = self.chunks(count, BLOCK)?;
+        let mut leaves = Vec::with_capacity(count);
+        for block in blocks {
+            leaves.push(MerkleLeaf {
+                data: to_array(block),
+                path: self.path()?,
+            });
+        }
+        Ok(leaves)
+    }
+
+    fn path(&mut self) -> Result<Vec<MerklePathNode>, &'static str> {
+        let len = self.len()?;
+        let turns = self.take(len.div_ceil(8))?;
+        let hashes = self.chunks(len, 32)?;
+        Ok(hashes
+            .enumerate()
+            .map(|(i, hash)| MerklePathNode {
+                sibling_hash: to_array(hash),
+                is_left_child: turns[i / 8] & (1 << (i % 8)) == 0,
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,333 @@
+extern crate alloc;
+
+use alloc::{format, string::String, vec::Vec};
+use miniz_oxide::inflate::decompress_to_vec_zlib_with_limit;
+use risc0_zkvm::sha::{Impl, Sha256};
+use semver::Version;
+use serde::Deserialize;
+use zk_sca_guest_abi::{ScaError, ToolchainAttestation};
+
+use crate::cargo::{AuditOptions, CargoArchive, ResolvedDependencies, ResolvedDependency};
+use crate::toolchain::CargoVersionBounds;
+
+const SECTION: &str = ".dep-v0";
+/// The limit `cargo auditable`'s own reader applies to the decompressed JSON.
+const MAX_JSON_LEN: usize = 8 * 1024 * 1024;
+
+const SHT_NOBITS: u64 = 8;
+const LC_SEGMENT: u64 = 0x1;
+const LC_SEGMENT_64: u64 = 0x19;
+
+fn invalid(detail: impl Into<String>) -> (ScaError, String) {
+    (ScaError::InvalidAuditableBinary, detail.into())
+}
+
+/// The dependency list `cargo auditable` embeds, as of format 0.
+#[derive(Deserialize)]
+struct VersionInfo {
+    packages: Vec<Package>,
+}
+
+#[derive(Deserialize)]
+struct Package {
+    name: String,
+    version: String,
+    /// `crates.io`, `git`, `local`, `registry` or another source kind.
+    source: String,
+    /// Indices into `packages`.
+    #[serde(default)]
+    dependencies: Vec<usize>,
+    #[serde(default)]
+    root: bool,
+}
+
+/// Read the dependency list embedded in `binary` and return it with the
+/// binary's SHA-256.
+///
+/// `binary` must be an ELF, PE or Mach-O file with exactly one `.dep-v0`
+/// section. Every package not built from a local path is audited, whether it
+/// was compiled into the binary or only into its build scripts; dev-dependencies
+/// are never recorded. Options that select part of a workspace, or check files
+/// besides the binary, are rejected.
+pub fn read_auditable_binary(
+    binary: &[u8],
+    options: AuditOptions<'_>,
+) -> Result<(CargoArchive, [u8; 32]), (ScaError, String)> {
+    ensure_options_apply(options, "a binary")?;
+
+    let section = dependency_section(binary)?.ok_or_else(|| {
+        invalid(format!(
+            "binary is not an ELF, PE or Mach-O file with a `{SECTION}` section; \
+             was it built with `cargo auditable`?"
+        ))
+    })?;
+    let dependencies = read_dependency_list(section, SECTION)?;
+
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(Impl::hash_bytes(binary).as_bytes());
+    let cargo_archive = CargoArchive {
+        dependencies,
+        // A binary records nothing about the Cargo release or toolchain that built it.
+        cargo_versions: CargoVersionBounds::default().into_range()?,
+        toolchain: ToolchainAttestation::new(None, Vec::new()),
+    };
+    Ok((cargo_archive, hash))
+}
+
+/// Decompress and parse a `.dep-v0` section, returning every package not built
+/// from a local path, attributed to `provenance`.
+pub fn read_dependency_list(
+    section: &[u8],
+    provenance: &str,
+) -> Result<ResolvedDependencies, (ScaError, String)> {
+    let json = decompress_to_vec_zlib_with_limit(section, MAX_JSON_LEN).map_err(|e| {
+        invalid(format!(
+            "corrupt `{SECTION}` section in `{provenance}`: {e}"
+        ))
+    })?;
+    let info: VersionInfo = serde_json::from_slice(&json).map_err(|e| {
+        invalid(format!(
+            "malformed `{SECTION}` dependency list in `{provenance}`: {e}"
+        ))
+    })?;
+
+    let count = info.packages.len();
+    if info.packages.iter().filter(|p| p.root).count() != 1 {
+        return Err(invalid(format!(
+            "dependency list in `{provenance}` must have exactly one root package"
+        )));
+    }
+    let mut dependencies = Vec::new();
+    for pkg in &info.packages {
+        if let Some(&index) = pkg.dependencies.iter().find(|&&i| i >= count) {
+            return Err(invalid(format!(
+                "`{}` depends on package {index} of {count}",
+                pkg.name
+            )));
+        }
+        let version = Version::parse(&pkg.version)
+            .map_err(|e| invalid(format!("`{}` has invalid version: {e}", pkg.name)))?;
+        if pkg.source != "local" {
+            dependencies.push(ResolvedDependency {
+                name: pkg.name.clone(),
+                version,
+                provenance: provenance.into(),
+            });
+        }
+    }
+    Ok(dependencies)
+}
+
+/// Reject the options that select part of a workspace or check files besides
+/// those of `source`, which cannot be honoured without a source tree.
+pub fn ensure_options_apply(
+    options: AuditOptions<'_>,
+    source: &str,
+) -> Result<(), (ScaError, String)> {
+    let unsupported = if options.member_scope.is_some_and(|s| s.members().is_some()) {
+        "member scope"
+    } else if options.feature_selection.is_some() {
+        "feature selection"
+    } else if options.override_policy.is_some() {
+        "source override policy"
+    } else if options.verify_vendored_sources {
+        "vendored source verification"
+    } else if options.toolchain_policy.is_some() {
+        "toolchain policy"
+    } else {
+        return Ok(());
+    };
+    Err((
+        ScaError::InapplicableOption,
+        format!("{unsupported} cannot be applied to {source}"),
+    ))
+}
+
+/// Reads integers of one byte order at offsets into a binary.
+#[derive(Clone, Copy)]
+struct Reader<'a> {
+    data: &'a [u8],
+    big_endian: bool,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(self, at: u64, len: u64) -> Result<&'a [u8], (ScaError, String)> {
+        usize::try_from(at)
+            .ok()
+            .zip(usize::try_from(len).ok())
+            .and_then(|(at, len)| self.data.get(at..at.checked_add(len)?))
+            .ok_or_else(|| invalid("binary is truncated"))
+    }
+
+    fn array<const N: usize>(self, at: u64) -> Result<[u8; N], (ScaError, String)> {
+        let mut out = [0u8; N];
+        out.copy_from_slice(self.bytes(at, N as u64)?);
+        if self.big_endian {
+            out.reverse();
+        }
+        Ok(out)
+    }
+
+    fn u16(self, at: u64) -> Result<u64, (ScaError, String)> {
+        self.array(at).map(|b| u64::from(u16::from_le_bytes(b)))
+    }
+
+    fn u32(self, at: u64) -> Result<u64, (ScaError, String)> {
+        self.array(at).map(|b| u64::from(u32::from_le_bytes(b)))
+    }
+
+    fn u64(self, at: u64) -> Result<u64, (ScaError, String)> {
+        self.array(at).map(u64::from_le_bytes)
+    }
+}
+
+/// The contents of the `.dep-v0` section of an ELF, PE or Mach-O file.
+///
+/// `None` if `binary` is not such a file or has no such section; an error if
+/// it has several, or is truncated.
+pub fn dependency_section(binary: &[u8]) -> Result<Option<&[u8]>, (ScaError, String)> {
+    let little = |data| Reader {
+        data,
+        big_endian: false,
+    };
+    let big = |data| Reader {
+        data,
+        big_endian: true,
+    };
+    let sections = match binary.get(..4) {
+        Some([0x7f, b'E', b'L', b'F']) => {
+            let r = Reader {
+                data: binary,
+                big_endian: binary.get(5) == Some(&2),
+            };
+            elf_sections(r, binary.get(4) == Some(&2))?
+        }
+        Some([b'M', b'Z', ..]) => pe_sections(little(binary))?,
+        Some([0xcf, 0xfa, 0xed, 0xfe]) => macho_sections(little(binary), true)?,
+        Some([0xce, 0xfa, 0xed, 0xfe]) => macho_sections(little(binary), false)?,
+        Some([0xfe, 0xed, 0xfa, 0xcf]) => macho_sections(big(binary), true)?,
+        Some([0xfe, 0xed, 0xfa, 0xce]) => macho_sections(big(binary), false)?,
+        _ => Vec::new(),
+    };
+    match sections.as_slice() {
+        [] => Ok(None),
+        [section] => Ok(Some(section)),
+        _ => Err(invalid(format!("binary has several `{SECTION}` sections"))),
+    }
+}
+
+/// Every `.dep-v0` section of an ELF file.
+fn elf_sections(r: Reader<'_>, is_64: bool) -> Result<Vec<&[u8]>, (ScaError, String)> {
+    let (shoff, shentsize, shnum, shstrndx) = if is_64 {
+        (r.u64(0x28)?, r.u16(0x3a)?, r.u16(0x3c)?, r.u16(0x3e)?)
+    } else {
+        (r.u32(0x20)?, r.u16(0x2e)?, r.u16(0x30)?, r.u16(0x32)?)
+    };
+    // (name offset, type, file offset, size)
+    let header = |index: u64| -> Result<(u64, u64, u64, u64), (ScaError, String)> {
+        let base = shoff
+            .checked_add(index * shentsize)
+            .filter(|&base| base < r.data.len() as u64)
+            .ok_or_else(|| invalid("binary is truncated"))?;
+        if is_64 {
+            Ok((
+                r.u32(base)?,
+                r.u32(base + 4)?,
+                r.u64(base + 24)?,
+                r.u64(base + 32)?,
+            ))
+        } else {
+            Ok((
+                r.u32(base)?,
+                r.u32(base + 4)?,
+                r.u32(base + 16)?,
+                r.u32(base + 20)?,
+            ))
+        }
+    };
+    if shnum == 0 {
+        return Ok(Vec::new());
+    }
+    let (_, _, names_at, names_len) = header(shstrndx)?;
+    let names = r.bytes(names_at, names_len)?;
+
+    let mut found = Vec::new();
+    for index in 0..shnum {
+        let (name_offset, kind, at, len) = header(index)?;
+        let name = usize::try_from(name_offset)
+            .ok()
+            .and_then(|start| names.get(start..))
+            .map(|rest| rest.split(|&b| b == 0).next().unwrap_or_default())
+            .ok_or_else(|| invalid("ELF section name is out of bounds"))?;
+        if name == SECTION.as_bytes() {
+            if kind == SHT_NOBITS {
+                return Err(invalid(format!("`{SECTION}` section has no contents")));
+            }
+            found.push(r.bytes(at, len)?);
+        }
+    }
+    Ok(found)
+}
+
+/// Every `.dep-v0` section of a PE file; none if `MZ` starts some other file.
+fn pe_sections(r: Reader<'_>) -> Result<Vec<&[u8]>, (ScaError, String)> {
+    let Some(pe) = r
+        .u32(0x3c)
+        .ok()
+        .filter(|&pe| r.bytes(pe, 4).is_ok_and(|sig| sig == b"PE\0\0"))
+    else {
+        return Ok(Vec::new());
+    };
+    let count = r.u16(pe + 6)?;
+    let table = pe + 24 + r.u16(pe + 20)?;
+
+    let mut found = Vec::new();
+    for index in 0..count {
+        let base = table + index * 40;
+        if trim_nul(r.bytes(base, 8)?) == SECTION.as_bytes() {
+            // Raw data is padded to the file alignment; the virtual size is exact.
+            let len = r.u32(base + 8)?.min(r.u32(base + 16)?);
+            found.push(r.bytes(r.u32(base + 20)?, len)?);
+        }
+    }
+    Ok(found)
+}
+
+/// Every `.dep-v0` section of a thin Mach-O file.
+fn macho_sections(r: Reader<'_>, is_64: bool) -> Result<Vec<&[u8]>, (ScaError, String)> {
+    let (segment_cmd, first_cmd, nsects_at, sects_at, sect_len) = if is_64 {
+        (LC_SEGMENT_64, 32, 64, 72, 80)
+    } else {
+        (LC_SEGMENT, 28, 48, 56, 68)
+    };
+    let ncmds = r.u32(16)?;
+
+    let mut found = Vec::new();
+    let mut cmd = first_cmd;
+    for _ in 0..ncmds {
+        let cmdsize = r.u32(cmd + 4)?;
+        if cmdsize < 8 {
+            return Err(invalid("Mach-O load command is malformed"));
+        }
+        if r.u32(cmd)? == segment_cmd {
+            for index in 0..r.u32(cmd + nsects_at)? {
+                let base = cmd + sects_at + index * sect_len;
+                if trim_nul(r.bytes(base, 16)?) == SECTION.as_bytes() {
+                    let (len, at) = if is_64 {
+                        (r.u64(base + 40)?, r.u32(base + 48)?)
+                    } else {
+                        (r.u32(base + 36)?, r.u32(base + 40)?)
+                    };
+                    found.push(r.bytes(at, len)?);
+                }
+            }
+        }
+        cmd += cmdsize;
+    }
+    Ok(found)
+}
+
+fn trim_nul(name: &[u8]) -> &[u8] {
+    let end = name.iter().position(|&b| b == 0).unwrap_or(name.len());
+    &name[..end]
+}
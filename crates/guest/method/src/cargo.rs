@@ -8,12 +8,24 @@ use alloc::{
     vec::Vec,
 };
 use cargo_lock::{Lockfile, ResolveVersion};
-use cargo_manifest::{Dependency as ManifestDep, Manifest};
+use cargo_manifest::{Dependency as ManifestDep, Edition, Manifest, MaybeInherited};
 use core::hash::Hash;
 use hashbrown::{HashMap, HashSet};
 use semver::{Version, VersionReq};
-use zk_sca_guest_abi::ScaError;
-use zk_sca_guest_abi_utils::{ValidPartialArchive, ValidatedFile};
+use zk_sca_guest_abi::{
+    FeatureSelection, MemberScope, MemberToolchain, OverridePolicy, PackageManagerRange, ScaError,
+    ToolchainAttestation, ToolchainPolicy,
+};
+use zk_sca_guest_abi_utils::{
+    ArchivePath, ValidPartialArchive, ValidatedFile, is_cargo_dependency_file,
+};
+
+use crate::toolchain::{
+    CargoVersionBounds, ToolchainFile, ensure_toolchain_is_permitted, min_cargo_for_edition,
+    min_cargo_for_lockfile, parse_rust_version, parse_toolchain_file, pinned_releases,
+    rust_edition,
+};
+use crate::vendor::{CHECKSUM_FILE, parse_normalized_manifest, verify_vendor_directory};
 
 /// Fully‑resolved, version‑pinned dependency.
 #[derive(Debug, Clone)]
@@ -27,45 +39,125 @@ pub struct ResolvedDependency {
 /// Flat list produced by [`validate_cargo_archive`].
 pub type ResolvedDependencies = Vec<ResolvedDependency>;
 
+/// What [`validate_cargo_archive`] establishes about a Cargo archive.
+#[derive(Debug, Clone)]
+pub struct CargoArchive {
+    pub dependencies: ResolvedDependencies,
+    /// The Cargo releases that the lockfile format, the member editions and
+    /// `rust-version`s, and any `rust-toolchain` pin are consistent with.
+    pub cargo_versions: PackageManagerRange,
+    /// The channel, editions and `rust-version`s the workspace declares.
+    pub toolchain: ToolchainAttestation,
+}
+
+/// Which part of the dependency graph [`validate_cargo_archive`] returns.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AuditOptions<'a> {
+    /// Start from these workspace members instead of every path package, and
+    /// whether to follow the edges they declare only as dev-dependencies.
+    pub member_scope: Option<&'a MemberScope>,
+    /// Only follow the optional edges of workspace members that these features activate.
+    pub feature_selection: Option<&'a FeatureSelection>,
+    /// Which `[patch]`, `[replace]` and source replacement entries are accepted.
+    pub override_policy: Option<&'a OverridePolicy>,
+    /// Require every external package of the workspace lockfile to be vendored
+    /// and to match its `.cargo-checksum.json` and lockfile checksum.
+    pub verify_vendored_sources: bool,
+    /// Bounds on the pinned toolchain and the member editions.
+    pub toolchain_policy: Option<&'a ToolchainPolicy>,
+}
+
+impl AuditOptions<'_> {
+    /// `true` if every package of every lockfile is audited.
+    fn is_exhaustive(&self) -> bool {
+        self.member_scope
+            .is_none_or(|scope| scope.members().is_none() && !scope.excludes_dev_dependencies())
+            && self.feature_selection.is_none()
+    }
+
+    /// `true` if edges declared only as dev-dependencies are not followed.
+    pub fn excludes_dev_dependencies(&self) -> bool {
+        self.member_scope
+            .is_some_and(MemberScope::excludes_dev_dependencies)
+    }
+}
+
 /// Validate all Cargo metadata contained in a Merklized TAR archive and
 /// return a flattened list of fully-resolved external dependencies.
 ///
 /// Invariants enforced:
 /// 1. Exactly one Cargo workspace--implicit or explicit--is present.
 /// 2. The workspace root has a single `Cargo.lock`.
-/// 3. Every direct dependency declared in any `Cargo.toml`—including build/dev
-///    deps and rename syntax—is satisfied by at least one package version in
-///    the workspace lockfile.
+/// 3. Every workspace member's own `Cargo.lock` entry lists exactly the direct
+///    dependencies its `Cargo.toml` declares—including build/dev/target deps,
+///    rename syntax and workspace inheritance—each at a version matching the
+///    declared requirement.
 /// 4. Every package listed in every `Cargo.lock` is reachable from at least one
 ///    workspace member via the dependency graph encoded in that lockfile.
-/// 5. All lockfiles are version 3 or 4 (older formats may lack required metadata).
+/// 5. All lockfiles are version 1 to 4, and every dependency reference in a
+///    version 1 or 2 lockfile names exactly one of its packages.
+/// 6. Every manifest, lockfile and `.cargo/config.toml` in the archive is
+///    materialized, and the source overrides they declare satisfy the policy.
+/// 7. If requested, every external package of the workspace lockfile is
+///    vendored, and each vendored crate matches its `.cargo-checksum.json`.
+/// 8. Some Cargo release is consistent with the workspace lockfile format,
+///    every member's edition and `rust-version`, and the `rust-toolchain`
+///    pin that applies to the workspace root. If a toolchain policy is given,
+///    the pin and the editions satisfy it.
+///
+/// Files of the crate directories that hold a `.cargo-checksum.json`, inside
+/// the directories that a `.cargo/config.toml` replaces a source with, are
+/// vendored packages, not part of the workspace. Each must be a package the
+/// workspace lockfile locks from outside the workspace, and no other manifest
+/// may lie in a vendor directory.
 ///
-/// On success, returns `ResolvedDependencies`.
+/// Packages that `[patch]` or `[replace]` provide are audited even when the
+/// lockfile records them as path packages.
+///
+/// Unless `options` narrow the audit, every external package of every
+/// lockfile is returned. Otherwise only the external packages reachable in the
+/// workspace lockfile through the edges that `options` keep are.
+///
+/// On success, returns the `ResolvedDependencies` with the consistent Cargo
+/// releases and the toolchain the workspace declares.
 pub fn validate_cargo_archive(
     archive: &ValidPartialArchive,
-) -> Result<ResolvedDependencies, (ScaError, String)> {
+    options: AuditOptions<'_>,
+) -> Result<CargoArchive, (ScaError, String)> {
+    ensure_dependency_files_are_materialized(archive)?;
+
+    let configs: Vec<CargoConfig> = archive
+        .files
+        .iter()
+        .filter(|vf| is_cargo_config(&vf.header.name))
+        .map(parse_config_file)
+        .collect::<Result<_, _>>()?;
+    let vendor = VendorLayout::new(archive, &configs);
+
     let manifests: Vec<ManifestInfo> = archive
         .files
         .iter()
-        .filter(|vf| vf.header.name.ends_with("Cargo.toml"))
+        .filter(|vf| vf.header.name.ends_with("Cargo.toml") && !vendor.is_vendored(&vf.header.name))
         .map(parse_manifest_file)
         .collect::<Result<_, _>>()?;
+    vendor.ensure_holds_no_workspace_manifest(&manifests)?;
 
     let workspace_root_manifest_path = ensure_single_workspace(&manifests)?;
 
     let locks: Vec<LockInfo> = archive
         .files
         .iter()
-        .filter(|vf| vf.header.name.ends_with("Cargo.lock"))
+        .filter(|vf| vf.header.name.ends_with("Cargo.lock") && !vendor.is_vendored(&vf.header.name))
         .map(parse_lock_file)
         .collect::<Result<_, _>>()?;
     let manifest_by_path: HashMap<String, ManifestInfo> =
         map_by(manifests.clone(), |m| m.path.clone());
     let lock_by_path: HashMap<String, LockInfo> = map_by(locks, |l| l.path.clone());
 
-    // Member crates must not have their own lockfile.
+    // Member crates must not have their own lockfile. An implicit workspace
+    // root, such as the package of a `.crate` file, has the workspace's.
     for (path, manifest) in &manifest_by_path {
-        if !manifest.has_workspace {
+        if !manifest.has_workspace && path != workspace_root_manifest_path {
             let own_lock = to_lock_path(path);
             if lock_by_path.contains_key(&own_lock) {
                 return Err((
@@ -84,10 +176,25 @@ pub fn validate_cargo_archive(
             ),
         )
     })?;
+    let workspace_root = &manifest_by_path[workspace_root_manifest_path];
+    vendor.ensure_crates_are_locked(archive, workspace_lock)?;
 
-    // Ensure that every declared dep's requirements are met by the lockfile.
+    // Only the workspace root's `[patch]` and `[replace]` take effect; configs always do.
+    let mut overrides = workspace_root.overrides.clone();
+    for config in configs.iter().filter(|c| !vendor.is_vendored(&c.path)) {
+        overrides.extend(config.overrides.iter().cloned());
+    }
+    if let Some(policy) = options.override_policy {
+        ensure_overrides_are_permitted(&overrides, policy)?;
+    }
+    let overridden: HashSet<&str> = overrides
+        .iter()
+        .filter_map(|o| o.package.as_deref())
+        .collect();
+
+    // Ensure that each member's lockfile entry matches its declared deps edge for edge.
     for manifest in manifest_by_path.values() {
-        ensure_declared_reqs_are_satisfied(manifest, workspace_lock)?;
+        ensure_member_lock_entry_matches(manifest, workspace_root, workspace_lock)?;
     }
 
     // Ensure that no external deps in lockfile are unreachable by a declared dep.
@@ -95,39 +202,165 @@ pub fn validate_cargo_archive(
         ensure_lock_graph_is_reachable(lock)?;
     }
 
-    // Produce flattened list of external deps.
+    if options.verify_vendored_sources {
+        ensure_packages_are_vendored(archive, &vendor.dirs, workspace_lock)?;
+    }
+
+    let (cargo_versions, toolchain) =
+        inspect_toolchain(archive, &manifest_by_path, workspace_root, workspace_lock)?;
+    if let Some(policy) = options.toolchain_policy {
+        ensure_toolchain_is_permitted(&toolchain, policy)?;
+    }
+
+    let dependencies = if options.is_exhaustive() {
+        collect_all_dependencies(&lock_by_path, &overridden)
+    } else {
+        collect_reachable_dependencies(
+            options,
+            &overridden,
+            &manifest_by_path,
+            workspace_root,
+            workspace_lock,
+        )?
+    };
+
+    Ok(CargoArchive {
+        dependencies,
+        cargo_versions,
+        toolchain,
+    })
+}
+
+/// Parse a `Cargo.lock` found without its workspace and return its external
+/// packages. With no manifests to consult, path packages are taken to be the
+/// workspace's own code.
+pub fn read_lockfile_dependencies(
+    vf: &ValidatedFile,
+) -> Result<ResolvedDependencies, (ScaError, String)> {
+    let lock = parse_lock_file(vf)?;
+    Ok(lock
+        .packages
+        .into_iter()
+        .filter(|pkg| !pkg.is_path)
+        .map(|pkg| ResolvedDependency {
+            name: pkg.name,
+            version: pkg.version,
+            provenance: lock.path.clone(),
+        })
+        .collect())
+}
+
+/// Produce flattened list of external deps.
+fn collect_all_dependencies(
+    lock_by_path: &HashMap<String, LockInfo>,
+    overridden: &HashSet<&str>,
+) -> ResolvedDependencies {
     let mut resolved: ResolvedDependencies = Vec::new();
     for lock in lock_by_path.values() {
-        for (pkg, ver) in &lock.pkgs {
-            if lock.path_pkgs.contains(pkg) {
+        for pkg in &lock.packages {
+            if !is_audited(pkg, overridden) {
                 continue;
             }
             resolved.push(ResolvedDependency {
-                name: pkg.clone(),
-                version: ver.clone(),
+                name: pkg.name.clone(),
+                version: pkg.version.clone(),
                 provenance: lock.path.clone(),
             });
         }
     }
+    resolved
+}
 
-    Ok(resolved)
+/// The manifest table a dependency was declared in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DepKind {
+    Normal,
+    Build,
+    Dev,
+}
+
+impl DepKind {
+    const fn table(self) -> &'static str {
+        match self {
+            Self::Normal => "dependencies",
+            Self::Build => "build-dependencies",
+            Self::Dev => "dev-dependencies",
+        }
+    }
+}
+
+/// A direct dependency as written in a `Cargo.toml`.
+#[derive(Debug, Clone)]
+struct DeclaredDep {
+    /// Key the dependency is declared under; differs from `name` when renamed.
+    key: String,
+    /// Canonical package name.
+    name: String,
+    req: VersionReq,
+    kind: DepKind,
+    /// `{ workspace = true }`: name and requirement come from the workspace root.
+    inherited: bool,
+    /// `optional = true`: only built when a feature activates it.
+    optional: bool,
+    /// Features enabled on the dependency by this declaration.
+    features: Vec<String>,
+    default_features: bool,
 }
 
 #[derive(Debug, Clone)]
 struct ManifestInfo {
     path: String,
-    deps: HashMap<String, VersionReq>,
+    package_name: Option<String>,
+    package_version: Option<Version>,
+    edition: Option<MaybeInherited<Edition>>,
+    rust_version: Option<MaybeInherited<String>>,
+    deps: Vec<DeclaredDep>,
+    /// The `[features]` table.
+    features: BTreeMap<String, Vec<String>>,
+    /// `[patch]` and `[replace]` entries.
+    overrides: Vec<SourceOverride>,
     has_workspace: bool,
     workspace_members: Option<Vec<String>>,
     workspace_excludes: Option<Vec<String>>,
+    /// `[workspace.dependencies]`, keyed by declaration key.
+    workspace_deps: HashMap<String, DeclaredDep>,
+    /// `[workspace.package]` values that members can inherit.
+    workspace_edition: Option<Edition>,
+    workspace_rust_version: Option<String>,
+}
+
+/// A redirection of where Cargo takes dependency sources from.
+#[derive(Debug, Clone)]
+struct SourceOverride {
+    /// File that declares it.
+    path: String,
+    /// Table it is declared in: `patch`, `replace`, `source` or `paths`.
+    table: &'static str,
+    /// The package it provides. `None` for `[source]` and `paths`, which
+    /// redirect whole sources.
+    package: Option<String>,
+}
+
+/// A `name version` pair identifying one lockfile package.
+type PkgId = (String, Version);
+
+#[derive(Debug, Clone)]
+struct LockPackage {
+    name: String,
+    version: Version,
+    /// No `source`: a workspace member or other path dependency.
+    is_path: bool,
+    /// SHA-256 of the `.crate` file, as lowercase hex. `None` for path and git packages.
+    checksum: Option<String>,
+    deps: Vec<PkgId>,
 }
 
 #[derive(Debug, Clone)]
 struct LockInfo {
     path: String,
-    pkgs: HashMap<String, Version>,
-    deps: HashMap<String, Vec<String>>,
-    path_pkgs: HashSet<String>,
+    /// Format version, 1 to 4.
+    version: u32,
+    packages: Vec<LockPackage>,
 }
 
 fn ensure_single_workspace<'a>(
@@ -182,27 +415,26 @@ fn workspace_owns_manifest(root: &ManifestInfo, manifest: &ManifestInfo) -> bool
     if !manifest.path.starts_with(root_dir) {
         return false;
     }
+    // Members and excludes are normalized against the root, so `./a` and
+    // `a/` name the same directory as `a`.
+    let Ok(root_path) = ArchivePath::parse(root_dir) else {
+        return false;
+    };
+    let names = |dir: &str| {
+        root_path
+            .join(dir)
+            .is_ok_and(|dir| manifest.path.starts_with(dir.as_str()))
+    };
 
     // Respect `exclude` first.
     if let Some(excludes) = &root.workspace_excludes {
-        for excl in excludes {
-            let excl_prefix = format!("{}{}", root_dir, excl.trim_end_matches('/'));
-            if manifest.path.starts_with(&excl_prefix) {
-                return false;
-            }
+        if excludes.iter().any(|excl| names(excl)) {
+            return false;
         }
     }
 
     match &root.workspace_members {
-        Some(members) if !members.is_empty() => {
-            for member in members {
-                let member_prefix = format!("{}{}", root_dir, member.trim_end_matches('/'));
-                if manifest.path.starts_with(&member_prefix) {
-                    return true;
-                }
-            }
-            false
-        }
+        Some(members) if !members.is_empty() => members.iter().any(|member| names(member)),
         // `[workspace]` with an empty `members = []` list owns nothing.
         Some(_) => false,
         // No `members` key is wildcard: owns every crate under root not excluded.
@@ -225,17 +457,29 @@ fn parse_manifest_file(vf: &ValidatedFile) -> Result<ManifestInfo, (ScaError, St
             format!("Failed to parse `{}`: {e}", vf.header.name),
         )
     })?;
+    // `cargo_manifest` has no `[replace]` field, so it is read from the raw table.
+    let raw: toml::Table = text.parse().map_err(|e| {
+        (
+            ScaError::ManifestParseError,
+            format!("Failed to parse `{}`: {e}", vf.header.name),
+        )
+    })?;
 
-    // Collect all direct requirements (including build & dev) using canonical package name.
-    let mut deps = HashMap::new();
+    // Collect all direct requirements (including build, dev & target-specific), keeping the kind.
+    let mut deps = Vec::new();
     if let Some(tbl) = manifest.dependencies.clone() {
-        merge_deps(&mut deps, tbl);
+        merge_deps(&mut deps, tbl, DepKind::Normal);
     }
     if let Some(tbl) = manifest.build_dependencies.clone() {
-        merge_deps(&mut deps, tbl);
+        merge_deps(&mut deps, tbl, DepKind::Build);
     }
     if let Some(tbl) = manifest.dev_dependencies.clone() {
-        merge_deps(&mut deps, tbl);
+        merge_deps(&mut deps, tbl, DepKind::Dev);
+    }
+    for target in manifest.target.clone().unwrap_or_default().into_values() {
+        merge_deps(&mut deps, target.dependencies, DepKind::Normal);
+        merge_deps(&mut deps, target.build_dependencies, DepKind::Build);
+        merge_deps(&mut deps, target.dev_dependencies, DepKind::Dev);
     }
 
     // Workspace membership & exclusions, preserving Cargo semantics.
@@ -245,16 +489,46 @@ fn parse_manifest_file(vf: &ValidatedFile) -> Result<ManifestInfo, (ScaError, St
         (members_opt, excludes_opt)
     });
 
+    let mut workspace_deps = Vec::new();
+    if let Some(tbl) = manifest
+        .workspace
+        .as_ref()
+        .and_then(|ws| ws.dependencies.clone())
+    {
+        merge_deps(&mut workspace_deps, tbl, DepKind::Normal);
+    }
+
+    let workspace_package = manifest
+        .workspace
+        .as_ref()
+        .and_then(|ws| ws.package.as_ref());
+    let package = manifest.package.as_ref();
+    let package_version = package
+        .and_then(|p| p.version.as_ref())
+        .and_then(|v| match v {
+            MaybeInherited::Local(v) => Version::parse(v).ok(),
+            MaybeInherited::Inherited { .. } => None,
+        });
+
     Ok(ManifestInfo {
-        path: vf.header.name.clone(),
+        path: vf.header.name.to_string(),
+        package_name: package.map(|p| p.name.clone()),
+        package_version,
+        edition: package.and_then(|p| p.edition.clone()),
+        rust_version: package.and_then(|p| p.rust_version.clone()),
         deps,
+        features: manifest.features.clone().unwrap_or_default(),
+        overrides: manifest_overrides(&vf.header.name, &manifest, &raw),
         has_workspace: manifest.workspace.is_some(),
         workspace_members: members_opt,
         workspace_excludes: excludes_opt,
+        workspace_deps: map_by(workspace_deps, |d| d.key.clone()),
+        workspace_edition: workspace_package.and_then(|p| p.edition),
+        workspace_rust_version: workspace_package.and_then(|p| p.rust_version.clone()),
     })
 }
 
-/// Parse a `Cargo.lock` and produce maps for packages and their dependencies.
+/// Parse a `Cargo.lock` into its package entries and their dependency edges.
 fn parse_lock_file(vf: &ValidatedFile) -> Result<LockInfo, (ScaError, String)> {
     let text = core::str::from_utf8(&vf.bytes).map_err(|_| {
         (
@@ -263,95 +537,693 @@ fn parse_lock_file(vf: &ValidatedFile) -> Result<LockInfo, (ScaError, String)> {
         )
     })?;
 
-    let lockfile: Lockfile = text.parse().map_err(|e| {
+    // `cargo_lock` resolves v3 and v4 lockfiles; older ones are reconstructed here.
+    let (version, packages) = match text.parse::<Lockfile>() {
+        Ok(lockfile) if matches!(lockfile.version, ResolveVersion::V3 | ResolveVersion::V4) => {
+            let version = if lockfile.version == ResolveVersion::V3 {
+                3
+            } else {
+                4
+            };
+            let packages = lockfile
+                .packages
+                .into_iter()
+                .map(|pkg| LockPackage {
+                    name: pkg.name.to_string(),
+                    version: pkg.version,
+                    is_path: pkg.source.is_none(),
+                    checksum: pkg.checksum.map(|c| c.to_string()),
+                    deps: pkg
+                        .dependencies
+                        .into_iter()
+                        .map(|d| (d.name.to_string(), d.version))
+                        .collect(),
+                })
+                .collect();
+            (version, packages)
+        }
+        Ok(_) => parse_legacy_lockfile(&vf.header.name, text)?,
+        Err(e) => match parse_legacy_lockfile(&vf.header.name, text) {
+            Err((ScaError::UnsupportedLockfileVersion, _)) => {
+                return Err((
+                    ScaError::LockfileParseError,
+                    format!("Failed to parse `{}`: {e}", vf.header.name),
+                ));
+            }
+            legacy => legacy?,
+        },
+    };
+
+    Ok(LockInfo {
+        path: vf.header.name.to_string(),
+        version,
+        packages,
+    })
+}
+
+/// Parse the format version and packages of a version 1 or 2 `Cargo.lock`.
+///
+/// Version 1 keeps checksums in the `[metadata]` table. Version 2 stores them
+/// inline but abbreviates dependency references to `name` or `name version`
+/// wherever Cargo judged that unambiguous, so each reference is resolved
+/// against the listed packages and must match exactly one of them.
+fn parse_legacy_lockfile(
+    path: &str,
+    text: &str,
+) -> Result<(u32, Vec<LockPackage>), (ScaError, String)> {
+    fn str_field<'a>(pkg: &'a toml::Value, key: &str) -> Option<&'a str> {
+        pkg.get(key).and_then(toml::Value::as_str)
+    }
+
+    let malformed = |detail: &str| {
         (
             ScaError::LockfileParseError,
-            format!("Failed to parse `{}`: {e}", vf.header.name),
+            format!("Failed to parse `{path}`: {detail}"),
         )
-    })?;
+    };
+    let lockfile = text
+        .parse::<toml::Table>()
+        .map_err(|e| malformed(&e.to_string()))?;
 
-    // Require Cargo.lock v3 or v4 because v1/v2 may lack transitive deps’ source/checksum.
-    if lockfile.version != ResolveVersion::V3 && lockfile.version != ResolveVersion::V4 {
-        return Err((
-            ScaError::UnsupportedLockfileVersion,
-            "Unsupported Cargo.lock version (expected 3 or 4)".to_string(),
-        ));
+    let declared = match lockfile.get("version").map(toml::Value::as_integer) {
+        None => None,
+        Some(Some(v @ (1 | 2))) => Some(v),
+        Some(_) => {
+            return Err((
+                ScaError::UnsupportedLockfileVersion,
+                "Unsupported Cargo.lock version (expected 1 to 4)".to_string(),
+            ));
+        }
+    };
+
+    let mut raw = Vec::new();
+    let entries = lockfile.get("package").and_then(toml::Value::as_array);
+    for pkg in entries.into_iter().flatten() {
+        let name = str_field(pkg, "name").ok_or_else(|| malformed("package without a name"))?;
+        let version = str_field(pkg, "version")
+            .and_then(|v| Version::parse(v).ok())
+            .ok_or_else(|| malformed(&format!("package `{name}` has no valid version")))?;
+        let deps: Vec<&str> = pkg
+            .get("dependencies")
+            .and_then(toml::Value::as_array)
+            .into_iter()
+            .flatten()
+            .map(|d| {
+                d.as_str()
+                    .ok_or_else(|| malformed("dependency is not a string"))
+            })
+            .collect::<Result<_, _>>()?;
+        raw.push(LegacyLockPackage {
+            name,
+            version,
+            source: str_field(pkg, "source"),
+            checksum: str_field(pkg, "checksum"),
+            deps,
+        });
     }
 
-    let mut pkgs = HashMap::with_capacity(lockfile.packages.len());
-    let mut deps: HashMap<String, Vec<String>> = HashMap::new();
-    let mut path_pkgs: HashSet<String> = HashSet::new();
+    let metadata = lockfile.get("metadata").and_then(toml::Value::as_table);
+    let mut packages = Vec::with_capacity(raw.len());
+    for pkg in &raw {
+        let (name, version) = (pkg.name, &pkg.version);
+        let checksum = pkg
+            .checksum
+            .or_else(|| {
+                let key = format!("checksum {name} {version} ({})", pkg.source?);
+                metadata?.get(&key)?.as_str()
+            })
+            .filter(|c| *c != "<none>")
+            .map(ToOwned::to_owned);
 
-    for pkg in lockfile.packages {
-        let name = pkg.name.to_string();
-        pkgs.insert(name.clone(), pkg.version.clone());
-        let dep_names = pkg
-            .dependencies
-            .into_iter()
-            .map(|d| d.name.to_string())
-            .collect::<Vec<_>>();
-        deps.insert(name.clone(), dep_names);
-        if pkg.source.is_none() {
-            path_pkgs.insert(name);
+        let mut resolved = Vec::with_capacity(pkg.deps.len());
+        for dep in &pkg.deps {
+            let candidates = match_legacy_reference(&raw, dep)
+                .map_err(|e| malformed(&format!("dependency `{dep}`: {e}")))?;
+            match candidates.as_slice() {
+                [c] => resolved.push((c.name.to_owned(), c.version.clone())),
+                _ => {
+                    return Err((
+                        ScaError::UnresolvableLockfileDependency,
+                        format!(
+                            "`{name} {version}` in `{path}` depends on `{dep}`, which matches {} packages",
+                            candidates.len()
+                        ),
+                    ));
+                }
+            }
         }
+
+        packages.push(LockPackage {
+            name: name.to_owned(),
+            version: version.clone(),
+            is_path: pkg.source.is_none(),
+            checksum,
+            deps: resolved,
+        });
     }
 
-    Ok(LockInfo {
-        path: vf.header.name.clone(),
-        pkgs,
-        deps,
-        path_pkgs,
-    })
+    // Cargo itself reads a lockfile without `version` as V2 once any package
+    // carries an inline checksum.
+    let version = match declared {
+        Some(v) => u32::try_from(v).unwrap_or_default(),
+        None if raw.iter().any(|p| p.checksum.is_some()) => 2,
+        None => 1,
+    };
+    Ok((version, packages))
+}
+
+/// A `[[package]]` entry of a version 1 or 2 `Cargo.lock`, as written.
+struct LegacyLockPackage<'a> {
+    name: &'a str,
+    version: Version,
+    source: Option<&'a str>,
+    checksum: Option<&'a str>,
+    /// `name [version] [(source)]` references.
+    deps: Vec<&'a str>,
 }
 
-/// Checks that every declared dependency requirement in `manifest` is
-/// satisfied by some package version in `lock`.
-fn ensure_declared_reqs_are_satisfied(
+/// Returns every package that the dependency reference `dep` could denote.
+fn match_legacy_reference<'p, 'a>(
+    packages: &'p [LegacyLockPackage<'a>],
+    dep: &str,
+) -> Result<Vec<&'p LegacyLockPackage<'a>>, semver::Error> {
+    let mut parts = dep.splitn(3, ' ');
+    let name = parts.next().unwrap_or_default();
+    let version = parts.next().map(Version::parse).transpose()?;
+    let source = parts
+        .next()
+        .map(|s| s.trim_start_matches('(').trim_end_matches(')'));
+    Ok(packages
+        .iter()
+        .filter(|p| {
+            p.name == name
+                && version.as_ref().is_none_or(|v| p.version == *v)
+                && source.is_none_or(|s| p.source == Some(s))
+        })
+        .collect())
+}
+
+/// Checks that the lockfile entry of the member defined by `manifest` has
+/// exactly one dependency edge per declared dependency, each at a version
+/// satisfying the declared requirement, and no edges that the manifest does
+/// not declare.
+fn ensure_member_lock_entry_matches(
     manifest: &ManifestInfo,
+    workspace_root: &ManifestInfo,
     lock: &LockInfo,
 ) -> Result<(), (ScaError, String)> {
-    for (pkg, req) in &manifest.deps {
-        match lock.pkgs.get(pkg) {
-            Some(ver) if req.matches(ver) => {}
-            _ => {
-                return Err((
-                    ScaError::ManifestLockMismatch,
+    // Virtual manifests have no lockfile entry of their own.
+    let Some(name) = &manifest.package_name else {
+        return Ok(());
+    };
+    let entry = find_member_entry(manifest, name, lock)?;
+    let declared = resolve_inherited_deps(manifest, workspace_root)?;
+
+    for dep in &declared {
+        if !entry
+            .deps
+            .iter()
+            .any(|(n, v)| n == &dep.name && dep.req.matches(v))
+        {
+            return Err((
+                ScaError::ManifestLockMismatch,
+                format!(
+                    "`{}` declares {} `{}` {} but its entry in {} has no matching edge",
+                    manifest.path,
+                    dep.kind.table(),
+                    dep.key,
+                    dep.req,
+                    lock.path
+                ),
+            ));
+        }
+    }
+
+    for (dep_name, dep_version) in &entry.deps {
+        if !declared
+            .iter()
+            .any(|d| &d.name == dep_name && d.req.matches(dep_version))
+        {
+            return Err((
+                ScaError::ManifestLockMismatch,
+                format!(
+                    "entry for `{name}` in {} depends on `{dep_name}` {dep_version}, which `{}` does not declare",
+                    lock.path, manifest.path
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Locates the single path package in `lock` that corresponds to `manifest`.
+fn find_member_entry<'a>(
+    manifest: &ManifestInfo,
+    name: &str,
+    lock: &'a LockInfo,
+) -> Result<&'a LockPackage, (ScaError, String)> {
+    let candidates: Vec<&LockPackage> = lock
+        .packages
+        .iter()
+        .filter(|p| p.is_path && p.name == name)
+        .filter(|p| {
+            manifest
+                .package_version
+                .as_ref()
+                .is_none_or(|v| v == &p.version)
+        })
+        .collect();
+
+    match candidates.as_slice() {
+        [entry] => Ok(entry),
+        [] => Err((
+            ScaError::ManifestLockMismatch,
+            format!(
+                "`{}` defines package `{name}` but {} has no entry for it",
+                manifest.path, lock.path
+            ),
+        )),
+        _ => Err((
+            ScaError::ManifestLockMismatch,
+            format!(
+                "`{}` defines package `{name}` but {} has several entries for it",
+                manifest.path, lock.path
+            ),
+        )),
+    }
+}
+
+/// Replaces `{ workspace = true }` declarations with the name and requirement
+/// from the workspace root's `[workspace.dependencies]`.
+fn resolve_inherited_deps(
+    manifest: &ManifestInfo,
+    workspace_root: &ManifestInfo,
+) -> Result<Vec<DeclaredDep>, (ScaError, String)> {
+    manifest
+        .deps
+        .iter()
+        .map(|dep| {
+            if !dep.inherited {
+                return Ok(dep.clone());
+            }
+            let base = workspace_root.workspace_deps.get(&dep.key).ok_or_else(|| {
+                (
+                    ScaError::ManifestParseError,
                     format!(
-                        "Requirement `{}` {} not satisfied by {}",
-                        pkg, req, lock.path
+                        "`{}` inherits `{}` but `{}` has no such workspace dependency",
+                        manifest.path, dep.key, workspace_root.path
                     ),
-                ));
+                )
+            })?;
+            Ok(DeclaredDep {
+                key: dep.key.clone(),
+                name: base.name.clone(),
+                req: base.req.clone(),
+                kind: dep.kind,
+                inherited: true,
+                optional: dep.optional,
+                features: base.features.iter().chain(&dep.features).cloned().collect(),
+                default_features: base.default_features,
+            })
+        })
+        .collect()
+}
+
+/// Collects the external packages reachable in `lock` from the members named
+/// in the scope, or from every path package without one. Edges of path
+/// packages are only followed if [`follows_edge`] keeps them.
+fn collect_reachable_dependencies(
+    options: AuditOptions<'_>,
+    overridden: &HashSet<&str>,
+    manifest_by_path: &HashMap<String, ManifestInfo>,
+    workspace_root: &ManifestInfo,
+    lock: &LockInfo,
+) -> Result<ResolvedDependencies, (ScaError, String)> {
+    let mut members: HashMap<&str, (&ManifestInfo, Vec<DeclaredDep>)> = HashMap::new();
+    for manifest in manifest_by_path.values() {
+        if let Some(name) = &manifest.package_name {
+            let declared = resolve_inherited_deps(manifest, workspace_root)?;
+            members.insert(name.as_str(), (manifest, declared));
+        }
+    }
+
+    let mut stack: Vec<&LockPackage> = Vec::new();
+    if let Some(roots) = options.member_scope.and_then(MemberScope::members) {
+        for name in roots {
+            let (manifest, _) = members.get(name.as_str()).ok_or_else(|| {
+                (
+                    ScaError::UnknownWorkspaceMember,
+                    format!("`{name}` is not a package of this workspace"),
+                )
+            })?;
+            stack.push(find_member_entry(manifest, name, lock)?);
+        }
+    } else {
+        stack.extend(lock.packages.iter().filter(|p| p.is_path));
+    }
+
+    let activated = match options.feature_selection {
+        Some(selection) => {
+            let roots: Vec<&str> = stack.iter().map(|p| p.name.as_str()).collect();
+            let mut resolver = FeatureResolver::new(&members, options.excludes_dev_dependencies());
+            resolver.resolve(selection, &roots)?;
+            Some(resolver.activated)
+        }
+        None => None,
+    };
+    let none_activated = HashSet::new();
+    let exclude_dev_dependencies = options.excludes_dev_dependencies();
+
+    let by_id = index_packages(lock);
+    let mut seen: HashSet<(&str, &Version)> = HashSet::new();
+    let mut resolved: ResolvedDependencies = Vec::new();
+
+    while let Some(pkg) = stack.pop() {
+        if !seen.insert((pkg.name.as_str(), &pkg.version)) {
+            continue;
+        }
+        if is_audited(pkg, overridden) {
+            resolved.push(ResolvedDependency {
+                name: pkg.name.clone(),
+                version: pkg.version.clone(),
+                provenance: lock.path.clone(),
+            });
+        }
+
+        let declared = if pkg.is_path {
+            members.get(pkg.name.as_str()).map(|(_, d)| d.as_slice())
+        } else {
+            None
+        };
+        let pkg_activated = activated
+            .as_ref()
+            .map(|a| a.get(pkg.name.as_str()).unwrap_or(&none_activated));
+        for (name, version) in &pkg.deps {
+            if let Some(declared) = declared {
+                let keep = |d: &DeclaredDep| {
+                    !(exclude_dev_dependencies && d.kind == DepKind::Dev)
+                        && (!d.optional || pkg_activated.is_none_or(|a| a.contains(&d.key)))
+                };
+                if !follows_edge(declared, name, version, keep) {
+                    continue;
+                }
+            }
+            if let Some(children) = by_id.get(&(name.as_str(), version)) {
+                stack.extend(children.iter().copied());
+            }
+        }
+    }
+
+    Ok(resolved)
+}
+
+/// Returns `true` unless the edge `name version` is declared, and `keep`
+/// rejects every declaration of it.
+fn follows_edge(
+    declared: &[DeclaredDep],
+    name: &str,
+    version: &Version,
+    keep: impl Fn(&DeclaredDep) -> bool,
+) -> bool {
+    let mut matching = declared
+        .iter()
+        .filter(|d| d.name == name && d.req.matches(version))
+        .peekable();
+    matching.peek().is_none() || matching.any(keep)
+}
+
+/// Evaluates `[features]` tables across workspace members, the way Cargo's
+/// feature resolver does for the selected packages, to find out which optional
+/// dependencies are activated.
+///
+/// Only workspace members are evaluated: the manifests of registry packages
+/// are not part of the archive, so all of their edges are kept.
+struct FeatureResolver<'a> {
+    members: &'a HashMap<&'a str, (&'a ManifestInfo, Vec<DeclaredDep>)>,
+    exclude_dev: bool,
+    /// Members whose non-optional dependencies have been activated.
+    reached: HashSet<&'a str>,
+    enabled: HashSet<(&'a str, String)>,
+    /// Keys of the optional dependencies activated on each member.
+    activated: HashMap<&'a str, HashSet<String>>,
+    /// `dep?/feature` entries waiting for `dep` to be activated.
+    weak: HashMap<(&'a str, String), Vec<String>>,
+}
+
+impl<'a> FeatureResolver<'a> {
+    fn new(
+        members: &'a HashMap<&'a str, (&'a ManifestInfo, Vec<DeclaredDep>)>,
+        exclude_dev: bool,
+    ) -> Self {
+        Self {
+            members,
+            exclude_dev,
+            reached: HashSet::new(),
+            enabled: HashSet::new(),
+            activated: HashMap::new(),
+            weak: HashMap::new(),
+        }
+    }
+
+    fn resolve(
+        &mut self,
+        selection: &FeatureSelection,
+        roots: &[&'a str],
+    ) -> Result<(), (ScaError, String)> {
+        let mut requested: Vec<(&'a str, &str)> = Vec::new();
+        for feature in selection.features() {
+            if let Some((member, feature)) = feature.split_once('/') {
+                let Some(&root) = roots.iter().find(|r| **r == member) else {
+                    return Err((
+                        ScaError::UnknownFeature,
+                        format!("`{member}/{feature}`: `{member}` is not an audited member"),
+                    ));
+                };
+                if !self.defines(root, feature) {
+                    return Err((
+                        ScaError::UnknownFeature,
+                        format!("`{member}` has no feature `{feature}`"),
+                    ));
+                }
+                requested.push((root, feature));
+            } else {
+                let before = requested.len();
+                requested.extend(
+                    roots
+                        .iter()
+                        .filter(|r| self.defines(r, feature))
+                        .map(|r| (*r, feature.as_str())),
+                );
+                if requested.len() == before {
+                    return Err((
+                        ScaError::UnknownFeature,
+                        format!("no audited member has a feature `{feature}`"),
+                    ));
+                }
+            }
+        }
+
+        for &root in roots {
+            self.reach(root);
+            if selection.default_features() {
+                self.enable(root, "default");
+            }
+        }
+        for (member, feature) in requested {
+            self.enable(member, feature);
+        }
+        Ok(())
+    }
+
+    fn declared(&self, member: &str) -> &'a [DeclaredDep] {
+        let members = self.members;
+        members.get(member).map_or(&[], |(_, d)| d.as_slice())
+    }
+
+    /// The workspace member a declaration points to, if any.
+    fn member_of(&self, dep: &DeclaredDep) -> Option<&'a str> {
+        self.members
+            .get_key_value(dep.name.as_str())
+            .filter(|(_, (m, _))| {
+                m.package_version
+                    .as_ref()
+                    .is_none_or(|v| dep.req.matches(v))
+            })
+            .map(|(name, _)| *name)
+    }
+
+    /// Cargo skips dev-dependencies it was told to exclude.
+    fn counts(&self, dep: &DeclaredDep) -> bool {
+        !(self.exclude_dev && dep.kind == DepKind::Dev)
+    }
+
+    /// `true` if `feature` is in `member`'s `[features]` table or is the
+    /// implicit feature of an optional dependency.
+    fn defines(&self, member: &str, feature: &str) -> bool {
+        let Some((manifest, declared)) = self.members.get(member) else {
+            return false;
+        };
+        manifest.features.contains_key(feature)
+            || (declared.iter().any(|d| d.optional && d.key == feature)
+                && !Self::has_explicit_dep_feature(manifest, feature))
+    }
+
+    /// `dep:name` anywhere in the table hides the implicit feature of `name`.
+    fn has_explicit_dep_feature(manifest: &ManifestInfo, key: &str) -> bool {
+        manifest
+            .features
+            .values()
+            .flatten()
+            .any(|entry| entry.strip_prefix("dep:") == Some(key))
+    }
+
+    fn reach(&mut self, member: &'a str) {
+        if !self.reached.insert(member) {
+            return;
+        }
+        for dep in self.declared(member) {
+            if !dep.optional && self.counts(dep) {
+                self.activate(member, dep);
+            }
+        }
+    }
+
+    fn activate(&mut self, member: &'a str, dep: &'a DeclaredDep) {
+        if dep.optional {
+            self.activated
+                .entry(member)
+                .or_default()
+                .insert(dep.key.clone());
+        }
+        let Some(target) = self.member_of(dep) else {
+            return;
+        };
+        self.reach(target);
+        for feature in &dep.features {
+            self.enable(target, feature);
+        }
+        if dep.default_features {
+            self.enable(target, "default");
+        }
+        if let Some(pending) = self.weak.remove(&(member, dep.key.clone())) {
+            for feature in &pending {
+                self.enable(target, feature);
+            }
+        }
+    }
+
+    /// Activates every counted declaration of `key` on `member`.
+    fn activate_key(&mut self, member: &'a str, key: &str) {
+        for dep in self.declared(member) {
+            if dep.key == key && self.counts(dep) {
+                self.activate(member, dep);
+            }
+        }
+    }
+
+    /// Enables `feature` on whichever workspace member `key` points to.
+    fn enable_on_dep(&mut self, member: &'a str, key: &str, feature: &str) {
+        for dep in self.declared(member) {
+            if dep.key == key && self.counts(dep) {
+                if let Some(target) = self.member_of(dep) {
+                    self.enable(target, feature);
+                }
+            }
+        }
+    }
+
+    fn is_active(&self, member: &str, key: &str) -> bool {
+        self.declared(member).iter().any(|d| {
+            d.key == key
+                && self.counts(d)
+                && (!d.optional || self.activated.get(member).is_some_and(|a| a.contains(key)))
+        })
+    }
+
+    fn enable(&mut self, member: &'a str, feature: &str) {
+        if !self.enabled.insert((member, feature.to_owned())) {
+            return;
+        }
+        let members = self.members;
+        let Some((manifest, _)) = members.get(member) else {
+            return;
+        };
+        let Some(entries) = manifest.features.get(feature) else {
+            // Not in the table: either the implicit feature of an optional
+            // dependency, or a missing `default`.
+            if !Self::has_explicit_dep_feature(manifest, feature) {
+                self.activate_key(member, feature);
+            }
+            return;
+        };
+        for entry in entries {
+            if let Some(key) = entry.strip_prefix("dep:") {
+                self.activate_key(member, key);
+            } else if let Some((key, dep_feature)) = entry.split_once('/') {
+                if let Some(key) = key.strip_suffix('?') {
+                    if self.is_active(member, key) {
+                        self.enable_on_dep(member, key, dep_feature);
+                    } else {
+                        self.weak
+                            .entry((member, key.to_owned()))
+                            .or_default()
+                            .push(dep_feature.to_owned());
+                    }
+                } else {
+                    self.activate_key(member, key);
+                    self.enable_on_dep(member, key, dep_feature);
+                }
+            } else {
+                self.enable(member, entry);
             }
         }
     }
-    Ok(())
+}
+
+/// Groups lockfile packages by `name version`.
+fn index_packages(lock: &LockInfo) -> HashMap<(&str, &Version), Vec<&LockPackage>> {
+    let mut by_id: HashMap<(&str, &Version), Vec<&LockPackage>> = HashMap::new();
+    for pkg in &lock.packages {
+        by_id
+            .entry((pkg.name.as_str(), &pkg.version))
+            .or_default()
+            .push(pkg);
+    }
+    by_id
 }
 
 /// Ensures that each dependency in a lockfile is reachable from at least one
 /// workspace member via the graph encoded in the lockfile.
 fn ensure_lock_graph_is_reachable(lock: &LockInfo) -> Result<(), (ScaError, String)> {
-    let mut stack: Vec<&String> = lock.path_pkgs.iter().collect();
-    let mut seen: HashSet<&String> = HashSet::new();
+    let by_id = index_packages(lock);
 
-    while let Some(pkg) = stack.pop() {
-        if !seen.insert(pkg) {
+    let mut stack: Vec<(&str, &Version)> = lock
+        .packages
+        .iter()
+        .filter(|p| p.is_path)
+        .map(|p| (p.name.as_str(), &p.version))
+        .collect();
+    let mut seen: HashSet<(&str, &Version)> = HashSet::new();
+
+    while let Some(id) = stack.pop() {
+        if !seen.insert(id) {
             continue;
         }
-        if let Some(children) = lock.deps.get(pkg) {
-            for child in children {
-                stack.push(child);
+        for pkg in by_id.get(&id).into_iter().flatten() {
+            for (name, version) in &pkg.deps {
+                stack.push((name.as_str(), version));
             }
         }
     }
 
     // Any package not visited is undeclared.
-    for pkg in lock.pkgs.keys() {
-        if !seen.contains(pkg) {
+    for pkg in &lock.packages {
+        if !seen.contains(&(pkg.name.as_str(), &pkg.version)) {
             return Err((
                 ScaError::UndeclaredLockfileDependency,
                 format!(
                     "dependency `{}` in {} is not reachable from workspace roots",
-                    pkg, lock.path
+                    pkg.name, lock.path
                 ),
             ));
         }
@@ -359,14 +1231,441 @@ fn ensure_lock_graph_is_reachable(lock: &LockInfo) -> Result<(), (ScaError, Stri
     Ok(())
 }
 
-fn merge_deps(target: &mut HashMap<String, VersionReq>, src: BTreeMap<String, ManifestDep>) {
+fn merge_deps(target: &mut Vec<DeclaredDep>, src: BTreeMap<String, ManifestDep>, kind: DepKind) {
     for (user_key, dep) in src {
+        let inherited = matches!(dep, ManifestDep::Inherited(_));
         let canonical = dep.package().unwrap_or(&user_key).to_string();
         let req_str = dep.req().to_owned();
+        let default_features = dep
+            .detail()
+            .and_then(|d| d.default_features)
+            .unwrap_or(true);
         if let Ok(req) = VersionReq::parse(&req_str) {
-            target.insert(canonical, req);
+            target.push(DeclaredDep {
+                key: user_key,
+                name: canonical,
+                req,
+                kind,
+                inherited,
+                optional: dep.optional(),
+                features: dep.req_features().to_vec(),
+                default_features,
+            });
+        }
+    }
+}
+
+/// Collects the `[patch]` and `[replace]` entries of a manifest, whose raw
+/// table is `raw`.
+fn manifest_overrides(path: &str, manifest: &Manifest, raw: &toml::Table) -> Vec<SourceOverride> {
+    let mut overrides = Vec::new();
+    for deps in manifest.patch.iter().flat_map(BTreeMap::values) {
+        for (key, dep) in deps {
+            overrides.push(SourceOverride {
+                path: path.to_owned(),
+                table: "patch",
+                package: Some(dep.package().unwrap_or(key).to_owned()),
+            });
+        }
+    }
+    // `[replace]` keys are package IDs: `name:version`.
+    let replace = raw.get("replace").and_then(toml::Value::as_table);
+    for key in replace.into_iter().flat_map(toml::Table::keys) {
+        overrides.push(SourceOverride {
+            path: path.to_owned(),
+            table: "replace",
+            package: key.split(':').next().map(ToOwned::to_owned),
+        });
+    }
+    overrides
+}
+
+/// What a `.cargo/config.toml` changes about where sources come from.
+#[derive(Debug, Clone)]
+struct CargoConfig {
+    path: String,
+    overrides: Vec<SourceOverride>,
+    /// Directory sources that a `replace-with` names, with a trailing `/`.
+    vendor_dirs: Vec<String>,
+}
+
+/// Parse a `.cargo/config.toml` and collect the source overrides it declares.
+fn parse_config_file(vf: &ValidatedFile) -> Result<CargoConfig, (ScaError, String)> {
+    let path = &vf.header.name;
+    let text = core::str::from_utf8(&vf.bytes).map_err(|_| {
+        (
+            ScaError::InvalidManifestEncoding,
+            format!("`{path}` is not valid UTF‑8"),
+        )
+    })?;
+    let config: toml::Table = text.parse().map_err(|e| {
+        (
+            ScaError::ManifestParseError,
+            format!("Failed to parse `{path}`: {e}"),
+        )
+    })?;
+
+    let mut overrides = Vec::new();
+    let patches = config.get("patch").and_then(toml::Value::as_table);
+    for deps in patches.into_iter().flat_map(toml::Table::values) {
+        for (key, dep) in deps.as_table().into_iter().flatten() {
+            let package = dep
+                .get("package")
+                .and_then(toml::Value::as_str)
+                .unwrap_or(key);
+            overrides.push(SourceOverride {
+                path: path.to_string(),
+                table: "patch",
+                package: Some(package.to_owned()),
+            });
+        }
+    }
+    let sources = config.get("source").and_then(toml::Value::as_table);
+    let mut vendor_dirs = Vec::new();
+    for source in sources.into_iter().flat_map(toml::Table::values) {
+        let Some(replacement) = source.get("replace-with") else {
+            continue;
+        };
+        overrides.push(SourceOverride {
+            path: path.to_string(),
+            table: "source",
+            package: None,
+        });
+        // Relative directories resolve against the directory holding `.cargo/`.
+        let base = ArchivePath::parse(&path[..path.rfind(".cargo/").unwrap_or(0)]);
+        let directory = sources
+            .zip(replacement.as_str())
+            .and_then(|(s, name)| s.get(name))
+            .and_then(|s| s.get("directory"))
+            .and_then(toml::Value::as_str)
+            .and_then(|d| base.as_ref().ok()?.join(d).ok())
+            .filter(|d| !d.is_root());
+        if let Some(directory) = directory {
+            vendor_dirs.push(format!("{directory}/"));
+        }
+    }
+    let paths = config.get("paths").and_then(toml::Value::as_array);
+    if paths.is_some_and(|p| !p.is_empty()) {
+        overrides.push(SourceOverride {
+            path: path.to_string(),
+            table: "paths",
+            package: None,
+        });
+    }
+    Ok(CargoConfig {
+        path: path.to_string(),
+        overrides,
+        vendor_dirs,
+    })
+}
+
+/// The directories that the `.cargo/config.toml` files of the workspace
+/// replace a source with, and the vendored crates inside them.
+struct VendorLayout<'a> {
+    /// Directory sources, with a trailing `/`.
+    dirs: Vec<&'a str>,
+    /// The children of `dirs` that hold a `.cargo-checksum.json`, with a trailing `/`.
+    crate_dirs: Vec<&'a str>,
+}
+
+impl<'a> VendorLayout<'a> {
+    /// A config inside a directory holding a `.cargo-checksum.json` is a file
+    /// of a vendored crate, so the directories it names are not vendor directories.
+    fn new(archive: &'a ValidPartialArchive, configs: &'a [CargoConfig]) -> Self {
+        let checksum_dirs: Vec<&str> = archive
+            .headers
+            .iter()
+            .filter_map(|h| h.name.strip_suffix(CHECKSUM_FILE))
+            .filter(|dir| dir.ends_with('/'))
+            .collect();
+        let in_checksum_dir = |path: &str| checksum_dirs.iter().any(|d| path.starts_with(d));
+        let dirs: Vec<&str> = configs
+            .iter()
+            .filter(|c| !in_checksum_dir(&c.path))
+            .flat_map(|c| c.vendor_dirs.iter().map(String::as_str))
+            .collect();
+        let crate_dirs = checksum_dirs
+            .iter()
+            .copied()
+            .filter(|crate_dir| {
+                dirs.iter().any(|dir| {
+                    crate_dir
+                        .strip_prefix(dir)
+                        .and_then(|name| name.strip_suffix('/'))
+                        .is_some_and(|name| !name.is_empty() && !name.contains('/'))
+                })
+            })
+            .collect();
+        Self { dirs, crate_dirs }
+    }
+
+    /// `true` if `path` is a file of a vendored crate. Directories end in `/`,
+    /// so only whole path segments match.
+    fn is_vendored(&self, path: &str) -> bool {
+        self.crate_dirs.iter().any(|dir| path.starts_with(dir))
+    }
+
+    /// Rejects a vendor directory that holds a manifest of the workspace,
+    /// which would hide it from the workspace checks.
+    fn ensure_holds_no_workspace_manifest(
+        &self,
+        manifests: &[ManifestInfo],
+    ) -> Result<(), (ScaError, String)> {
+        for manifest in manifests {
+            if let Some(dir) = self.dirs.iter().find(|d| manifest.path.starts_with(*d)) {
+                return Err((
+                    ScaError::VendoredSourceMismatch,
+                    format!(
+                        "vendor directory `{dir}` holds workspace manifest `{}`",
+                        manifest.path
+                    ),
+                ));
+            }
         }
+        Ok(())
     }
+
+    /// Checks that every vendored crate is a package that `lock` locks from
+    /// outside the workspace, as its own manifest names it.
+    fn ensure_crates_are_locked(
+        &self,
+        archive: &ValidPartialArchive,
+        lock: &LockInfo,
+    ) -> Result<(), (ScaError, String)> {
+        let read = |path: &str| {
+            archive
+                .files
+                .iter()
+                .find(|vf| vf.header.name == path)
+                .map(|vf| vf.bytes.as_slice())
+                .ok_or_else(|| {
+                    (
+                        ScaError::VendoredSourceMismatch,
+                        format!("vendored file `{path}` is missing"),
+                    )
+                })
+        };
+        for crate_dir in &self.crate_dirs {
+            let (name, version) =
+                parse_normalized_manifest(&format!("{crate_dir}Cargo.toml"), &read)?;
+            let locked = lock
+                .packages
+                .iter()
+                .any(|p| !p.is_path && p.name == name && p.version == version);
+            if !locked {
+                return Err((
+                    ScaError::VendoredSourceMismatch,
+                    format!(
+                        "vendored `{name} {version}` in `{crate_dir}` is not an external package of `{}`",
+                        lock.path
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn ensure_overrides_are_permitted(
+    overrides: &[SourceOverride],
+    policy: &OverridePolicy,
+) -> Result<(), (ScaError, String)> {
+    for o in overrides {
+        let permitted = o.package.as_deref().map_or_else(
+            || policy.permits_source_replacement(),
+            |p| policy.permits_package(p),
+        );
+        if !permitted {
+            let what = o.package.as_deref().unwrap_or("a source");
+            return Err((
+                ScaError::ForbiddenSourceOverride,
+                format!("`{}` overrides {what} via `[{}]`", o.path, o.table),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Checks that every external package of `lock` is provided by one of the
+/// `vendor_dirs`, as the release its lockfile checksum names.
+fn ensure_packages_are_vendored(
+    archive: &ValidPartialArchive,
+    vendor_dirs: &[&str],
+    lock: &LockInfo,
+) -> Result<(), (ScaError, String)> {
+    if vendor_dirs.is_empty() {
+        return Err((
+            ScaError::VendoredSourceMismatch,
+            "no source is replaced with a vendor directory".to_string(),
+        ));
+    }
+    let mut vendored: HashMap<PkgId, Option<String>> = HashMap::new();
+    for dir in vendor_dirs {
+        for krate in verify_vendor_directory(archive, dir)? {
+            vendored.insert((krate.name, krate.version), krate.package_checksum);
+        }
+    }
+    for pkg in lock.packages.iter().filter(|p| !p.is_path) {
+        let id = (pkg.name.clone(), pkg.version.clone());
+        match vendored.get(&id) {
+            None => {
+                return Err((
+                    ScaError::VendoredSourceMismatch,
+                    format!("`{} {}` is not vendored", pkg.name, pkg.version),
+                ));
+            }
+            Some(package) if *package != pkg.checksum => {
+                return Err((
+                    ScaError::VendoredSourceMismatch,
+                    format!(
+                        "vendored `{} {}` does not match the checksum in `{}`",
+                        pkg.name, pkg.version, lock.path
+                    ),
+                ));
+            }
+            Some(_) => {}
+        }
+    }
+    Ok(())
+}
+
+/// What the archive shows about the toolchain it targets: each member's
+/// edition and `rust-version`, and the channel of the `rust-toolchain` file
+/// that rustup would pick for the workspace root. Together with the workspace
+/// lockfile format, these bound the Cargo releases the archive is consistent with.
+fn inspect_toolchain(
+    archive: &ValidPartialArchive,
+    manifest_by_path: &HashMap<String, ManifestInfo>,
+    workspace_root: &ManifestInfo,
+    workspace_lock: &LockInfo,
+) -> Result<(PackageManagerRange, ToolchainAttestation), (ScaError, String)> {
+    let mut bounds = CargoVersionBounds::default();
+    if let Some(min) = min_cargo_for_lockfile(workspace_lock.version) {
+        bounds.at_least(min, || {
+            format!(
+                "`{}` (version {})",
+                workspace_lock.path, workspace_lock.version
+            )
+        });
+    }
+
+    let mut members = Vec::new();
+    for manifest in manifest_by_path.values() {
+        let edition = match manifest.edition {
+            Some(MaybeInherited::Local(edition)) => Some(edition),
+            Some(MaybeInherited::Inherited { .. }) => workspace_root.workspace_edition,
+            None => None,
+        };
+        if let Some(min) = edition.and_then(min_cargo_for_edition) {
+            bounds.at_least(min, || format!("the edition of `{}`", manifest.path));
+        }
+
+        let rust_version = match &manifest.rust_version {
+            Some(MaybeInherited::Local(v)) => Some(v.as_str()),
+            Some(MaybeInherited::Inherited { .. }) => {
+                workspace_root.workspace_rust_version.as_deref()
+            }
+            None => None,
+        };
+        let rust_version = rust_version
+            .map(|text| {
+                parse_rust_version(text).ok_or_else(|| {
+                    (
+                        ScaError::ManifestParseError,
+                        format!("`{}` has an invalid rust-version `{text}`", manifest.path),
+                    )
+                })
+            })
+            .transpose()?;
+        if let Some(min) = &rust_version {
+            bounds.at_least(min.clone(), || {
+                format!("the rust-version of `{}`", manifest.path)
+            });
+        }
+
+        // A virtual workspace root is no member.
+        if let Some(name) = &manifest.package_name {
+            members.push(MemberToolchain::new(
+                name.clone(),
+                rust_edition(edition.unwrap_or(Edition::E2015)),
+                rust_version,
+            ));
+        }
+    }
+
+    let toolchain = find_toolchain_file(archive, &workspace_root.path)?;
+    if let Some(toolchain) = &toolchain {
+        let channel = toolchain.channel.as_deref();
+        if let Some((min, max)) = channel.map(pinned_releases).transpose()?.flatten() {
+            let reason = || format!("the channel pinned by `{}`", toolchain.path);
+            bounds.at_least(min, reason);
+            bounds.below(max, reason);
+        }
+    }
+
+    let channel = toolchain.and_then(|t| t.channel);
+    Ok((
+        bounds.into_range()?,
+        ToolchainAttestation::new(channel, members),
+    ))
+}
+
+/// The `rust-toolchain` file nearest to the directory of `manifest_path`.
+/// Like rustup, a legacy `rust-toolchain` wins over a `rust-toolchain.toml`
+/// in the same directory.
+fn find_toolchain_file(
+    archive: &ValidPartialArchive,
+    manifest_path: &str,
+) -> Result<Option<ToolchainFile>, (ScaError, String)> {
+    let mut dir = manifest_path.trim_end_matches("Cargo.toml");
+    loop {
+        for name in ["rust-toolchain", "rust-toolchain.toml"] {
+            let path = format!("{dir}{name}");
+            if let Some(vf) = archive.files.iter().find(|vf| vf.header.name == path) {
+                return parse_toolchain_file(vf).map(Some);
+            }
+        }
+        if dir.is_empty() {
+            return Ok(None);
+        }
+        let parent = dir.trim_end_matches('/');
+        dir = parent.rfind('/').map_or("", |i| &parent[..=i]);
+    }
+}
+
+/// Ensures that the prover did not leave any file Cargo would read out of
+/// the dependency-file leaves.
+fn ensure_dependency_files_are_materialized(
+    archive: &ValidPartialArchive,
+) -> Result<(), (ScaError, String)> {
+    let materialized: HashSet<&str> = archive
+        .files
+        .iter()
+        .map(|vf| vf.header.name.as_str())
+        .collect();
+    for header in &archive.headers {
+        if is_cargo_dependency_file(&header.name) && !materialized.contains(header.name.as_str()) {
+            return Err((
+                ScaError::InvalidMerkleArchive,
+                format!("dependency file `{}` was left out", header.name),
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[inline]
+fn is_cargo_config(path: &str) -> bool {
+    let mut components = path.rsplit('/');
+    matches!(components.next(), Some("config.toml" | "config"))
+        && components.next() == Some(".cargo")
+}
+
+/// External packages are audited, and so are path packages that a
+/// `[patch]` or `[replace]` entry provides in place of a registry package.
+#[inline]
+fn is_audited(pkg: &LockPackage, overridden: &HashSet<&str>) -> bool {
+    !pkg.is_path || overridden.contains(pkg.name.as_str())
 }
 
 fn map_by<K, V, F>(items: Vec<V>, key_fn: F) -> HashMap<K, V>
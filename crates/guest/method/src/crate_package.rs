@@ -0,0 +1,116 @@
+extern crate alloc;
+
+use alloc::{format, string::String, vec::Vec};
+use miniz_oxide::inflate::decompress_to_vec;
+use risc0_zkvm::sha::{Impl, Sha256};
+use zk_sca_guest_abi::{MerkleFormat, ScaError};
+use zk_sca_guest_abi_utils::{ValidPartialArchive, tar_merkle_root};
+
+use crate::vendor::parse_normalized_manifest;
+
+const FEXTRA: u8 = 1 << 2;
+const FNAME: u8 = 1 << 3;
+const FCOMMENT: u8 = 1 << 4;
+const FHCRC: u8 = 1 << 1;
+
+fn invalid(detail: impl Into<String>) -> (ScaError, String) {
+    (ScaError::InvalidCratePackage, detail.into())
+}
+
+/// Check that `package` is the `.crate` file `archive` was unpacked from, and
+/// return its SHA-256, which registries record as the package checksum.
+///
+/// The package must decompress to the TAR whose `format` Merkle root is
+/// `root_hash`, and every entry must lie in the `<name>-<version>/` directory
+/// that its normalized `Cargo.toml` names. `Cargo.toml.orig` is kept as published but
+/// not analyzed: Cargo builds dependents from the normalized manifest.
+pub fn verify_crate_package(
+    package: &[u8],
+    root_hash: &[u8; 32],
+    format: MerkleFormat,
+    archive: &ValidPartialArchive,
+) -> Result<[u8; 32], (ScaError, String)> {
+    let tar = gunzip(package)?;
+    if tar_merkle_root(&tar, format).as_ref() != Some(root_hash) {
+        return Err(invalid("package does not unpack to the analyzed archive"));
+    }
+
+    let first = archive
+        .headers
+        .first()
+        .ok_or_else(|| invalid("package is empty"))?;
+    let prefix = match first.name.split_once('/') {
+        Some((dir, _)) if !dir.is_empty() => format!("{dir}/"),
+        _ => {
+            return Err(invalid(format!(
+                "`{}` is outside a package directory",
+                first.name
+            )));
+        }
+    };
+    if let Some(stray) = archive
+        .headers
+        .iter()
+        .find(|h| !h.name.starts_with(&prefix))
+    {
+        return Err(invalid(format!("`{}` is outside `{prefix}`", stray.name)));
+    }
+
+    let manifest_path = format!("{prefix}Cargo.toml");
+    let read = |path: &str| {
+        archive
+            .files
+            .iter()
+            .find(|vf| vf.header.name == path)
+            .map(|vf| vf.bytes.as_slice())
+            .ok_or_else(|| invalid(format!("`{path}` is missing")))
+    };
+    let (name, version) = parse_normalized_manifest(&manifest_path, &read)?;
+    if prefix != format!("{name}-{version}/") {
+        return Err(invalid(format!(
+            "`{manifest_path}` declares `{name} {version}`, which does not match its directory"
+        )));
+    }
+
+    let mut checksum = [0u8; 32];
+    checksum.copy_from_slice(Impl::hash_bytes(package).as_bytes());
+    Ok(checksum)
+}
+
+/// Decompress a single-member gzip stream, checking its CRC-32 and size trailer.
+fn gunzip(data: &[u8]) -> Result<Vec<u8>, (ScaError, String)> {
+    let truncated = || invalid("truncated gzip stream");
+    if data.len() < 18 || data[..3] != [0x1f, 0x8b, 8] {
+        return Err(invalid("package is not gzip-compressed"));
+    }
+    let flags = data[3];
+    let mut pos = 10;
+    if flags & FEXTRA != 0 {
+        let len = data.get(pos..pos + 2).ok_or_else(truncated)?;
+        pos += 2 + usize::from(u16::from_le_bytes([len[0], len[1]]));
+    }
+    for flag in [FNAME, FCOMMENT] {
+        if flags & flag != 0 {
+            let len = data
+                .get(pos..)
+                .and_then(|rest| rest.iter().position(|&b| b == 0))
+                .ok_or_else(truncated)?;
+            pos += len + 1;
+        }
+    }
+    if flags & FHCRC != 0 {
+        pos += 2;
+    }
+
+    let body = data.get(pos..data.len() - 8).ok_or_else(truncated)?;
+    let tar = decompress_to_vec(body).map_err(|e| invalid(format!("corrupt gzip stream: {e}")))?;
+    let trailer = &data[data.len() - 8..];
+    let crc = u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]);
+    let size = u32::from_le_bytes([trailer[4], trailer[5], trailer[6], trailer[7]]);
+    // ISIZE holds the size modulo 2^32.
+    #[allow(clippy::cast_possible_truncation)]
+    if crc32fast::hash(&tar) != crc || tar.len() as u32 != size {
+        return Err(invalid("gzip trailer does not match the decompressed data"));
+    }
+    Ok(tar)
+}
@@ -0,0 +1,138 @@
+extern crate alloc;
+
+use alloc::{borrow::ToOwned, format, string::String, vec, vec::Vec};
+use core::str;
+use hashbrown::{HashMap, HashSet};
+use sha1::{Digest, Sha1};
+use zk_sca_guest_abi::{GitCommit, GitTree, ScaError, SharedBytes};
+use zk_sca_guest_abi_utils::{
+    ArchivePath, GitEntryKind, TarEntryKind, TarHeader, ValidPartialArchive, ValidatedFile,
+    is_cargo_dependency_file, parse_git_commit_tree, parse_git_tree, to_hex,
+};
+
+fn invalid(detail: impl Into<String>) -> (ScaError, String) {
+    (ScaError::InvalidGitTree, detail.into())
+}
+
+/// Authenticate the tree of a Git commit from the commit ID and list it as
+/// an archive of its regular files and symbolic links, materializing the
+/// Cargo dependency files and any other file whose blob is provided.
+///
+/// Every tree object must be provided, so no file can be left out, and so
+/// must the blob of every dependency file. A symbolic link's blob holds its
+/// target; a dependency file that is a link is materialized empty, for the
+/// link policy to reject or resolve. Submodules are left out, and the sizes
+/// of files that are not materialized are given as zero.
+pub fn read_git_tree(
+    tree: &GitTree,
+    verify_vendored_sources: bool,
+) -> Result<(ValidPartialArchive, GitCommit), (ScaError, String)> {
+    if verify_vendored_sources {
+        return Err((
+            ScaError::InapplicableOption,
+            "vendored sources are not materialized from a Git tree".to_owned(),
+        ));
+    }
+
+    let commit_id = object_id("commit", &tree.commit);
+    let root = parse_git_commit_tree(&tree.commit)
+        .ok_or_else(|| invalid("the commit does not name a tree"))?;
+    let trees: HashMap<[u8; 20], &[u8]> = tree
+        .trees
+        .iter()
+        .map(|body| (object_id("tree", body), body.as_slice()))
+        .collect();
+    let blobs: HashMap<[u8; 20], &[u8]> = tree
+        .blobs
+        .iter()
+        .map(|body| (object_id("blob", body), body.as_slice()))
+        .collect();
+
+    let mut headers = Vec::new();
+    let mut files = Vec::new();
+    let mut paths = HashSet::new();
+    let mut pending = vec![(root, String::new())];
+    while let Some((id, dir)) = pending.pop() {
+        let body = trees
+            .get(&id)
+            .ok_or_else(|| invalid(format!("tree {} of `{dir}` is missing", to_hex(&id))))?;
+        let entries = parse_git_tree(body).map_err(|e| invalid(format!("`{dir}`: {e}")))?;
+        for entry in entries {
+            let path = ArchivePath::parse(&format!("{dir}{}", entry.name)).map_err(invalid)?;
+            if !paths.insert(path.clone()) {
+                return Err(invalid(format!("`{path}` appears twice")));
+            }
+            match entry.kind {
+                GitEntryKind::Tree => pending.push((entry.id, format!("{path}/"))),
+                GitEntryKind::File => {
+                    let blob = blobs.get(&entry.id);
+                    if blob.is_none() && is_cargo_dependency_file(&path) {
+                        return Err(invalid(format!(
+                            "blob {} of `{path}` is missing",
+                            to_hex(&entry.id)
+                        )));
+                    }
+                    let header = TarHeader {
+                        name: path,
+                        size: blob.map_or(0, |bytes| bytes.len()),
+                        kind: TarEntryKind::File,
+                        link_name: None,
+                    };
+                    headers.push(header.clone());
+                    if let Some(bytes) = blob {
+                        files.push(ValidatedFile {
+                            header,
+                            bytes: bytes.to_vec().into(),
+                        });
+                    }
+                }
+                GitEntryKind::Symlink => {
+                    let is_dependency_file = is_cargo_dependency_file(&path);
+                    let link_name = match blobs.get(&entry.id) {
+                        Some(bytes) => Some(
+                            str::from_utf8(bytes)
+                                .map_err(|_| {
+                                    invalid(format!("`{path}` links to a non-UTF-8 name"))
+                                })?
+                                .to_owned(),
+                        ),
+                        None if is_dependency_file => {
+                            return Err(invalid(format!(
+                                "blob {} of `{path}` is missing",
+                                to_hex(&entry.id)
+                            )));
+                        }
+                        None => None,
+                    };
+                    let header = TarHeader {
+                        name: path,
+                        size: 0,
+                        kind: TarEntryKind::Symlink,
+                        link_name,
+                    };
+                    headers.push(header.clone());
+                    if is_dependency_file {
+                        files.push(ValidatedFile {
+                            header,
+                            bytes: SharedBytes::default(),
+                        });
+                    }
+                }
+                GitEntryKind::Submodule => {}
+            }
+        }
+    }
+
+    Ok((
+        ValidPartialArchive { headers, files },
+        GitCommit::new(commit_id, root),
+    ))
+}
+
+/// The ID Git gives an object: the SHA-1 of its type, size and contents.
+fn object_id(kind: &str, body: &[u8]) -> [u8; 20] {
+    let mut hasher = Sha1::new();
+    hasher.update(format!("{kind} {}\0", body.len()));
+    hasher.update(body);
+    hasher.finalize().into()
+}
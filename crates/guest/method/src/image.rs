@@ -0,0 +1,159 @@
+extern crate alloc;
+
+use alloc::{borrow::ToOwned, format, string::String, vec::Vec};
+use risc0_zkvm::sha::{Impl, Sha256};
+use serde::Deserialize;
+use zk_sca_guest_abi::{
+    ContainerImage, ImageAttestation, ImageFile, MerkleFormat, ScaError, ToolchainAttestation,
+};
+use zk_sca_guest_abi_utils::{
+    ArchivePath, TarEntryKind, TarHeader, TarMember, ValidatedFile, read_tar_members,
+    tar_merkle_root,
+};
+
+use crate::auditable::{dependency_section, ensure_options_apply, read_dependency_list};
+use crate::cargo::{AuditOptions, CargoArchive, read_lockfile_dependencies};
+use crate::toolchain::CargoVersionBounds;
+
+fn invalid(detail: impl Into<String>) -> (ScaError, String) {
+    (ScaError::InvalidContainerImage, detail.into())
+}
+
+/// The fields of an OCI (or Docker v2) image manifest that name its blobs.
+#[derive(Deserialize)]
+struct Manifest {
+    config: Descriptor,
+    layers: Vec<Descriptor>,
+}
+
+#[derive(Deserialize)]
+struct Descriptor {
+    digest: String,
+}
+
+#[derive(Deserialize)]
+struct Config {
+    rootfs: RootFs,
+}
+
+/// Lists the SHA-256 of each uncompressed layer, which runtimes check on unpacking.
+#[derive(Deserialize)]
+struct RootFs {
+    #[serde(rename = "type")]
+    kind: String,
+    diff_ids: Vec<String>,
+}
+
+/// Authenticate `image` from its manifest and audit every `Cargo.lock` and
+/// `cargo auditable` binary in any of its layers.
+///
+/// The configuration must match the digest the manifest gives it, and each
+/// layer the `diff_id` the configuration lists for it. Layers are read as
+/// [`read_tar_members`] reads any TAR, so every header must be valid. Files
+/// that a later layer deletes or replaces are audited all the same. Options
+/// that need a workspace's manifests are rejected. Layer roots are `format`
+/// Merkle roots.
+pub fn read_container_image(
+    image: &ContainerImage,
+    format: MerkleFormat,
+    options: AuditOptions<'_>,
+) -> Result<(CargoArchive, ImageAttestation), (ScaError, String)> {
+    ensure_options_apply(options, "a container image")?;
+    if options.excludes_dev_dependencies() {
+        return Err((
+            ScaError::InapplicableOption,
+            "dev-dependencies cannot be told apart in a container image".to_owned(),
+        ));
+    }
+
+    let manifest: Manifest = serde_json::from_slice(&image.manifest)
+        .map_err(|e| invalid(format!("malformed image manifest: {e}")))?;
+    if sha256(&image.config) != parse_digest(&manifest.config.digest)? {
+        return Err(invalid("image configuration does not match its digest"));
+    }
+    let config: Config = serde_json::from_slice(&image.config)
+        .map_err(|e| invalid(format!("malformed image configuration: {e}")))?;
+    let diff_ids = config.rootfs.diff_ids;
+    if config.rootfs.kind != "layers"
+        || diff_ids.len() != manifest.layers.len()
+        || diff_ids.len() != image.layers.len()
+    {
+        return Err(invalid(format!(
+            "manifest lists {} layers, configuration {} and input {}",
+            manifest.layers.len(),
+            diff_ids.len(),
+            image.layers.len()
+        )));
+    }
+
+    let mut dependencies = Vec::new();
+    let mut layer_roots = Vec::new();
+    let mut lockfiles = Vec::new();
+    let mut binaries = Vec::new();
+    for (index, (layer, diff_id)) in image.layers.iter().zip(&diff_ids).enumerate() {
+        if sha256(layer) != parse_digest(diff_id)? {
+            return Err(invalid(format!("layer {index} does not match its diff_id")));
+        }
+        let members =
+            read_tar_members(layer).map_err(|e| invalid(format!("layer {index}: {e}")))?;
+        layer_roots.push(
+            tar_merkle_root(layer, format)
+                .ok_or_else(|| invalid(format!("layer {index} is not a valid TAR")))?,
+        );
+        for member in members {
+            let TarMember::Entry(header) = member.member else {
+                continue;
+            };
+            if header.kind != TarEntryKind::File {
+                continue;
+            }
+            let path = header.name.as_str().to_owned();
+            let name = ArchivePath::parse(&format!("layers[{index}]/{path}"))
+                .map_err(|e| invalid(format!("layer {index}: {e}")))?;
+            if path == "Cargo.lock" || path.ends_with("/Cargo.lock") {
+                let vf = ValidatedFile {
+                    header: TarHeader { name, ..header },
+                    bytes: member.data.to_vec().into(),
+                };
+                dependencies.extend(read_lockfile_dependencies(&vf)?);
+                lockfiles.push(ImageFile::new(index, path));
+            } else if let Some(section) = dependency_section(member.data)
+                .map_err(|(code, detail)| (code, format!("`{name}`: {detail}")))?
+            {
+                dependencies.extend(read_dependency_list(section, &name)?);
+                binaries.push(ImageFile::new(index, path));
+            }
+        }
+    }
+
+    let cargo_archive = CargoArchive {
+        dependencies,
+        // An image records nothing about the Cargo release or toolchain that built it.
+        cargo_versions: CargoVersionBounds::default().into_range()?,
+        toolchain: ToolchainAttestation::new(None, Vec::new()),
+    };
+    let attestation =
+        ImageAttestation::new(sha256(&image.manifest), layer_roots, lockfiles, binaries);
+    Ok((cargo_archive, attestation))
+}
+
+fn sha256(bytes: &[u8]) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    out.copy_from_slice(Impl::hash_bytes(bytes).as_bytes());
+    out
+}
+
+/// Parse a `sha256:<hex>` digest; other algorithms are not supported.
+fn parse_digest(digest: &str) -> Result<[u8; 32], (ScaError, String)> {
+    let malformed = || invalid(format!("unsupported digest `{digest}`"));
+    let hex = digest.strip_prefix("sha256:").ok_or_else(malformed)?;
+    if hex.len() != 64 {
+        return Err(malformed());
+    }
+    let mut out = [0u8; 32];
+    for (byte, pair) in out.iter_mut().zip(hex.as_bytes().chunks(2)) {
+        let pair = core::str::from_utf8(pair).map_err(|_| malformed())?;
+        *byte = u8::from_str_radix(pair, 16).map_err(|_| malformed())?;
+    }
+    Ok(out)
+}
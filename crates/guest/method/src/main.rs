@@ -10,13 +10,26 @@ extern crate alloc;
 
 use alloc::{format, string::String};
 use risc0_zkvm::guest::env;
-use zk_sca_guest_abi::{GuestInput, GuestOutput, GuestOutputV0, PackageManager, ScaError, Version};
-use zk_sca_guest_abi_utils::validate_merkle_archive;
+use zk_sca_guest_abi::{
+    GuestInput, GuestOutput, GuestOutputV2, ImageAttestation, MerkleFormat, PackageManager,
+    PackageManagerSpec, PermittedDependencies, ScaError, Source,
+};
+use zk_sca_guest_abi_utils::{ValidPartialArchive, apply_link_policy, validate_merkle_archive};
 
 mod audit;
 use audit::audit_dependencies;
+mod auditable;
+use auditable::read_auditable_binary;
 mod cargo;
-use cargo::validate_cargo_archive;
+use cargo::{AuditOptions, CargoArchive, validate_cargo_archive};
+mod crate_package;
+use crate_package::verify_crate_package;
+mod git;
+use git::read_git_tree;
+mod image;
+use image::read_container_image;
+mod toolchain;
+mod vendor;
 
 risc0_zkvm::guest::entry!(main);
 
@@ -28,27 +41,54 @@ fn main() {
 
 fn real_main() -> Result<(), (ScaError, String)> {
     let guest_input: GuestInput = env::read();
-    let merkle_archive = guest_input.src_archive;
+    end_stage("read input");
+    let source = guest_input.source;
     let permitted = guest_input.permitted_deps;
     let license_policy = guest_input.license_policy;
-    if !(merkle_archive.resolved_with.manager() == permitted.resolvable_with()) {
-        return Err((
-            ScaError::InconsistentPackageManager,
-            format!(
-                "archive resolved with `{:?}` but permitted deps are resolvable with `{:?}`",
-                merkle_archive.resolved_with,
-                permitted.resolvable_with()
-            ),
-        ));
-    }
+    let member_scope = guest_input.member_scope;
+    let feature_selection = guest_input.feature_selection;
+    let override_policy = guest_input.override_policy;
+    let verify_vendored_sources = guest_input.verify_vendored_sources;
+    let link_policy = guest_input.link_policy;
+    let toolchain_policy = guest_input.toolchain_policy;
+    let crate_package = guest_input.crate_package;
+    let spec = source.resolved_with().clone();
+    ensure_consistent_package_manager(&spec, &permitted)?;
+
+    // The root and format of the archive, which other sources come without.
+    let (root, vpa) = match &source {
+        Source::Archive(archive) => (
+            Some((archive.root_hash, archive.format)),
+            validate_merkle_archive(archive)?,
+        ),
+        _ => (None, ValidPartialArchive::default()),
+    };
+    end_stage("validate archive");
+    let (vpa, git_commit) = match &source {
+        Source::GitTree { tree, .. } => {
+            let (vpa, commit) = read_git_tree(tree, verify_vendored_sources)?;
+            (vpa, Some(commit))
+        }
+        _ => (vpa, None),
+    };
+    let vpa = apply_link_policy(vpa, link_policy)?;
+    end_stage("git tree and links");
 
-    let vpa = validate_merkle_archive(&merkle_archive)?;
+    let crate_checksum = crate_package
+        .map(|package| verify_archived_package(&package, root, &vpa))
+        .transpose()?;
+    end_stage("crate package");
 
-    let spec = merkle_archive.resolved_with;
-    let resolved = match (spec.manager(), spec.version()) {
-        // Cargo 1.51 is the first stable version that can produce V3 lockfiles.
-        (PackageManager::Cargo, version) if version >= &Version::new(1, 51, 0) => {
-            validate_cargo_archive(&vpa)?
+    let (cargo_archive, binary_hash, image) = match spec.manager() {
+        PackageManager::Cargo => {
+            let options = AuditOptions {
+                member_scope: member_scope.as_ref(),
+                feature_selection: feature_selection.as_ref(),
+                override_policy: override_policy.as_ref(),
+                verify_vendored_sources,
+                toolchain_policy: toolchain_policy.as_ref(),
+            };
+            read_cargo_source(&source, &vpa, options)?
         }
         _ => {
             return Err((
@@ -57,16 +97,113 @@ fn real_main() -> Result<(), (ScaError, String)> {
             ));
         }
     };
+    end_stage("cargo source");
+
+    // The claimed version must be one the archive itself is consistent with.
+    let package_manager_range = cargo_archive.cargo_versions;
+    if !package_manager_range.contains(&spec) {
+        return Err((
+            ScaError::PackageManagerVersionMismatch,
+            format!("`{spec:?}` is outside the consistent range `{package_manager_range:?}`"),
+        ));
+    }
 
-    audit_dependencies(&resolved, permitted.dependencies(), license_policy.as_ref())?;
+    audit_dependencies(
+        &cargo_archive.dependencies,
+        permitted.dependencies(),
+        license_policy.as_ref(),
+    )?;
+    end_stage("audit");
 
-    let out_v0 = GuestOutputV0 {
-        root_hash: merkle_archive.root_hash,
+    let out_v2 = GuestOutputV2 {
+        root_hash: root.map(|(root_hash, _)| root_hash),
+        merkle_format: match &source {
+            Source::ContainerImage { format, .. } => Some(*format),
+            _ => root.map(|(_, format)| format),
+        },
         permitted_deps: permitted,
         license_policy,
+        member_scope,
+        feature_selection,
+        override_policy,
+        verify_vendored_sources,
+        link_policy,
+        package_manager_range,
+        toolchain_policy,
+        toolchain: cargo_archive.toolchain,
+        crate_checksum,
+        binary_hash,
+        image,
+        git_commit,
     };
-    let out: GuestOutput = out_v0.into();
+    let out: GuestOutput = out_v2.into();
     env::commit(&out);
+    end_stage("commit");
 
     Ok(())
 }
+
+/// The source must be resolved with the package manager that the permitted
+/// dependencies are resolvable with.
+fn ensure_consistent_package_manager(
+    resolved_with: &PackageManagerSpec,
+    permitted: &PermittedDependencies,
+) -> Result<(), (ScaError, String)> {
+    if !(resolved_with.manager() == permitted.resolvable_with()) {
+        return Err((
+            ScaError::InconsistentPackageManager,
+            format!(
+                "archive resolved with `{resolved_with:?}` but permitted deps are resolvable with `{:?}`",
+                permitted.resolvable_with()
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Check a `.crate` file against the archive it was unpacked into, and return
+/// its checksum.
+fn verify_archived_package(
+    package: &[u8],
+    root: Option<([u8; 32], MerkleFormat)>,
+    vpa: &ValidPartialArchive,
+) -> Result<[u8; 32], (ScaError, String)> {
+    let Some((root_hash, format)) = root else {
+        return Err((
+            ScaError::InvalidCratePackage,
+            "a `.crate` file is analyzed with its archive".into(),
+        ));
+    };
+    verify_crate_package(package, &root_hash, format, vpa)
+}
+
+/// Marks the end of a stage of the analysis. The host prints each mark
+/// with the cycle count it was reached at, which the cycle report shows.
+fn end_stage(stage: &str) {
+    env::log(stage);
+}
+
+/// A source's dependencies, with a binary's hash or an image's attestation.
+type CargoSource = (CargoArchive, Option<[u8; 32]>, Option<ImageAttestation>);
+
+/// Read the dependencies of the archive or Git tree, a `cargo auditable`
+/// binary or a container image.
+fn read_cargo_source(
+    source: &Source,
+    vpa: &ValidPartialArchive,
+    options: AuditOptions<'_>,
+) -> Result<CargoSource, (ScaError, String)> {
+    match source {
+        Source::Archive(_) | Source::GitTree { .. } => {
+            Ok((validate_cargo_archive(vpa, options)?, None, None))
+        }
+        Source::AuditableBinary { binary, .. } => {
+            let (archive, hash) = read_auditable_binary(binary, options)?;
+            Ok((archive, Some(hash), None))
+        }
+        Source::ContainerImage { format, image, .. } => {
+            let (cargo_archive, attestation) = read_container_image(image, *format, options)?;
+            Ok((cargo_archive, None, Some(attestation)))
+        }
+    }
+}
@@ -0,0 +1,223 @@
+extern crate alloc;
+
+use alloc::{
+    borrow::ToOwned,
+    format,
+    string::{String, ToString},
+};
+use cargo_manifest::Edition;
+use semver::Version;
+use zk_sca_guest_abi::{
+    PackageManager, PackageManagerRange, RustEdition, ScaError, ToolchainAttestation,
+    ToolchainPolicy,
+};
+use zk_sca_guest_abi_utils::ValidatedFile;
+
+/// First Cargo release that reads a `Cargo.lock` of the given format version.
+pub const fn min_cargo_for_lockfile(version: u32) -> Option<Version> {
+    match version {
+        2 => Some(Version::new(1, 38, 0)),
+        3 => Some(Version::new(1, 47, 0)),
+        4 => Some(Version::new(1, 78, 0)),
+        _ => None,
+    }
+}
+
+/// The ABI counterpart of a manifest's edition.
+pub const fn rust_edition(edition: Edition) -> RustEdition {
+    match edition {
+        Edition::E2015 => RustEdition::E2015,
+        Edition::E2018 => RustEdition::E2018,
+        Edition::E2021 => RustEdition::E2021,
+        Edition::E2024 => RustEdition::E2024,
+    }
+}
+
+/// First Cargo release that builds crates of the given edition.
+pub const fn min_cargo_for_edition(edition: Edition) -> Option<Version> {
+    match edition {
+        Edition::E2015 => None,
+        Edition::E2018 => Some(Version::new(1, 31, 0)),
+        Edition::E2021 => Some(Version::new(1, 56, 0)),
+        Edition::E2024 => Some(Version::new(1, 85, 0)),
+    }
+}
+
+/// Parse a `rust-version` field, which may omit the minor and patch components.
+pub fn parse_rust_version(text: &str) -> Option<Version> {
+    let mut parts = text.split('.').map(str::parse::<u64>);
+    let major = parts.next()?.ok()?;
+    let minor = parts.next().transpose().ok()?.unwrap_or(0);
+    let patch = parts.next().transpose().ok()?.unwrap_or(0);
+    parts
+        .next()
+        .is_none()
+        .then(|| Version::new(major, minor, patch))
+}
+
+/// The toolchain a `rust-toolchain.toml` or legacy `rust-toolchain` pins.
+#[derive(Debug, Clone)]
+pub struct ToolchainFile {
+    pub path: String,
+    /// `stable`, `nightly-2024-05-01`, `1.78`, ...; `None` if only a path is given.
+    pub channel: Option<String>,
+}
+
+/// The releases a toolchain channel admits, as `[min, max)`, if it names a release.
+///
+/// `1.78` admits every 1.78.x; `1.78.1` admits only that patch release.
+pub fn pinned_releases(channel: &str) -> Result<Option<(Version, Version)>, (ScaError, String)> {
+    let overflows = || {
+        (
+            ScaError::ManifestParseError,
+            format!("toolchain channel `{channel}` overflows a version number"),
+        )
+    };
+    // Strip an optional host triple, as in `1.78.0-x86_64-unknown-linux-gnu`.
+    let release = channel
+        .split_once('-')
+        .map_or(channel, |(release, _)| release);
+    let mut parts = release.split('.').map(str::parse::<u64>);
+    let (Some(Ok(major)), Some(Ok(minor))) = (parts.next(), parts.next()) else {
+        return Ok(None);
+    };
+    let releases = match parts.next() {
+        None => (
+            Version::new(major, minor, 0),
+            Version::new(major, minor.checked_add(1).ok_or_else(overflows)?, 0),
+        ),
+        Some(Ok(patch)) if parts.next().is_none() => (
+            Version::new(major, minor, patch),
+            Version::new(major, minor, patch.checked_add(1).ok_or_else(overflows)?),
+        ),
+        Some(_) => return Ok(None),
+    };
+    Ok(Some(releases))
+}
+
+/// Parse a `rust-toolchain.toml`, or a legacy `rust-toolchain` holding
+/// either the same TOML or a bare channel name.
+pub fn parse_toolchain_file(vf: &ValidatedFile) -> Result<ToolchainFile, (ScaError, String)> {
+    let path = &vf.header.name;
+    let text = core::str::from_utf8(&vf.bytes).map_err(|_| {
+        (
+            ScaError::InvalidManifestEncoding,
+            format!("`{path}` is not valid UTF‑8"),
+        )
+    })?;
+    let channel = match text.parse::<toml::Table>() {
+        Ok(table) => table
+            .get("toolchain")
+            .and_then(|t| t.get("channel"))
+            .and_then(toml::Value::as_str)
+            .map(ToOwned::to_owned),
+        Err(_) if path.file_name() == "rust-toolchain" && text.trim().lines().count() == 1 => {
+            Some(text.trim().to_owned())
+        }
+        Err(e) => {
+            return Err((
+                ScaError::ManifestParseError,
+                format!("Failed to parse `{path}`: {e}"),
+            ));
+        }
+    };
+    Ok(ToolchainFile {
+        path: path.to_string(),
+        channel,
+    })
+}
+
+/// Narrows down the Cargo releases an archive is consistent with, remembering
+/// which evidence set each bound.
+#[derive(Debug, Clone)]
+pub struct CargoVersionBounds {
+    min: Version,
+    min_reason: String,
+    max: Option<(Version, String)>,
+}
+
+impl Default for CargoVersionBounds {
+    fn default() -> Self {
+        Self {
+            min: Version::new(1, 0, 0),
+            min_reason: "Cargo 1.0".to_owned(),
+            max: None,
+        }
+    }
+}
+
+impl CargoVersionBounds {
+    /// Requires at least `version`, because of `reason`.
+    pub fn at_least(&mut self, version: Version, reason: impl FnOnce() -> String) {
+        if version > self.min {
+            self.min = version;
+            self.min_reason = reason();
+        }
+    }
+
+    /// Requires a release below `version`, because of `reason`.
+    pub fn below(&mut self, version: Version, reason: impl FnOnce() -> String) {
+        if self.max.as_ref().is_none_or(|(max, _)| version < *max) {
+            self.max = Some((version, reason()));
+        }
+    }
+
+    /// The consistent Cargo releases; an error if the evidence contradicts itself.
+    pub fn into_range(self) -> Result<PackageManagerRange, (ScaError, String)> {
+        let Self {
+            min,
+            min_reason,
+            max,
+        } = self;
+        let (max, max_reason) = max.unzip();
+        PackageManagerRange::try_new(PackageManager::Cargo, min, max).map_err(|_| {
+            (
+                ScaError::PackageManagerVersionMismatch,
+                format!(
+                    "{min_reason} conflicts with {}",
+                    max_reason.unwrap_or_default()
+                ),
+            )
+        })
+    }
+}
+
+/// Checks the pinned toolchain and every member's edition against `policy`.
+pub fn ensure_toolchain_is_permitted(
+    toolchain: &ToolchainAttestation,
+    policy: &ToolchainPolicy,
+) -> Result<(), (ScaError, String)> {
+    if policy.bounds_toolchain() {
+        let channel = toolchain.channel().ok_or_else(|| {
+            (
+                ScaError::ToolchainPolicyViolation,
+                "no rust-toolchain file pins the toolchain".to_owned(),
+            )
+        })?;
+        let (release, _) = pinned_releases(channel)?.ok_or_else(|| {
+            (
+                ScaError::ToolchainPolicyViolation,
+                format!("toolchain channel `{channel}` does not pin a release"),
+            )
+        })?;
+        if !policy.permits_toolchain(&release) {
+            return Err((
+                ScaError::ToolchainPolicyViolation,
+                format!("pinned toolchain `{channel}` is outside the permitted range"),
+            ));
+        }
+    }
+    for member in toolchain.members() {
+        if !policy.permits_edition(member.edition()) {
+            return Err((
+                ScaError::ToolchainPolicyViolation,
+                format!(
+                    "member `{}` uses edition {:?}, which the policy does not permit",
+                    member.name(),
+                    member.edition()
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,151 @@
+extern crate alloc;
+
+use alloc::{collections::BTreeMap, format, string::String, vec::Vec};
+use cargo_manifest::{Manifest, MaybeInherited};
+use hashbrown::HashMap;
+use risc0_zkvm::sha::{Impl, Sha256};
+use semver::Version;
+use serde::Deserialize;
+use zk_sca_guest_abi::ScaError;
+use zk_sca_guest_abi_utils::{TarEntryKind, ValidPartialArchive, to_hex};
+
+pub const CHECKSUM_FILE: &str = ".cargo-checksum.json";
+
+/// A crate directory of a `cargo vendor` directory source.
+#[derive(Debug, Clone)]
+pub struct VendoredCrate {
+    pub name: String,
+    pub version: Version,
+    /// SHA-256 of the `.crate` file it was unpacked from; `None` for git sources.
+    pub package_checksum: Option<String>,
+}
+
+/// The `.cargo-checksum.json` that `cargo vendor` writes into each crate directory.
+#[derive(Deserialize)]
+struct ChecksumFile {
+    /// SHA-256 of every file, keyed by path relative to the crate directory.
+    files: BTreeMap<String, String>,
+    package: Option<String>,
+}
+
+/// Verify every crate directory of the vendor directory `dir` (with a trailing
+/// `/`) and return the crates it provides.
+///
+/// Each crate directory must hold a `.cargo-checksum.json` listing exactly
+/// the files the archive has in that directory, each with a matching SHA-256.
+/// The crate is identified by its own `Cargo.toml`.
+pub fn verify_vendor_directory(
+    archive: &ValidPartialArchive,
+    dir: &str,
+) -> Result<Vec<VendoredCrate>, (ScaError, String)> {
+    let contents: HashMap<&str, &[u8]> = archive
+        .files
+        .iter()
+        .filter(|vf| vf.header.name.starts_with(dir))
+        .map(|vf| (vf.header.name.as_str(), vf.bytes.as_slice()))
+        .collect();
+    let read = |path: &str| {
+        contents.get(path).copied().ok_or_else(|| {
+            (
+                ScaError::VendoredSourceMismatch,
+                format!("vendored file `{path}` is missing"),
+            )
+        })
+    };
+
+    let mut crate_dirs: Vec<&str> = Vec::new();
+    for header in &archive.headers {
+        let Some(rest) = header.name.strip_prefix(dir) else {
+            continue;
+        };
+        // Cargo only looks inside the crate directories.
+        if let Some((crate_name, _)) = rest.split_once('/').filter(|(n, _)| !n.is_empty()) {
+            let crate_dir = &header.name[..=dir.len() + crate_name.len()];
+            if !crate_dirs.contains(&crate_dir) {
+                crate_dirs.push(crate_dir);
+            }
+        }
+    }
+
+    let mut crates = Vec::with_capacity(crate_dirs.len());
+    for crate_dir in crate_dirs {
+        let checksum_path = format!("{crate_dir}{CHECKSUM_FILE}");
+        let checksums: ChecksumFile =
+            serde_json::from_slice(read(&checksum_path)?).map_err(|e| {
+                (
+                    ScaError::VendoredSourceMismatch,
+                    format!("Failed to parse `{checksum_path}`: {e}"),
+                )
+            })?;
+
+        let mut listed = 0;
+        for header in &archive.headers {
+            let Some(file) = header.name.strip_prefix(crate_dir) else {
+                continue;
+            };
+            if header.kind == TarEntryKind::Directory || file == CHECKSUM_FILE {
+                continue;
+            }
+            let expected = checksums.files.get(file).ok_or_else(|| {
+                (
+                    ScaError::VendoredSourceMismatch,
+                    format!("`{}` is not listed in `{checksum_path}`", header.name),
+                )
+            })?;
+            if &sha256_hex(read(&header.name)?) != expected {
+                return Err((
+                    ScaError::VendoredSourceMismatch,
+                    format!("`{}` does not match `{checksum_path}`", header.name),
+                ));
+            }
+            listed += 1;
+        }
+        if listed != checksums.files.len() {
+            return Err((
+                ScaError::VendoredSourceMismatch,
+                format!("files listed in `{checksum_path}` are missing"),
+            ));
+        }
+
+        let (name, version) = parse_normalized_manifest(&format!("{crate_dir}Cargo.toml"), &read)?;
+        crates.push(VendoredCrate {
+            name,
+            version,
+            package_checksum: checksums.package,
+        });
+    }
+    Ok(crates)
+}
+
+/// Read the package name and version of a manifest that `cargo package`
+/// normalized, as found in `.crate` files and vendor directories.
+pub fn parse_normalized_manifest<'a>(
+    path: &str,
+    read: &impl Fn(&str) -> Result<&'a [u8], (ScaError, String)>,
+) -> Result<(String, Version), (ScaError, String)> {
+    let manifest = Manifest::from_slice(read(path)?).map_err(|e| {
+        (
+            ScaError::ManifestParseError,
+            format!("Failed to parse `{path}`: {e}"),
+        )
+    })?;
+    // A normalized manifest inherits nothing.
+    let package = manifest.package.as_ref();
+    let version = package
+        .and_then(|p| p.version.as_ref())
+        .and_then(|v| match v {
+            MaybeInherited::Local(v) => Version::parse(v).ok(),
+            MaybeInherited::Inherited { .. } => None,
+        });
+    match (package, version) {
+        (Some(package), Some(version)) => Ok((package.name.clone(), version)),
+        _ => Err((
+            ScaError::ManifestParseError,
+            format!("`{path}` does not declare a package name and version"),
+        )),
+    }
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    to_hex(Impl::hash_bytes(bytes).as_bytes())
+}
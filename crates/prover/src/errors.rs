@@ -35,6 +35,34 @@ pub enum ProverError {
     UnsupportedPackageManager(String),
     #[error("inconsistent package manager between archive and permitted deps: {0}")]
     InconsistentPackageManager(String),
+    #[error("requested workspace member not found: {0}")]
+    UnknownWorkspaceMember(String),
+    #[error("requested feature not found: {0}")]
+    UnknownFeature(String),
+    #[error("source override not permitted by policy: {0}")]
+    ForbiddenSourceOverride(String),
+    #[error("vendored sources do not match their checksums: {0}")]
+    VendoredSourceMismatch(String),
+    #[error("lockfile dependency reference does not name exactly one package: {0}")]
+    UnresolvableLockfileDependency(String),
+    #[error("claimed package manager version is inconsistent with the archive: {0}")]
+    PackageManagerVersionMismatch(String),
+    #[error("toolchain not permitted by policy: {0}")]
+    ToolchainPolicyViolation(String),
+    #[error("source is not a valid .crate package: {0}")]
+    InvalidCratePackage(String),
+    #[error("source is not a valid cargo-auditable binary: {0}")]
+    InvalidAuditableBinary(String),
+    #[error("option does not apply to this kind of source: {0}")]
+    InapplicableOption(String),
+    #[error("source is not a valid container image: {0}")]
+    InvalidContainerImage(String),
+    #[error("source is not a valid Git tree: {0}")]
+    InvalidGitTree(String),
+    #[error("archive has an invalid TAR header: {0}")]
+    InvalidTarHeader(String),
+    #[error("dependency file is a link that cannot be read: {0}")]
+    LinkedDependencyFile(String),
     #[error("failed to convert archive into Merkle tree: {0}")]
     ArchiveParseError(String),
     #[error("failed to execute prover (unknown guest error {0}): {1}")]
@@ -1,10 +1,19 @@
 use crate::{EnvVarGuard, ProverError};
 use risc0_zkvm::{ExecutorEnv, Receipt, default_prover};
-use std::sync::{LazyLock, Mutex};
+use std::{
+    io,
+    sync::{LazyLock, Mutex},
+};
 use zk_sca_guest::SCA_ELF;
 use zk_sca_guest_abi::{self as abi};
-use zk_sca_guest_abi_utils::build_merkle_archive;
-use zk_sca_types::{LicensePolicy, PermittedDependencies, SourceBundle};
+use zk_sca_guest_abi_utils::{
+    BuildOptions, build_merkle_archive_with, read_container_image, read_git_tree,
+};
+use zk_sca_types::{
+    ArtifactBundle, ArtifactKind, BundleKind, FeatureSelection, LicensePolicy, LinkPolicy,
+    MemberScope, MerkleFormat, OverridePolicy, PermittedDependencies, SourceBundle,
+    ToolchainPolicy,
+};
 
 #[non_exhaustive]
 #[derive(Debug, Clone, Copy, Default)]
@@ -13,6 +22,8 @@ pub struct ProverOpts {
     pub dev_mode: bool,
     /// Log cycle counts.
     pub cycle_report: bool,
+    /// Hash the archive on every available core.
+    pub parallel_hashing: bool,
 }
 
 /// Builder for proof configuration.
@@ -21,9 +32,17 @@ pub struct ProverOpts {
 /// chained in any order. Later calls overwrite earlier ones.
 #[derive(Debug, Clone)]
 pub struct Prover {
-    src_bundle: Option<SourceBundle>,
+    input: Option<ProverInput>,
     permitted_deps: Option<PermittedDependencies>,
     license_policy: Option<LicensePolicy>,
+    member_scope: Option<MemberScope>,
+    feature_selection: Option<FeatureSelection>,
+    override_policy: Option<OverridePolicy>,
+    verify_vendored_sources: bool,
+    link_policy: LinkPolicy,
+    merkle_format: MerkleFormat,
+    multiproof: bool,
+    toolchain_policy: Option<ToolchainPolicy>,
     opts: ProverOpts,
 }
 
@@ -34,18 +53,35 @@ impl Prover {
     #[must_use]
     pub fn new() -> Self {
         Self {
-            src_bundle: None,
+            input: None,
             permitted_deps: None,
             license_policy: None,
+            member_scope: None,
+            feature_selection: None,
+            override_policy: None,
+            verify_vendored_sources: false,
+            link_policy: LinkPolicy::default(),
+            merkle_format: MerkleFormat::default(),
+            multiproof: false,
+            toolchain_policy: None,
             opts: ProverOpts::default(),
         }
     }
 
-    /// Set the `SourceBundle` to be analyzed. This is required before calling `prove`.
+    /// Set the `SourceBundle` to be analyzed. This, or `with_artifact`, is
+    /// required before calling `prove`.
     #[must_use]
     pub fn with_bundle(&self, bundle: SourceBundle) -> Self {
         let mut next = self.clone();
-        next.src_bundle = Some(bundle);
+        next.input = Some(ProverInput::Source(bundle));
+        next
+    }
+
+    /// Set an `ArtifactBundle` to be analyzed in place of a `SourceBundle`.
+    #[must_use]
+    pub fn with_artifact(&self, artifact: ArtifactBundle) -> Self {
+        let mut next = self.clone();
+        next.input = Some(ProverInput::Artifact(artifact));
         next
     }
 
@@ -65,6 +101,78 @@ impl Prover {
         next
     }
 
+    /// Set the `MemberScope`. If unset, every package in every lockfile is audited.
+    #[must_use]
+    pub fn with_member_scope(&self, scope: &MemberScope) -> Self {
+        let mut next = self.clone();
+        next.member_scope = Some(scope.clone());
+        next
+    }
+
+    /// Set the `FeatureSelection`. If unset, every optional dependency is audited.
+    #[must_use]
+    pub fn with_feature_selection(&self, selection: &FeatureSelection) -> Self {
+        let mut next = self.clone();
+        next.feature_selection = Some(selection.clone());
+        next
+    }
+
+    /// Set the `OverridePolicy`. If unset, every source override is allowed.
+    #[must_use]
+    pub fn with_override_policy(&self, policy: &OverridePolicy) -> Self {
+        let mut next = self.clone();
+        next.override_policy = Some(policy.clone());
+        next
+    }
+
+    /// Check vendored crates against `.cargo-checksum.json` and `Cargo.lock`. Defaults to `false`.
+    #[must_use]
+    pub fn with_vendored_sources_verified(&self, verified: bool) -> Self {
+        let mut next = self.clone();
+        next.verify_vendored_sources = verified;
+        next
+    }
+
+    /// Set the `LinkPolicy` for dependency files stored as links. Defaults to rejecting them.
+    #[must_use]
+    pub fn with_link_policy(&self, policy: LinkPolicy) -> Self {
+        let mut next = self.clone();
+        next.link_policy = policy;
+        next
+    }
+
+    /// Set the `MerkleFormat` the archive is hashed in. Defaults to v1.
+    #[must_use]
+    pub fn with_merkle_format(&self, format: MerkleFormat) -> Self {
+        let mut next = self.clone();
+        next.merkle_format = format;
+        next
+    }
+
+    /// Send the guest one multiproof of the archive's leaves instead of a path per leaf. Defaults to `false`.
+    #[must_use]
+    pub fn with_multiproof(&self, enabled: bool) -> Self {
+        let mut next = self.clone();
+        next.multiproof = enabled;
+        next
+    }
+
+    /// Hash the archive on every available core. Defaults to `false`.
+    #[must_use]
+    pub fn with_parallel_hashing(&self, enabled: bool) -> Self {
+        let mut next = self.clone();
+        next.opts.parallel_hashing = enabled;
+        next
+    }
+
+    /// Set the `ToolchainPolicy`. If unset, any toolchain and edition is allowed.
+    #[must_use]
+    pub fn with_toolchain_policy(&self, policy: &ToolchainPolicy) -> Self {
+        let mut next = self.clone();
+        next.toolchain_policy = Some(policy.clone());
+        next
+    }
+
     /// Enable or disable dev mode (skips proof generation).
     #[must_use]
     pub fn with_dev_mode(&self, enabled: bool) -> Self {
@@ -83,10 +191,7 @@ impl Prover {
 
     /// Validate required fields and return a `ProverConfig`, or a `ProverError`.
     pub fn build(&mut self) -> Result<ProverConfig, ProverError> {
-        let bundle = self
-            .src_bundle
-            .take()
-            .ok_or(ProverError::MissingSourceArchive)?;
+        let input = self.input.take().ok_or(ProverError::MissingSourceArchive)?;
 
         let permitted_deps = self
             .permitted_deps
@@ -94,9 +199,17 @@ impl Prover {
             .ok_or(ProverError::MissingPermittedDependencies)?;
 
         Ok(ProverConfig {
-            bundle,
+            input,
             permitted_deps,
             license_policy: self.license_policy.clone(),
+            member_scope: self.member_scope.clone(),
+            feature_selection: self.feature_selection.clone(),
+            override_policy: self.override_policy.clone(),
+            verify_vendored_sources: self.verify_vendored_sources,
+            link_policy: self.link_policy,
+            merkle_format: self.merkle_format,
+            multiproof: self.multiproof,
+            toolchain_policy: self.toolchain_policy.clone(),
             opts: self.opts,
         })
     }
@@ -110,12 +223,27 @@ impl Prover {
     }
 }
 
+/// What a proof analyzes.
+#[derive(Debug, Clone)]
+pub enum ProverInput {
+    Source(SourceBundle),
+    Artifact(ArtifactBundle),
+}
+
 /// Validated configuration used by `prove`.
 #[derive(Debug, Clone)]
 pub struct ProverConfig {
-    pub bundle: SourceBundle,
+    pub input: ProverInput,
     pub permitted_deps: PermittedDependencies,
     pub license_policy: Option<LicensePolicy>,
+    pub member_scope: Option<MemberScope>,
+    pub feature_selection: Option<FeatureSelection>,
+    pub override_policy: Option<OverridePolicy>,
+    pub verify_vendored_sources: bool,
+    pub link_policy: LinkPolicy,
+    pub merkle_format: MerkleFormat,
+    pub multiproof: bool,
+    pub toolchain_policy: Option<ToolchainPolicy>,
     pub opts: ProverOpts,
 }
 
@@ -123,6 +251,47 @@ pub struct ProverConfig {
 static PROVE_LOCK: LazyLock<Mutex<()>> = LazyLock::new(|| Mutex::new(()));
 
 impl ProverConfig {
+    /// The source the guest analyzes, with the `.crate` file that goes with
+    /// it. Only a `SourceBundle` is built into a Merkle archive.
+    fn guest_source(&self) -> Result<(abi::Source, Option<Vec<u8>>), ProverError> {
+        let bundle = match &self.input {
+            ProverInput::Source(bundle) => bundle,
+            ProverInput::Artifact(artifact) => {
+                let resolved_with = artifact.resolved_with().clone();
+                let source = match artifact.kind() {
+                    ArtifactKind::AuditableBinary => abi::Source::AuditableBinary {
+                        resolved_with,
+                        binary: artifact.bytes().to_vec(),
+                    },
+                    ArtifactKind::ContainerImage => abi::Source::ContainerImage {
+                        resolved_with,
+                        format: self.merkle_format,
+                        image: read_container_image(artifact.bytes())
+                            .map_err(|e| ProverError::ArchiveParseError(e.to_string()))?,
+                    },
+                    ArtifactKind::GitTree => abi::Source::GitTree {
+                        resolved_with,
+                        tree: read_git_tree(artifact.bytes())
+                            .map_err(|e| ProverError::ArchiveParseError(e.to_string()))?,
+                    },
+                };
+                return Ok((source, None));
+            }
+        };
+
+        // Construct the Merkle archive from the provided source tar.gz.
+        let build_options = BuildOptions::default()
+            .with_vendored_sources(self.verify_vendored_sources)
+            .with_merkle_format(self.merkle_format)
+            .with_multiproof(self.multiproof)
+            .with_parallel_hashing(self.opts.parallel_hashing);
+        let merkle_archive = build_merkle_archive_with(bundle, build_options)
+            .map_err(|e| ProverError::ArchiveParseError(e.to_string()))?;
+        let crate_package =
+            (bundle.kind() == BundleKind::CratePackage).then(|| bundle.tar_gz().to_vec());
+        Ok((abi::Source::Archive(merkle_archive), crate_package))
+    }
+
     /// Generate the proof using this configuration.
     pub fn prove(self) -> Result<Receipt, ProverError> {
         // Only one proof may flip the RISC0_* env vars at a time.
@@ -131,23 +300,31 @@ impl ProverConfig {
         let _risc0_info_guard = EnvVarGuard::new("RISC0_INFO", "1", self.opts.cycle_report)?;
         let _rust_log_guard = EnvVarGuard::new("RUST_LOG", "info", self.opts.cycle_report)?;
 
-        // Construct the Merkle archive from the provided source tar.gz.
-        let merkle_archive = build_merkle_archive(&self.bundle)
-            .map_err(|e| ProverError::ArchiveParseError(e.to_string()))?;
+        let (source, crate_package) = self.guest_source()?;
 
         // Create the ABI‐level GuestInput that will be written into the prover environment.
         let guest_input = abi::GuestInput {
-            src_archive: merkle_archive,
+            source,
             permitted_deps: self.permitted_deps,
             license_policy: self.license_policy,
+            member_scope: self.member_scope,
+            feature_selection: self.feature_selection,
+            override_policy: self.override_policy,
+            verify_vendored_sources: self.verify_vendored_sources,
+            link_policy: self.link_policy,
+            toolchain_policy: self.toolchain_policy,
+            crate_package,
         };
 
         // Build the RISC0 executor environment by writing the GuestInput.
-        let exec_env = ExecutorEnv::builder()
-            .write(&guest_input)
-            .unwrap()
-            .build()
-            .unwrap();
+        // The guest logs the end of each stage to stdout, which only the cycle
+        // report shows.
+        let mut exec_env = ExecutorEnv::builder();
+        exec_env.write(&guest_input).unwrap();
+        if !self.opts.cycle_report {
+            exec_env.stdout(io::sink());
+        }
+        let exec_env = exec_env.build().unwrap();
 
         // Run the prover. If the guest panics, parse the panic message as "{code}|{detail}".
         let receipt = match default_prover().prove(exec_env, SCA_ELF) {
@@ -176,6 +353,20 @@ impl ProverConfig {
                             14 => ProverError::InvalidWorkspaceCount(detail.to_string()),
                             15 => ProverError::UnsupportedPackageManager(detail.to_string()),
                             16 => ProverError::InconsistentPackageManager(detail.to_string()),
+                            17 => ProverError::UnknownWorkspaceMember(detail.to_string()),
+                            18 => ProverError::UnknownFeature(detail.to_string()),
+                            19 => ProverError::ForbiddenSourceOverride(detail.to_string()),
+                            20 => ProverError::VendoredSourceMismatch(detail.to_string()),
+                            21 => ProverError::UnresolvableLockfileDependency(detail.to_string()),
+                            22 => ProverError::PackageManagerVersionMismatch(detail.to_string()),
+                            23 => ProverError::ToolchainPolicyViolation(detail.to_string()),
+                            24 => ProverError::InvalidCratePackage(detail.to_string()),
+                            25 => ProverError::InvalidAuditableBinary(detail.to_string()),
+                            26 => ProverError::InapplicableOption(detail.to_string()),
+                            27 => ProverError::InvalidContainerImage(detail.to_string()),
+                            28 => ProverError::InvalidGitTree(detail.to_string()),
+                            29 => ProverError::InvalidTarHeader(detail.to_string()),
+                            30 => ProverError::LinkedDependencyFile(detail.to_string()),
                             _ => ProverError::UnknownGuestError(code, detail.to_string()),
                         };
                         return Err(err);
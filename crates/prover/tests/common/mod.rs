@@ -4,16 +4,20 @@ use std::{
     path::Path,
     sync::{LazyLock, Mutex},
 };
-use zk_sca_guest_abi::PartialMerkleArchive;
-use zk_sca_guest_abi_utils::build_merkle_archive;
+use zk_sca_guest_abi::{ContainerImage, GitTree, MerkleFormat, PartialMerkleArchive};
+use zk_sca_guest_abi_utils::{
+    BuildOptions, build_merkle_archive, build_merkle_archive_with, read_container_image,
+    read_git_tree,
+};
 use zk_sca_types::{
-    PackageManager, PackageManagerSpec, PermittedDependencies, SourceBundle, Version,
+    ArtifactBundle, ArtifactKind, BundleKind, PackageManager, PackageManagerSpec,
+    PermittedDependencies, SourceBundle, Version,
 };
 
 static FIXTURE_CACHE: LazyLock<Mutex<HashMap<String, Vec<u8>>>> =
     LazyLock::new(|| Mutex::new(HashMap::new()));
 
-fn load_fixture(name: &str) -> Vec<u8> {
+pub fn load_fixture(name: &str) -> Vec<u8> {
     let mut cache = FIXTURE_CACHE.lock().unwrap();
     if let Some(data) = cache.get(name) {
         return data.clone();
@@ -30,13 +34,72 @@ fn load_fixture(name: &str) -> Vec<u8> {
 }
 
 pub fn load_cargo_bundle(name: &str) -> SourceBundle {
+    load_cargo_bundle_resolved_with(name, Version::new(1, 82, 0))
+}
+
+pub fn load_cargo_bundle_resolved_with(name: &str, cargo_version: Version) -> SourceBundle {
     let tar_gz = load_fixture(name);
     SourceBundle::from_vec(
         tar_gz,
+        PackageManagerSpec::new(PackageManager::Cargo, cargo_version),
+    )
+}
+
+pub fn load_crate_bundle(name: &str) -> SourceBundle {
+    load_cargo_bundle(name).with_kind(BundleKind::CratePackage)
+}
+
+// False warning bc not used in packed_input.rs.
+#[allow(dead_code)]
+pub fn load_auditable_bundle(name: &str) -> ArtifactBundle {
+    ArtifactBundle::from_vec(
+        load_fixture(name),
+        PackageManagerSpec::new(PackageManager::Cargo, Version::new(1, 82, 0)),
+        ArtifactKind::AuditableBinary,
+    )
+}
+
+// False warning bc not used in packed_input.rs.
+#[allow(dead_code)]
+pub fn load_image_bundle(name: &str) -> ArtifactBundle {
+    ArtifactBundle::from_vec(
+        load_fixture(name),
+        PackageManagerSpec::new(PackageManager::Cargo, Version::new(1, 82, 0)),
+        ArtifactKind::ContainerImage,
+    )
+}
+
+// False warning bc not used in end_to_end.rs.
+#[allow(dead_code)]
+pub fn load_container_image(name: &str) -> ContainerImage {
+    read_container_image(&load_fixture(name))
+        .unwrap_or_else(|_| panic!("Fixture parse failed for {}", name))
+}
+
+// False warning bc not used in packed_input.rs.
+#[allow(dead_code)]
+pub fn load_git_bundle(name: &str) -> ArtifactBundle {
+    ArtifactBundle::from_vec(
+        load_fixture(name),
         PackageManagerSpec::new(PackageManager::Cargo, Version::new(1, 82, 0)),
+        ArtifactKind::GitTree,
     )
 }
 
+// False warning bc not used in end_to_end.rs.
+#[allow(dead_code)]
+pub fn load_git_tree(name: &str) -> GitTree {
+    read_git_tree(&load_fixture(name))
+        .unwrap_or_else(|_| panic!("Fixture parse failed for {}", name))
+}
+
+// False warning bc not used in end_to_end.rs.
+#[allow(dead_code)]
+pub fn load_crate_archive(name: &str) -> PartialMerkleArchive {
+    let bundle = load_crate_bundle(name);
+    build_merkle_archive(&bundle).unwrap_or_else(|_| panic!("Fixture parse failed for {}", name))
+}
+
 // False warning bc not used in end_to_end.rs.
 #[allow(dead_code)]
 pub fn load_cargo_archive(name: &str) -> PartialMerkleArchive {
@@ -44,6 +107,38 @@ pub fn load_cargo_archive(name: &str) -> PartialMerkleArchive {
     build_merkle_archive(&bundle).unwrap_or_else(|_| panic!("Fixture parse failed for {}", name))
 }
 
+// False warning bc not used in end_to_end.rs.
+#[allow(dead_code)]
+pub fn load_cargo_archive_with_format(name: &str, format: MerkleFormat) -> PartialMerkleArchive {
+    let bundle = load_cargo_bundle(name);
+    let options = BuildOptions::default().with_merkle_format(format);
+    build_merkle_archive_with(&bundle, options)
+        .unwrap_or_else(|_| panic!("Fixture parse failed for {}", name))
+}
+
+// False warning bc not used in end_to_end.rs.
+#[allow(dead_code)]
+pub fn load_cargo_archive_with_multiproof(
+    name: &str,
+    format: MerkleFormat,
+) -> PartialMerkleArchive {
+    let bundle = load_cargo_bundle(name);
+    let options = BuildOptions::default()
+        .with_merkle_format(format)
+        .with_multiproof(true);
+    build_merkle_archive_with(&bundle, options)
+        .unwrap_or_else(|_| panic!("Fixture parse failed for {}", name))
+}
+
+// False warning bc not used in end_to_end.rs.
+#[allow(dead_code)]
+pub fn load_cargo_archive_with_vendored_sources(name: &str) -> PartialMerkleArchive {
+    let bundle = load_cargo_bundle(name);
+    let options = BuildOptions::default().with_vendored_sources(true);
+    build_merkle_archive_with(&bundle, options)
+        .unwrap_or_else(|_| panic!("Fixture parse failed for {}", name))
+}
+
 // False warning bc not used in env_conflict.rs.
 #[allow(dead_code)]
 pub fn load_permitted_deps(name: &str) -> PermittedDependencies {
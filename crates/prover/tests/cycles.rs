@@ -0,0 +1,114 @@
+//! Reports how many cycles the guest spends in each stage of an analysis.
+//!
+//! Run with `cargo test -p zk-sca-prover --test cycles -- --nocapture` to see
+//! the report.
+
+use risc0_zkvm::{ExecutorEnv, default_executor};
+use zk_sca_guest::SCA_ELF;
+use zk_sca_guest_abi::{GuestInput, MerkleFormat, PartialMerkleArchive, Source};
+use zk_sca_types::LinkPolicy;
+
+mod common;
+use crate::common::{
+    load_cargo_archive_with_format, load_cargo_archive_with_multiproof, load_permitted_deps,
+};
+
+/// The stages the guest marks the end of, in order.
+const STAGES: [&str; 7] = [
+    "read input",
+    "validate archive",
+    "git tree and links",
+    "crate package",
+    "cargo source",
+    "audit",
+    "commit",
+];
+
+/// Executes the guest on `archive` and returns the cycles spent in each of
+/// [`STAGES`], and in all.
+fn stage_cycles(archive: PartialMerkleArchive) -> (Vec<u64>, u64) {
+    let guest_input = GuestInput {
+        source: Source::Archive(archive),
+        permitted_deps: load_permitted_deps("permitted-dependencies.json"),
+        license_policy: None,
+        member_scope: None,
+        feature_selection: None,
+        override_policy: None,
+        verify_vendored_sources: false,
+        link_policy: LinkPolicy::Reject,
+        toolchain_policy: None,
+        crate_package: None,
+    };
+    let mut stdout = Vec::new();
+    let exec_env = ExecutorEnv::builder()
+        .write(&guest_input)
+        .unwrap()
+        .stdout(&mut stdout)
+        .build()
+        .unwrap();
+    let session = default_executor().execute(exec_env, SCA_ELF).unwrap();
+
+    // Each mark reads `R0VM[<cycle>] <stage>`.
+    let marks: Vec<(u64, String)> = String::from_utf8(stdout)
+        .unwrap()
+        .lines()
+        .filter_map(|line| {
+            let (cycle, stage) = line.strip_prefix("R0VM[")?.split_once("] ")?;
+            Some((cycle.parse().ok()?, stage.to_string()))
+        })
+        .collect();
+    let stages: Vec<&str> = marks.iter().map(|(_, stage)| stage.as_str()).collect();
+    assert_eq!(stages, STAGES, "the guest marked other stages");
+
+    let mut start = 0;
+    let cycles = marks
+        .iter()
+        .map(|&(end, _)| end - std::mem::replace(&mut start, end))
+        .collect();
+    (cycles, session.cycles())
+}
+
+#[test]
+fn report_stage_cycles() {
+    let runs = [
+        (
+            "v1",
+            load_cargo_archive_with_format("safe.tar.gz", MerkleFormat::V1),
+        ),
+        (
+            "v2",
+            load_cargo_archive_with_format("safe.tar.gz", MerkleFormat::V2),
+        ),
+        (
+            "file records",
+            load_cargo_archive_with_format("safe.tar.gz", MerkleFormat::FileRecords),
+        ),
+        (
+            "v2 multiproof",
+            load_cargo_archive_with_multiproof("safe.tar.gz", MerkleFormat::V2),
+        ),
+    ];
+
+    print!("{:<20}", "stage");
+    for (name, _) in &runs {
+        print!("{name:>16}");
+    }
+    println!();
+    let reports: Vec<(Vec<u64>, u64)> = runs
+        .into_iter()
+        .map(|(_, archive)| stage_cycles(archive))
+        .collect();
+    for (i, stage) in STAGES.iter().enumerate() {
+        print!("{stage:<20}");
+        for (cycles, _) in &reports {
+            print!("{:>16}", cycles[i]);
+        }
+        println!();
+    }
+    print!("{:<20}", "total");
+    for (cycles, total) in &reports {
+        assert!(cycles.iter().sum::<u64>() <= *total);
+        print!("{total:>16}");
+    }
+    println!();
+}
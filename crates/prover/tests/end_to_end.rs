@@ -1,8 +1,17 @@
+use zk_sca_guest_abi_utils::{build_merkle_archive, to_hex, validate_merkle_archive};
 use zk_sca_prover::{Prover, ProverError};
-use zk_sca_types::{LicensePolicy, PackageManager, PackageManagerSpec, SourceBundle, Version};
+use zk_sca_types::{
+    FeatureSelection, LicensePolicy, LinkPolicy, MemberScope, MerkleFormat, PackageManager,
+    PackageManagerSpec, RustEdition, SourceBundle, ToolchainPolicy, Version,
+};
+
+const PAX_ROOT: &str = "5b187880b0f0747340cdc9ac4492345450e654c6ea8bf3819ddd1fdabcc65c68";
 
 mod common;
-use crate::common::{load_cargo_bundle, load_permitted_deps};
+use crate::common::{
+    load_auditable_bundle, load_cargo_bundle, load_cargo_bundle_resolved_with, load_crate_bundle,
+    load_git_bundle, load_image_bundle, load_permitted_deps,
+};
 
 #[test]
 fn happy_path_no_dependencies_declared() {
@@ -119,6 +128,395 @@ fn happy_path_with_dependencies_and_license_policy_with_cycle_report() {
     assert!(result.is_ok(), "Expected Ok(Receipt), got {:?}", result);
 }
 
+#[test]
+fn happy_path_member_scope_excluding_dev_dependencies() {
+    let bundle = load_cargo_bundle("member_scope.tar.gz");
+    let permitted = load_permitted_deps("permitted-dependencies.json");
+    let scope = MemberScope::try_new(vec!["app".to_owned()])
+        .unwrap()
+        .with_dev_dependencies_excluded(true);
+
+    let prover = Prover::new()
+        .with_bundle(bundle)
+        .with_permitted_deps(&permitted)
+        .with_member_scope(&scope)
+        .with_dev_mode(true)
+        .with_cycle_report(false);
+
+    let result = prover.prove();
+    assert!(result.is_ok(), "Expected Ok(Receipt), got {:?}", result);
+}
+
+#[test]
+fn happy_path_default_features_only() {
+    let bundle = load_cargo_bundle("optional_features.tar.gz");
+    let permitted = load_permitted_deps("permitted-dependencies.json");
+    let features = FeatureSelection::try_new(Vec::new(), true).unwrap();
+
+    let prover = Prover::new()
+        .with_bundle(bundle)
+        .with_permitted_deps(&permitted)
+        .with_feature_selection(&features)
+        .with_dev_mode(true)
+        .with_cycle_report(false);
+
+    let result = prover.prove();
+    assert!(result.is_ok(), "Expected Ok(Receipt), got {:?}", result);
+}
+
+#[test]
+fn happy_path_cargo_lockfile_v1() {
+    let bundle = load_cargo_bundle("cargo_lock_v1.tar.gz");
+    let permitted = load_permitted_deps("permitted-dependencies.json");
+
+    let prover = Prover::new()
+        .with_bundle(bundle)
+        .with_permitted_deps(&permitted)
+        .with_dev_mode(true)
+        .with_cycle_report(false);
+
+    let result = prover.prove();
+    assert!(result.is_ok(), "Expected Ok(Receipt), got {:?}", result);
+}
+
+#[test]
+fn happy_path_cargo_lockfile_v2() {
+    let bundle = load_cargo_bundle("cargo_lock_v2.tar.gz");
+    let permitted = load_permitted_deps("permitted-dependencies.json");
+
+    let prover = Prover::new()
+        .with_bundle(bundle)
+        .with_permitted_deps(&permitted)
+        .with_dev_mode(true)
+        .with_cycle_report(false);
+
+    let result = prover.prove();
+    assert!(result.is_ok(), "Expected Ok(Receipt), got {:?}", result);
+}
+
+#[test]
+fn happy_path_pinned_toolchain() {
+    let bundle = load_cargo_bundle_resolved_with("pinned_toolchain.tar.gz", Version::new(1, 80, 1));
+    let permitted = load_permitted_deps("permitted-dependencies.json");
+
+    let prover = Prover::new()
+        .with_bundle(bundle)
+        .with_permitted_deps(&permitted)
+        .with_dev_mode(true)
+        .with_cycle_report(false);
+
+    let result = prover.prove();
+    assert!(result.is_ok(), "Expected Ok(Receipt), got {:?}", result);
+}
+
+#[test]
+fn happy_path_legacy_toolchain_file_at_archive_root() {
+    let bundle = load_cargo_bundle_resolved_with("root_toolchain.tar.gz", Version::new(1, 80, 1));
+    let permitted = load_permitted_deps("permitted-dependencies.json");
+
+    let prover = Prover::new()
+        .with_bundle(bundle)
+        .with_permitted_deps(&permitted)
+        .with_dev_mode(true)
+        .with_cycle_report(false);
+
+    let result = prover.prove();
+    assert!(result.is_ok(), "Expected Ok(Receipt), got {:?}", result);
+}
+
+#[test]
+fn happy_path_toolchain_policy() {
+    let bundle = load_cargo_bundle_resolved_with("pinned_toolchain.tar.gz", Version::new(1, 80, 1));
+    let permitted = load_permitted_deps("permitted-dependencies.json");
+    let policy = ToolchainPolicy::try_new(
+        Some(Version::new(1, 75, 0)),
+        Some(Version::new(1, 82, 0)),
+        Some(RustEdition::E2021),
+        None,
+    )
+    .unwrap();
+
+    let prover = Prover::new()
+        .with_bundle(bundle)
+        .with_permitted_deps(&permitted)
+        .with_toolchain_policy(&policy)
+        .with_dev_mode(true)
+        .with_cycle_report(false);
+
+    let result = prover.prove();
+    assert!(result.is_ok(), "Expected Ok(Receipt), got {:?}", result);
+}
+
+#[test]
+fn happy_path_crate_package() {
+    let bundle = load_crate_bundle("safe-0.1.0.crate");
+    let permitted = load_permitted_deps("permitted-dependencies.json");
+
+    let prover = Prover::new()
+        .with_bundle(bundle)
+        .with_permitted_deps(&permitted)
+        .with_dev_mode(true)
+        .with_cycle_report(false);
+
+    let result = prover.prove();
+    assert!(result.is_ok(), "Expected Ok(Receipt), got {:?}", result);
+}
+
+#[test]
+fn happy_path_auditable_binary() {
+    let artifact = load_auditable_bundle("safe_auditable");
+    let permitted = load_permitted_deps("permitted-dependencies.json");
+
+    let prover = Prover::new()
+        .with_artifact(artifact)
+        .with_permitted_deps(&permitted)
+        .with_dev_mode(true)
+        .with_cycle_report(false);
+
+    let result = prover.prove();
+    assert!(result.is_ok(), "Expected Ok(Receipt), got {:?}", result);
+}
+
+#[test]
+fn happy_path_container_image() {
+    let artifact = load_image_bundle("safe_image.tar");
+    let permitted = load_permitted_deps("permitted-dependencies.json");
+
+    let prover = Prover::new()
+        .with_artifact(artifact)
+        .with_permitted_deps(&permitted)
+        .with_dev_mode(true)
+        .with_cycle_report(false);
+
+    let result = prover.prove();
+    assert!(result.is_ok(), "Expected Ok(Receipt), got {:?}", result);
+}
+
+#[test]
+fn happy_path_git_tree() {
+    let artifact = load_git_bundle("safe_git.objects");
+    let permitted = load_permitted_deps("permitted-dependencies.json");
+
+    let prover = Prover::new()
+        .with_artifact(artifact)
+        .with_permitted_deps(&permitted)
+        .with_dev_mode(true)
+        .with_cycle_report(false);
+
+    let result = prover.prove();
+    assert!(result.is_ok(), "Expected Ok(Receipt), got {:?}", result);
+}
+
+#[test]
+fn happy_path_source_replacement() {
+    let bundle = load_cargo_bundle("source_replacement.tar.gz");
+    let permitted = load_permitted_deps("permitted-dependencies.json");
+
+    let prover = Prover::new()
+        .with_bundle(bundle)
+        .with_permitted_deps(&permitted)
+        .with_dev_mode(true)
+        .with_cycle_report(false);
+
+    let result = prover.prove();
+    assert!(result.is_ok(), "Expected Ok(Receipt), got {:?}", result);
+}
+
+#[test]
+fn happy_path_vendored_sources() {
+    let bundle = load_cargo_bundle("vendored.tar.gz");
+    let permitted = load_permitted_deps("permitted-dependencies.json");
+
+    let prover = Prover::new()
+        .with_bundle(bundle)
+        .with_permitted_deps(&permitted)
+        .with_vendored_sources_verified(true)
+        .with_dev_mode(true)
+        .with_cycle_report(false);
+
+    let result = prover.prove();
+    assert!(result.is_ok(), "Expected Ok(Receipt), got {:?}", result);
+}
+
+#[test]
+fn happy_path_pax_extended_headers() {
+    let bundle = load_cargo_bundle("safe_pax.tar.gz");
+    let permitted = load_permitted_deps("permitted-dependencies.json");
+
+    let prover = Prover::new()
+        .with_bundle(bundle)
+        .with_permitted_deps(&permitted)
+        .with_dev_mode(true)
+        .with_cycle_report(false);
+
+    let result = prover.prove();
+    assert!(result.is_ok(), "Expected Ok(Receipt), got {:?}", result);
+}
+
+#[test]
+fn happy_path_gnu_long_names() {
+    let bundle = load_cargo_bundle("safe_gnu_long_names.tar.gz");
+    let permitted = load_permitted_deps("permitted-dependencies.json");
+
+    let prover = Prover::new()
+        .with_bundle(bundle)
+        .with_permitted_deps(&permitted)
+        .with_dev_mode(true)
+        .with_cycle_report(false);
+
+    let result = prover.prove();
+    assert!(result.is_ok(), "Expected Ok(Receipt), got {:?}", result);
+}
+
+#[test]
+fn happy_path_resolved_links() {
+    let bundle = load_cargo_bundle("linked_dependency_files.tar.gz");
+    let permitted = load_permitted_deps("permitted-dependencies.json");
+
+    let prover = Prover::new()
+        .with_bundle(bundle)
+        .with_permitted_deps(&permitted)
+        .with_link_policy(LinkPolicy::Resolve)
+        .with_dev_mode(true)
+        .with_cycle_report(false);
+
+    let result = prover.prove();
+    assert!(result.is_ok(), "Expected Ok(Receipt), got {:?}", result);
+}
+
+#[test]
+fn happy_path_merkle_format_v2() {
+    let bundle = load_cargo_bundle("safe.tar.gz");
+    let permitted = load_permitted_deps("permitted-dependencies.json");
+
+    let prover = Prover::new()
+        .with_bundle(bundle)
+        .with_permitted_deps(&permitted)
+        .with_merkle_format(MerkleFormat::V2)
+        .with_dev_mode(true)
+        .with_cycle_report(false);
+
+    let result = prover.prove();
+    assert!(result.is_ok(), "Expected Ok(Receipt), got {:?}", result);
+}
+
+#[test]
+fn happy_path_crate_package_merkle_format_v2() {
+    let bundle = load_crate_bundle("safe-0.1.0.crate");
+    let permitted = load_permitted_deps("permitted-dependencies.json");
+
+    let prover = Prover::new()
+        .with_bundle(bundle)
+        .with_permitted_deps(&permitted)
+        .with_merkle_format(MerkleFormat::V2)
+        .with_dev_mode(true)
+        .with_cycle_report(false);
+
+    let result = prover.prove();
+    assert!(result.is_ok(), "Expected Ok(Receipt), got {:?}", result);
+}
+
+#[test]
+fn happy_path_merkle_format_file_records() {
+    let bundle = load_cargo_bundle("safe_pax.tar.gz");
+    let permitted = load_permitted_deps("permitted-dependencies.json");
+
+    let prover = Prover::new()
+        .with_bundle(bundle)
+        .with_permitted_deps(&permitted)
+        .with_merkle_format(MerkleFormat::FileRecords)
+        .with_dev_mode(true)
+        .with_cycle_report(false);
+
+    let result = prover.prove();
+    assert!(result.is_ok(), "Expected Ok(Receipt), got {:?}", result);
+}
+
+#[test]
+fn happy_path_multiproof() {
+    let bundle = load_cargo_bundle("safe.tar.gz");
+    let permitted = load_permitted_deps("permitted-dependencies.json");
+
+    let prover = Prover::new()
+        .with_bundle(bundle)
+        .with_permitted_deps(&permitted)
+        .with_multiproof(true)
+        .with_dev_mode(true)
+        .with_cycle_report(false);
+
+    let result = prover.prove();
+    assert!(result.is_ok(), "Expected Ok(Receipt), got {:?}", result);
+}
+
+#[test]
+fn happy_path_parallel_hashing() {
+    let bundle = load_cargo_bundle("safe.tar.gz");
+    let permitted = load_permitted_deps("permitted-dependencies.json");
+
+    let prover = Prover::new()
+        .with_bundle(bundle)
+        .with_permitted_deps(&permitted)
+        .with_merkle_format(MerkleFormat::V2)
+        .with_parallel_hashing(true)
+        .with_dev_mode(true)
+        .with_cycle_report(false);
+
+    let result = prover.prove();
+    assert!(result.is_ok(), "Expected Ok(Receipt), got {:?}", result);
+}
+
+#[test]
+fn happy_path_merkle_format_file_records_multiproof() {
+    let bundle = load_cargo_bundle("safe.tar.gz");
+    let permitted = load_permitted_deps("permitted-dependencies.json");
+
+    let prover = Prover::new()
+        .with_bundle(bundle)
+        .with_permitted_deps(&permitted)
+        .with_merkle_format(MerkleFormat::FileRecords)
+        .with_multiproof(true)
+        .with_dev_mode(true)
+        .with_cycle_report(false);
+
+    let result = prover.prove();
+    assert!(result.is_ok(), "Expected Ok(Receipt), got {:?}", result);
+}
+
+#[test]
+fn happy_path_crate_package_merkle_format_file_records() {
+    let bundle = load_crate_bundle("safe-0.1.0.crate");
+    let permitted = load_permitted_deps("permitted-dependencies.json");
+
+    let prover = Prover::new()
+        .with_bundle(bundle)
+        .with_permitted_deps(&permitted)
+        .with_merkle_format(MerkleFormat::FileRecords)
+        .with_dev_mode(true)
+        .with_cycle_report(false);
+
+    let result = prover.prove();
+    assert!(result.is_ok(), "Expected Ok(Receipt), got {:?}", result);
+}
+
+#[test]
+fn reject_linked_dependency_files() {
+    let bundle = load_cargo_bundle("linked_dependency_files.tar.gz");
+    let permitted = load_permitted_deps("permitted-dependencies.json");
+
+    let prover = Prover::new()
+        .with_bundle(bundle)
+        .with_permitted_deps(&permitted)
+        .with_dev_mode(true)
+        .with_cycle_report(false);
+
+    let result = prover.prove();
+    assert!(
+        matches!(result, Err(ProverError::LinkedDependencyFile(_))),
+        "Expected Err(ProverError::LinkedDependencyFile(_)), got {:?}",
+        result
+    );
+}
+
 #[test]
 fn missing_source_archive() {
     let permitted = load_permitted_deps("permitted-dependencies.json");
@@ -171,10 +569,18 @@ fn archive_parse_error() {
 }
 
 #[test]
-fn reject_pax_tar_format() {
+fn accept_pax_tar_format() {
+    // Old GNU `ustar  ` headers are authenticated like POSIX ones. The archive
+    // has no workspace, so proving stops only once it has been read.
     let bundle = load_cargo_bundle("pax.tar.gz");
-    let permitted = load_permitted_deps("permitted-dependencies.json");
+    let archive = build_merkle_archive(&bundle).expect("Expected PAX TAR to build");
+    assert_eq!(to_hex(&archive.root_hash), PAX_ROOT);
 
+    let valid = validate_merkle_archive(&archive).expect("Expected PAX TAR to validate");
+    let names: Vec<_> = valid.headers.iter().map(|h| h.name.as_str()).collect();
+    assert_eq!(names, ["no_deps", "no_deps/no_deps.txt"]);
+
+    let permitted = load_permitted_deps("permitted-dependencies.json");
     let prover = Prover::new()
         .with_bundle(bundle)
         .with_permitted_deps(&permitted)
@@ -183,8 +589,8 @@ fn reject_pax_tar_format() {
 
     let err = prover.prove().unwrap_err();
     assert!(
-        matches!(err, ProverError::ArchiveParseError(_)),
-        "Expected ArchiveParseError for PAX TAR, got {:?}",
+        matches!(err, ProverError::InvalidWorkspaceCount(_)),
+        "Expected InvalidWorkspaceCount for PAX TAR, got {:?}",
         err
     );
 }
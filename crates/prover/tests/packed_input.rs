@@ -0,0 +1,188 @@
+use risc0_zkvm::serde::{from_slice, to_vec};
+use zk_sca_guest_abi::{
+    ContainerImage, GitTree, GuestInput, MerkleFormat, PACKED_ARCHIVE_V1, PartialMerkleArchive,
+    Source,
+};
+use zk_sca_types::{LinkPolicy, PackageManager, PackageManagerSpec, Version};
+
+mod common;
+use crate::common::{
+    load_cargo_archive_with_format, load_cargo_archive_with_multiproof,
+    load_cargo_archive_with_vendored_sources, load_container_image, load_crate_archive,
+    load_fixture, load_git_tree, load_permitted_deps,
+};
+
+fn guest_input(archive: PartialMerkleArchive) -> GuestInput {
+    GuestInput {
+        source: Source::Archive(archive),
+        permitted_deps: load_permitted_deps("permitted-dependencies.json"),
+        license_policy: None,
+        member_scope: None,
+        feature_selection: None,
+        override_policy: None,
+        verify_vendored_sources: false,
+        link_policy: LinkPolicy::Reject,
+        toolchain_policy: None,
+        crate_package: None,
+    }
+}
+
+/// An archive of every shape the prover builds.
+fn archives() -> Vec<(&'static str, PartialMerkleArchive)> {
+    let mut archives = vec![
+        (
+            "vendored",
+            load_cargo_archive_with_vendored_sources("vendored.tar.gz"),
+        ),
+        ("crate", load_crate_archive("safe-0.1.0.crate")),
+    ];
+    for format in [
+        MerkleFormat::V1,
+        MerkleFormat::V2,
+        MerkleFormat::FileRecords,
+    ] {
+        archives.push((
+            "block paths",
+            load_cargo_archive_with_format("safe.tar.gz", format),
+        ));
+        archives.push((
+            "multiproof",
+            load_cargo_archive_with_multiproof("safe.tar.gz", format),
+        ));
+    }
+    archives
+}
+
+#[test]
+fn packed_archive_round_trips_to_its_serde_form() {
+    for (name, archive) in archives() {
+        let format = archive.format;
+        let serde_form: PartialMerkleArchive = from_slice(&to_vec(&archive).unwrap()).unwrap();
+        let input: GuestInput = from_slice(&to_vec(&guest_input(archive)).unwrap()).unwrap();
+        assert_eq!(
+            input.source,
+            Source::Archive(serde_form),
+            "{name} archive in {format:?}"
+        );
+    }
+}
+
+#[test]
+fn packed_archive_is_smaller_than_its_serde_form() {
+    for (name, archive) in archives() {
+        let serde_words = to_vec(&archive).unwrap().len();
+        let input = guest_input(archive);
+        let packed_words =
+            to_vec(&input).unwrap().len() - to_vec(&input.permitted_deps).unwrap().len();
+        assert!(
+            packed_words * 3 < serde_words,
+            "{name} archive packs into {packed_words} words, against {serde_words} for serde"
+        );
+    }
+}
+
+#[test]
+fn unknown_packed_archive_version_is_rejected() {
+    let input = guest_input(load_cargo_archive_with_format(
+        "safe.tar.gz",
+        MerkleFormat::V2,
+    ));
+    let mut words = to_vec(&input).unwrap();
+    // The source comes first, and the archive's version right after its variant.
+    assert_eq!(words[1], PACKED_ARCHIVE_V1);
+    words[1] = PACKED_ARCHIVE_V1 + 1;
+    assert!(from_slice::<GuestInput, _>(&words).is_err());
+}
+
+#[test]
+fn truncated_packed_archive_is_rejected() {
+    let input = guest_input(load_cargo_archive_with_format(
+        "safe.tar.gz",
+        MerkleFormat::V1,
+    ));
+    let words = to_vec(&input).unwrap();
+    for len in [1, words.len() / 4, words.len() / 2] {
+        assert!(from_slice::<GuestInput, _>(&words[..len]).is_err());
+    }
+}
+
+#[test]
+fn crate_package_is_sent_as_bytes() {
+    let package = load_fixture("safe-0.1.0.crate");
+    let mut input = guest_input(load_crate_archive("safe-0.1.0.crate"));
+    let without = to_vec(&input).unwrap().len();
+    input.crate_package = Some(package.clone());
+    let words = to_vec(&input).unwrap();
+    assert!(
+        words.len() - without <= package.len() / 4 + 2,
+        "{} bytes take {} words",
+        package.len(),
+        words.len() - without
+    );
+    let input: GuestInput = from_slice(&words).unwrap();
+    assert_eq!(input.crate_package, Some(package));
+}
+
+#[test]
+fn auditable_binary_is_sent_as_bytes() {
+    let binary = load_fixture("safe_auditable");
+    let resolved_with = PackageManagerSpec::new(PackageManager::Cargo, Version::new(1, 82, 0));
+    let mut input = guest_input(load_cargo_archive_with_format(
+        "safe.tar.gz",
+        MerkleFormat::V1,
+    ));
+    input.source = Source::AuditableBinary {
+        resolved_with: resolved_with.clone(),
+        binary: Vec::new(),
+    };
+    let without = to_vec(&input).unwrap().len();
+    input.source = Source::AuditableBinary {
+        resolved_with,
+        binary: binary.clone(),
+    };
+    let words = to_vec(&input).unwrap();
+    assert!(
+        words.len() - without <= binary.len() / 4 + 1,
+        "{} bytes take {} words",
+        binary.len(),
+        words.len() - without
+    );
+    let decoded: GuestInput = from_slice(&words).unwrap();
+    assert_eq!(decoded.source, input.source);
+}
+
+#[test]
+fn container_image_is_sent_as_bytes() {
+    let image = load_container_image("safe_image.tar");
+    let blobs = [&image.manifest, &image.config]
+        .into_iter()
+        .chain(&image.layers)
+        .map(Vec::len);
+    // Each blob takes its length and its bytes, and the layers their count.
+    let bound = blobs.map(|len| len.div_ceil(4) + 1).sum::<usize>() + 1;
+    let words = to_vec(&image).unwrap();
+    assert!(
+        words.len() <= bound,
+        "{} words against {bound}",
+        words.len()
+    );
+    assert_eq!(from_slice::<ContainerImage, _>(&words).unwrap(), image);
+}
+
+#[test]
+fn git_tree_is_sent_as_bytes() {
+    let tree = load_git_tree("safe_git.objects");
+    let objects = core::iter::once(&tree.commit)
+        .chain(&tree.trees)
+        .chain(&tree.blobs)
+        .map(Vec::len);
+    // Each object takes its length and its bytes, and the trees and blobs their count.
+    let bound = objects.map(|len| len.div_ceil(4) + 1).sum::<usize>() + 2;
+    let words = to_vec(&tree).unwrap();
+    assert!(
+        words.len() <= bound,
+        "{} words against {bound}",
+        words.len()
+    );
+    assert_eq!(from_slice::<GitTree, _>(&words).unwrap(), tree);
+}
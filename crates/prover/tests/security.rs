@@ -1,12 +1,28 @@
-use risc0_zkvm::{ExecutorEnv, default_prover};
+use risc0_zkvm::{
+    ExecutorEnv, default_prover,
+    sha::{Impl, Sha256},
+};
 use std::sync::{LazyLock, Mutex};
 use zk_sca_guest::SCA_ELF;
-use zk_sca_guest_abi::{GuestInput, MerkleLeaf, PartialMerkleArchive, ScaError};
-use zk_sca_guest_abi_utils::{block_count, parse_tar_header};
-use zk_sca_types::{PackageManager, PackageManagerSpec, Version};
+use zk_sca_guest_abi::{
+    ContainerImage, GuestInput, MerkleFormat, MerkleLeaf, MerklePathNode, PartialMerkleArchive,
+    ScaError, Source,
+};
+use zk_sca_guest_abi_utils::{
+    TreeMetadata, block_count, leaf_hash, merkle_layers, parse_tar_header, record_hash, sibling,
+    to_hex,
+};
+use zk_sca_types::{
+    FeatureSelection, LinkPolicy, MemberScope, OverridePolicy, PackageManager, PackageManagerSpec,
+    RustEdition, ToolchainPolicy, Version,
+};
 
 mod common;
-use crate::common::{load_cargo_archive, load_permitted_deps};
+use crate::common::{
+    load_auditable_bundle, load_cargo_archive, load_cargo_archive_with_format,
+    load_cargo_archive_with_multiproof, load_cargo_archive_with_vendored_sources,
+    load_container_image, load_crate_archive, load_fixture, load_git_tree, load_permitted_deps,
+};
 
 // Protect RISC-0 environment when running tests in parallel.
 static PROVE_LOCK: LazyLock<Mutex<()>> = LazyLock::new(|| Mutex::new(()));
@@ -48,29 +64,264 @@ fn prove_should_fail(input: GuestInput, expected: ScaError) {
 fn run_guest_expect_invalid(archive: PartialMerkleArchive) {
     let permitted = load_permitted_deps("permitted-dependencies.json");
     let guest_input = GuestInput {
-        src_archive: archive,
+        source: Source::Archive(archive),
         permitted_deps: permitted,
         license_policy: None,
+        member_scope: None,
+        feature_selection: None,
+        override_policy: None,
+        verify_vendored_sources: false,
+        link_policy: LinkPolicy::Reject,
+        toolchain_policy: None,
+        crate_package: None,
     };
     prove_should_fail(guest_input, ScaError::InvalidMerkleArchive);
 }
 
+fn binary_input(binary: &str) -> GuestInput {
+    let artifact = load_auditable_bundle(binary);
+    GuestInput {
+        source: Source::AuditableBinary {
+            resolved_with: artifact.resolved_with().clone(),
+            binary: artifact.bytes().to_vec(),
+        },
+        permitted_deps: load_permitted_deps("permitted-dependencies.json"),
+        license_policy: None,
+        member_scope: None,
+        feature_selection: None,
+        override_policy: None,
+        verify_vendored_sources: false,
+        link_policy: LinkPolicy::Reject,
+        toolchain_policy: None,
+        crate_package: None,
+    }
+}
+
+fn image_input(image: &str) -> GuestInput {
+    GuestInput {
+        source: Source::ContainerImage {
+            resolved_with: PackageManagerSpec::new(PackageManager::Cargo, Version::new(1, 82, 0)),
+            format: MerkleFormat::V1,
+            image: load_container_image(image),
+        },
+        permitted_deps: load_permitted_deps("permitted-dependencies.json"),
+        license_policy: None,
+        member_scope: None,
+        feature_selection: None,
+        override_policy: None,
+        verify_vendored_sources: false,
+        link_policy: LinkPolicy::Reject,
+        toolchain_policy: None,
+        crate_package: None,
+    }
+}
+
+/// Replaces layer `index` of `image` with `layer`, updating the `diff_id`
+/// and configuration digest that name it, so that only the layer is at fault.
+fn replace_layer(image: &mut ContainerImage, index: usize, layer: Vec<u8>) {
+    let digest = |bytes: &[u8]| format!("sha256:{}", to_hex(Impl::hash_bytes(bytes).as_bytes()));
+    let swap = |document: &mut Vec<u8>, old: &str, new: &str| {
+        let text = String::from_utf8(document.clone()).unwrap();
+        assert!(text.contains(old), "`{old}` is not in the document");
+        *document = text.replace(old, new).into_bytes();
+    };
+    let old_config = digest(&image.config);
+    swap(
+        &mut image.config,
+        &digest(&image.layers[index]),
+        &digest(&layer),
+    );
+    swap(&mut image.manifest, &old_config, &digest(&image.config));
+    image.layers[index] = layer;
+}
+
+fn git_input(objects: &str) -> GuestInput {
+    GuestInput {
+        source: Source::GitTree {
+            resolved_with: PackageManagerSpec::new(PackageManager::Cargo, Version::new(1, 82, 0)),
+            tree: load_git_tree(objects),
+        },
+        permitted_deps: load_permitted_deps("permitted-dependencies.json"),
+        license_policy: None,
+        member_scope: None,
+        feature_selection: None,
+        override_policy: None,
+        verify_vendored_sources: false,
+        link_policy: LinkPolicy::Reject,
+        toolchain_policy: None,
+        crate_package: None,
+    }
+}
+
+/// A USTAR header with a valid checksum for `name`, of the given typeflag
+/// and raw size field.
+fn tar_header(name: &str, typeflag: u8, size: &[u8; 12]) -> [u8; 512] {
+    let mut block = [0u8; 512];
+    block[..name.len()].copy_from_slice(name.as_bytes());
+    block[100..108].copy_from_slice(b"0000644\0");
+    block[124..136].copy_from_slice(size);
+    block[156] = typeflag;
+    block[257..265].copy_from_slice(b"ustar\x0000");
+    set_tar_checksum(&mut block);
+    block
+}
+
+fn link_header(name: &str, typeflag: u8, target: &str) -> [u8; 512] {
+    let mut block = tar_header(name, typeflag, b"00000000000\0");
+    block[157..157 + target.len()].copy_from_slice(target.as_bytes());
+    set_tar_checksum(&mut block);
+    block
+}
+
+fn set_tar_checksum(block: &mut [u8; 512]) {
+    block[148..156].fill(b' ');
+    let sum: u32 = block.iter().map(|&b| u32::from(b)).sum();
+    block[148..156].copy_from_slice(format!("{sum:06o}\0 ").as_bytes());
+}
+
+/// Input whose archive holds `headers` as its only leaves, under a Merkle
+/// tree built as the builder builds one, so that only the headers' contents
+/// can make the guest reject it.
+fn crafted_headers_input(headers: &[[u8; 512]]) -> GuestInput {
+    let hash =
+        |bytes: &[u8]| -> [u8; 32] { Impl::hash_bytes(bytes).as_bytes().try_into().unwrap() };
+
+    let mut blocks = vec![[0u8; 512]];
+    let count = headers.len().to_string();
+    blocks[0][..count.len()].copy_from_slice(count.as_bytes());
+    blocks.extend_from_slice(headers);
+
+    let mut layers: Vec<Vec<[u8; 32]>> = vec![blocks.iter().map(|b| hash(b)).collect()];
+    while layers.last().unwrap().len() > 1 {
+        let next = layers
+            .last()
+            .unwrap()
+            .chunks(2)
+            .map(|pair| hash(&[pair[0], *pair.get(1).unwrap_or(&pair[0])].concat()))
+            .collect();
+        layers.push(next);
+    }
+    let mut leaves = blocks.iter().enumerate().map(|(mut idx, data)| {
+        let mut path = Vec::new();
+        for level in &layers[..layers.len() - 1] {
+            let is_left = idx % 2 == 0;
+            let sibling = if is_left {
+                *level.get(idx + 1).unwrap_or(&level[idx])
+            } else {
+                level[idx - 1]
+            };
+            path.push(MerklePathNode {
+                sibling_hash: sibling,
+                is_left_child: is_left,
+            });
+            idx /= 2;
+        }
+        MerkleLeaf { data: *data, path }
+    });
+
+    GuestInput {
+        source: Source::Archive(PartialMerkleArchive {
+            resolved_with: PackageManagerSpec::new(PackageManager::Cargo, Version::new(0, 0, 0)),
+            format: MerkleFormat::V1,
+            root_hash: layers.last().unwrap()[0],
+            count_leaf: leaves.next().unwrap(),
+            header_leaves: leaves.collect(),
+            extension_leaves: Vec::new(),
+            dependency_file_leaves: Vec::new(),
+            dependency_file_header_indices: Vec::new(),
+            file_records: Vec::new(),
+            dependency_file_contents: Vec::new(),
+            multiproof: None,
+        }),
+        permitted_deps: load_permitted_deps("permitted-dependencies.json"),
+        license_policy: None,
+        member_scope: None,
+        feature_selection: None,
+        override_policy: None,
+        verify_vendored_sources: false,
+        link_policy: LinkPolicy::Reject,
+        toolchain_policy: None,
+        crate_package: None,
+    }
+}
+
+/// A v2 archive whose leaves are a metadata leaf claiming `block_count`
+/// blocks and `header_count` headers, then `blocks`, of which the first
+/// `header_count` are proven as headers.
+fn crafted_v2_archive(
+    blocks: &[[u8; 512]],
+    header_count: usize,
+    block_count: usize,
+) -> PartialMerkleArchive {
+    let format = MerkleFormat::V2;
+    let mut raw = vec![TreeMetadata::leaf(format, header_count, block_count)];
+    raw.extend_from_slice(blocks);
+    let layers = merkle_layers(format, raw.iter().map(|b| leaf_hash(format, b)).collect());
+    let leaf = |mut idx: usize| {
+        let data = raw[idx];
+        let mut path = Vec::new();
+        for level in &layers[..layers.len() - 1] {
+            let is_left = idx % 2 == 0;
+            path.push(MerklePathNode {
+                sibling_hash: sibling(format, level, if is_left { idx + 1 } else { idx - 1 }),
+                is_left_child: is_left,
+            });
+            idx /= 2;
+        }
+        MerkleLeaf { data, path }
+    };
+
+    PartialMerkleArchive {
+        resolved_with: PackageManagerSpec::new(PackageManager::Cargo, Version::new(0, 0, 0)),
+        format,
+        root_hash: layers.last().unwrap()[0],
+        count_leaf: leaf(0),
+        header_leaves: (1..=header_count).map(leaf).collect(),
+        extension_leaves: Vec::new(),
+        dependency_file_leaves: Vec::new(),
+        dependency_file_header_indices: Vec::new(),
+        file_records: Vec::new(),
+        dependency_file_contents: Vec::new(),
+        multiproof: None,
+    }
+}
+
 // Merkle-tree integrity tests
 mod merkle_integrity {
     use super::*;
 
+    #[test]
+    fn record_hash_differs_from_block_leaf_hash() {
+        // A 480-byte header makes a record exactly as long as a leaf block,
+        // such as the metadata leaf of a file-record tree.
+        let content_hash = [7u8; 32];
+        let header = [b'h'; 480];
+        let mut block = [0u8; 512];
+        block[..32].copy_from_slice(&content_hash);
+        block[32..].copy_from_slice(&header);
+        assert_ne!(
+            record_hash(&header, &content_hash),
+            leaf_hash(MerkleFormat::FileRecords, &block)
+        );
+    }
+
     #[test]
     fn reject_zero_root_hash() {
         let archive = PartialMerkleArchive {
             resolved_with: PackageManagerSpec::new(PackageManager::Cargo, Version::new(0, 0, 0)),
+            format: MerkleFormat::V1,
             root_hash: [0u8; 32],
             count_leaf: MerkleLeaf {
                 data: [0u8; 512],
                 path: Vec::new(),
             },
             header_leaves: Vec::new(),
+            extension_leaves: Vec::new(),
             dependency_file_leaves: Vec::new(),
             dependency_file_header_indices: Vec::new(),
+            file_records: Vec::new(),
+            dependency_file_contents: Vec::new(),
+            multiproof: None,
         };
         run_guest_expect_invalid(archive);
     }
@@ -149,6 +400,57 @@ mod merkle_integrity {
         }
         run_guest_expect_invalid(archive);
     }
+
+    #[test]
+    fn reject_tampered_multiproof_hash() {
+        let mut archive = load_cargo_archive_with_multiproof("safe.tar.gz", MerkleFormat::V1);
+        archive.multiproof.as_mut().unwrap().hashes[0][0] ^= 1;
+        run_guest_expect_invalid(archive);
+    }
+
+    #[test]
+    fn reject_multiproof_with_extra_hash() {
+        let mut archive = load_cargo_archive_with_multiproof("safe.tar.gz", MerkleFormat::V2);
+        archive.multiproof.as_mut().unwrap().hashes.push([0u8; 32]);
+        run_guest_expect_invalid(archive);
+    }
+
+    #[test]
+    fn reject_multiproof_over_other_leaves() {
+        let mut archive = load_cargo_archive_with_multiproof("safe.tar.gz", MerkleFormat::V2);
+        *archive
+            .multiproof
+            .as_mut()
+            .unwrap()
+            .leaf_indices
+            .last_mut()
+            .unwrap() += 1;
+        run_guest_expect_invalid(archive);
+    }
+
+    #[test]
+    fn reject_multiproof_understating_leaf_count() {
+        let mut archive = load_cargo_archive_with_multiproof("safe.tar.gz", MerkleFormat::V2);
+        archive.multiproof.as_mut().unwrap().leaf_count -= 1;
+        run_guest_expect_invalid(archive);
+    }
+
+    #[test]
+    fn reject_merkle_path_alongside_multiproof() {
+        let paths = load_cargo_archive("safe.tar.gz");
+        let mut archive = load_cargo_archive_with_multiproof("safe.tar.gz", MerkleFormat::V1);
+        archive.count_leaf.path = paths.count_leaf.path;
+        run_guest_expect_invalid(archive);
+    }
+
+    #[test]
+    fn reject_multiproof_omitting_a_file_record() {
+        let mut archive =
+            load_cargo_archive_with_multiproof("safe.tar.gz", MerkleFormat::FileRecords);
+        archive.file_records.pop();
+        archive.multiproof.as_mut().unwrap().leaf_indices.pop();
+        run_guest_expect_invalid(archive);
+    }
 }
 
 // tar integrity tests
@@ -168,7 +470,7 @@ mod tar_integrity {
             .header_leaves
             .iter()
             .position(|leaf| {
-                let hdr = parse_tar_header(&leaf.data);
+                let hdr = parse_tar_header(&leaf.data).unwrap();
                 hdr.name.ends_with("/Cargo.lock") || hdr.name == "Cargo.lock"
             })
             .unwrap();
@@ -197,6 +499,77 @@ mod tar_integrity {
         run_guest_expect_invalid(archive);
     }
 
+    #[test]
+    fn reject_v1_archive_labelled_v2() {
+        let mut archive = load_cargo_archive("safe.tar.gz");
+        archive.format = MerkleFormat::V2;
+        run_guest_expect_invalid(archive);
+    }
+
+    #[test]
+    fn reject_v2_archive_labelled_v1() {
+        let mut archive = load_cargo_archive_with_format("safe.tar.gz", MerkleFormat::V2);
+        archive.format = MerkleFormat::V1;
+        run_guest_expect_invalid(archive);
+    }
+
+    #[test]
+    fn reject_v2_metadata_with_unknown_version() {
+        let mut archive = load_cargo_archive_with_format("safe.tar.gz", MerkleFormat::V2);
+        archive.count_leaf.data[8..12].copy_from_slice(&3u32.to_le_bytes());
+        run_guest_expect_invalid(archive);
+    }
+
+    #[test]
+    fn reject_v2_blocks_past_the_last_entry() {
+        let empty_file = tar_header("a", b'0', b"00000000000\0");
+        run_guest_expect_invalid(crafted_v2_archive(&[empty_file, [0u8; 512]], 1, 3));
+    }
+
+    #[test]
+    fn reject_v2_metadata_overstating_block_count() {
+        let empty_file = tar_header("a", b'0', b"00000000000\0");
+        run_guest_expect_invalid(crafted_v2_archive(&[empty_file], 1, 3));
+    }
+
+    #[test]
+    fn reject_v2_truncated_merkle_path() {
+        let mut archive = load_cargo_archive_with_format("safe.tar.gz", MerkleFormat::V2);
+        archive.header_leaves[0].path.pop();
+        run_guest_expect_invalid(archive);
+    }
+
+    #[test]
+    fn reject_tampered_file_record() {
+        let mut archive = load_cargo_archive_with_format("safe.tar.gz", MerkleFormat::FileRecords);
+        archive.file_records[0].content_hash[0] ^= 1;
+        run_guest_expect_invalid(archive);
+    }
+
+    #[test]
+    fn reject_omitted_file_record() {
+        let mut archive = load_cargo_archive_with_format("safe.tar.gz", MerkleFormat::FileRecords);
+        archive.file_records.pop();
+        run_guest_expect_invalid(archive);
+    }
+
+    #[test]
+    fn reject_dependency_contents_not_matching_record() {
+        let mut archive = load_cargo_archive_with_format("safe.tar.gz", MerkleFormat::FileRecords);
+        let contents = archive.dependency_file_contents.last_mut().unwrap();
+        let mut flipped = contents.to_vec();
+        *flipped.last_mut().unwrap() ^= 1;
+        *contents = flipped.into();
+        run_guest_expect_invalid(archive);
+    }
+
+    #[test]
+    fn reject_omitted_dependency_contents() {
+        let mut archive = load_cargo_archive_with_format("safe.tar.gz", MerkleFormat::FileRecords);
+        archive.dependency_file_contents.pop();
+        run_guest_expect_invalid(archive);
+    }
+
     #[test]
     fn reject_non_numeric_header_count() {
         let mut archive = load_cargo_archive("safe.tar.gz");
@@ -227,6 +600,129 @@ mod tar_integrity {
         run_guest_expect_invalid(archive);
     }
 
+    #[test]
+    fn reject_header_with_bad_checksum() {
+        let mut header = tar_header("Cargo.toml", b'0', b"00000000000\0");
+        header[100..108].copy_from_slice(b"0000755\0");
+        prove_should_fail(crafted_headers_input(&[header]), ScaError::InvalidTarHeader);
+    }
+
+    #[test]
+    fn reject_header_without_ustar_magic() {
+        let mut header = tar_header("Cargo.toml", b'0', b"00000000000\0");
+        header[257..265].fill(0);
+        set_tar_checksum(&mut header);
+        prove_should_fail(crafted_headers_input(&[header]), ScaError::InvalidTarHeader);
+    }
+
+    #[test]
+    fn reject_non_octal_header_size() {
+        let header = tar_header("Cargo.toml", b'0', b"00000000009\0");
+        prove_should_fail(crafted_headers_input(&[header]), ScaError::InvalidTarHeader);
+    }
+
+    #[test]
+    fn reject_negative_base256_header_size() {
+        let header = tar_header("Cargo.toml", b'0', &[0xff; 12]);
+        prove_should_fail(crafted_headers_input(&[header]), ScaError::InvalidTarHeader);
+    }
+
+    #[test]
+    fn reject_device_entry() {
+        let header = tar_header("Cargo.toml", b'3', b"00000000000\0");
+        prove_should_fail(crafted_headers_input(&[header]), ScaError::InvalidTarHeader);
+    }
+
+    #[test]
+    fn reject_directory_declaring_data() {
+        let header = tar_header("src/", b'5', b"00000001000\0");
+        prove_should_fail(crafted_headers_input(&[header]), ScaError::InvalidTarHeader);
+    }
+
+    #[test]
+    fn reject_data_block_posing_as_header() {
+        // The second header is where the first entry's data block belongs.
+        let headers = [
+            tar_header("README.md", b'0', b"00000000001\0"),
+            tar_header("Cargo.toml", b'0', b"00000000000\0"),
+        ];
+        prove_should_fail(
+            crafted_headers_input(&headers),
+            ScaError::InvalidMerkleArchive,
+        );
+    }
+
+    #[test]
+    fn reject_parent_directory_traversal() {
+        let header = tar_header("../Cargo.toml", b'0', b"00000000000\0");
+        prove_should_fail(crafted_headers_input(&[header]), ScaError::InvalidTarHeader);
+    }
+
+    #[test]
+    fn reject_absolute_path() {
+        let header = tar_header("/Cargo.toml", b'0', b"00000000000\0");
+        prove_should_fail(crafted_headers_input(&[header]), ScaError::InvalidTarHeader);
+    }
+
+    #[test]
+    fn reject_names_equal_after_normalization() {
+        let headers = [
+            tar_header("a/Cargo.toml", b'0', b"00000000000\0"),
+            tar_header("./a//Cargo.toml", b'0', b"00000000000\0"),
+        ];
+        prove_should_fail(
+            crafted_headers_input(&headers),
+            ScaError::InvalidMerkleArchive,
+        );
+    }
+
+    #[test]
+    fn reject_name_equal_to_prefixed_name() {
+        let mut prefixed = tar_header("Cargo.toml", b'0', b"00000000000\0");
+        prefixed[345] = b'a';
+        set_tar_checksum(&mut prefixed);
+        let headers = [prefixed, tar_header("a/Cargo.toml", b'0', b"00000000000\0")];
+        prove_should_fail(
+            crafted_headers_input(&headers),
+            ScaError::InvalidMerkleArchive,
+        );
+    }
+
+    #[test]
+    fn reject_extension_describing_no_entry() {
+        let headers = [
+            tar_header("Cargo.toml", b'0', b"00000000000\0"),
+            tar_header("PaxHeader", b'x', b"00000000000\0"),
+        ];
+        prove_should_fail(crafted_headers_input(&headers), ScaError::InvalidTarHeader);
+    }
+
+    #[test]
+    fn reject_two_pax_headers_for_one_entry() {
+        let headers = [
+            tar_header("PaxHeader", b'x', b"00000000000\0"),
+            tar_header("PaxHeader", b'x', b"00000000000\0"),
+            tar_header("Cargo.toml", b'0', b"00000000000\0"),
+        ];
+        prove_should_fail(crafted_headers_input(&headers), ScaError::InvalidTarHeader);
+    }
+
+    #[test]
+    fn reject_tampered_pax_path() {
+        let mut archive = load_cargo_archive("safe_pax.tar.gz");
+        let leaf = &mut archive.extension_leaves[0];
+        let at = leaf.data.windows(5).position(|w| w == b"path=").unwrap() + 5;
+        leaf.data[at] = b'X';
+        run_guest_expect_invalid(archive);
+    }
+
+    #[test]
+    fn reject_missing_extension_leaf() {
+        let mut archive = load_cargo_archive("safe_gnu_long_names.tar.gz");
+        archive.extension_leaves.pop();
+        run_guest_expect_invalid(archive);
+    }
+
     #[test]
     fn reject_scrambled_dependency_file_leaves_order() {
         let mut archive = load_cargo_archive("safe.tar.gz");
@@ -237,6 +733,72 @@ mod tar_integrity {
     }
 }
 
+// Link policy tests
+mod link_policy {
+    use super::*;
+
+    /// Input whose archive holds `headers`, of which those at `dependencies`
+    /// are dependency files, read under `policy`.
+    fn linked_input(
+        headers: &[[u8; 512]],
+        dependencies: &[usize],
+        policy: LinkPolicy,
+    ) -> GuestInput {
+        let mut input = crafted_headers_input(headers);
+        let Source::Archive(archive) = &mut input.source else {
+            unreachable!("crafted inputs carry an archive");
+        };
+        archive.dependency_file_header_indices = dependencies.to_vec();
+        input.link_policy = policy;
+        input
+    }
+
+    #[test]
+    fn reject_symlink_leaving_archive() {
+        let headers = [link_header("Cargo.lock", b'2', "../Cargo.lock")];
+        let input = linked_input(&headers, &[0], LinkPolicy::Resolve);
+        prove_should_fail(input, ScaError::LinkedDependencyFile);
+    }
+
+    #[test]
+    fn reject_symlink_loop() {
+        let headers = [link_header("a/Cargo.lock", b'2', "../a/Cargo.lock")];
+        let input = linked_input(&headers, &[0], LinkPolicy::Resolve);
+        prove_should_fail(input, ScaError::LinkedDependencyFile);
+    }
+
+    #[test]
+    fn reject_hard_link_to_directory() {
+        let headers = [
+            tar_header("d", b'5', b"00000000000\0"),
+            link_header("Cargo.toml", b'1', "d"),
+        ];
+        let input = linked_input(&headers, &[1], LinkPolicy::Resolve);
+        prove_should_fail(input, ScaError::LinkedDependencyFile);
+    }
+
+    #[test]
+    fn reject_dependency_file_beneath_symlink() {
+        let headers = [
+            link_header("a", b'2', "b"),
+            tar_header("b", b'5', b"00000000000\0"),
+            tar_header("a/Cargo.toml", b'0', b"00000000000\0"),
+        ];
+        let input = linked_input(&headers, &[2], LinkPolicy::Resolve);
+        prove_should_fail(input, ScaError::LinkedDependencyFile);
+    }
+
+    #[test]
+    fn reject_link_to_unmaterialized_file() {
+        let headers = [
+            tar_header("locks/safe.lock", b'0', b"00000000000\0"),
+            link_header("Cargo.lock", b'2', "locks/safe.lock"),
+        ];
+        let input = linked_input(&headers, &[1], LinkPolicy::Resolve);
+        prove_should_fail(input, ScaError::InvalidMerkleArchive);
+    }
+}
+
 // Cargo integrity tests
 mod cargo_integrity {
     use super::*;
@@ -249,7 +811,7 @@ mod cargo_integrity {
             .header_leaves
             .iter()
             .position(|leaf| {
-                let hdr = parse_tar_header(&leaf.data);
+                let hdr = parse_tar_header(&leaf.data).unwrap();
                 hdr.name == "Cargo.toml" || hdr.name.ends_with("/Cargo.toml")
             })
             .expect("Cargo.toml header not found");
@@ -264,7 +826,7 @@ mod cargo_integrity {
             .header_leaves
             .iter()
             .position(|leaf| {
-                let hdr = parse_tar_header(&leaf.data);
+                let hdr = parse_tar_header(&leaf.data).unwrap();
                 hdr.name == "Cargo.lock" || hdr.name.ends_with("/Cargo.lock")
             })
             .expect("Cargo.lock header not found");
@@ -279,7 +841,7 @@ mod cargo_integrity {
         let mut toml_offset = 0;
         let mut toml_blocks = 0;
         for leaf in &archive.header_leaves {
-            let hdr = parse_tar_header(&leaf.data);
+            let hdr = parse_tar_header(&leaf.data).unwrap();
             if hdr.name == "Cargo.toml" || hdr.name.ends_with("/Cargo.toml") {
                 toml_blocks = block_count(hdr.size);
                 break;
@@ -306,7 +868,7 @@ mod cargo_integrity {
         let mut lock_offset = 0;
         let mut lock_blocks = 0;
         for leaf in &archive.header_leaves {
-            let hdr = parse_tar_header(&leaf.data);
+            let hdr = parse_tar_header(&leaf.data).unwrap();
             if hdr.name == "Cargo.lock" || hdr.name.ends_with("/Cargo.lock") {
                 lock_blocks = block_count(hdr.size);
                 break;
@@ -331,21 +893,75 @@ mod cargo_integrity {
         let archive = load_cargo_archive("safe_lockfile_unsafe_manifest.tar.gz");
         let permitted = load_permitted_deps("permitted-dependencies.json");
         let guest_input = GuestInput {
-            src_archive: archive,
+            source: Source::Archive(archive),
+            permitted_deps: permitted,
+            license_policy: None,
+            member_scope: None,
+            feature_selection: None,
+            override_policy: None,
+            verify_vendored_sources: false,
+            link_policy: LinkPolicy::Reject,
+            toolchain_policy: None,
+            crate_package: None,
+        };
+        prove_should_fail(guest_input, ScaError::ManifestLockMismatch);
+    }
+
+    #[test]
+    fn reject_dependency_edge_moved_between_members() {
+        let archive = load_cargo_archive("moved_dependency_edge.tar.gz");
+        let permitted = load_permitted_deps("permitted-dependencies.json");
+        let guest_input = GuestInput {
+            source: Source::Archive(archive),
             permitted_deps: permitted,
             license_policy: None,
+            member_scope: None,
+            feature_selection: None,
+            override_policy: None,
+            verify_vendored_sources: false,
+            link_policy: LinkPolicy::Reject,
+            toolchain_policy: None,
+            crate_package: None,
         };
         prove_should_fail(guest_input, ScaError::ManifestLockMismatch);
     }
 
+    #[test]
+    fn reject_omitted_cargo_config() {
+        let mut archive = load_cargo_archive("source_replacement.tar.gz");
+        let mut offset = 0;
+        let mut pos = None;
+        for (i, &hdr_idx) in archive.dependency_file_header_indices.iter().enumerate() {
+            let hdr = parse_tar_header(&archive.header_leaves[hdr_idx].data).unwrap();
+            if hdr.name.ends_with(".cargo/config.toml") {
+                pos = Some((i, block_count(hdr.size)));
+                break;
+            }
+            offset += block_count(hdr.size);
+        }
+        let (pos, blocks) = pos.expect(".cargo/config.toml not selected");
+        archive.dependency_file_header_indices.remove(pos);
+        archive
+            .dependency_file_leaves
+            .drain(offset..offset + blocks);
+        run_guest_expect_invalid(archive);
+    }
+
     #[test]
     fn reject_missing_lockfile() {
         let archive = load_cargo_archive("missing_lockfile.tar.gz");
         let permitted = load_permitted_deps("permitted-dependencies.json");
         let guest_input = GuestInput {
-            src_archive: archive,
+            source: Source::Archive(archive),
             permitted_deps: permitted,
             license_policy: None,
+            member_scope: None,
+            feature_selection: None,
+            override_policy: None,
+            verify_vendored_sources: false,
+            link_policy: LinkPolicy::Reject,
+            toolchain_policy: None,
+            crate_package: None,
         };
         prove_should_fail(guest_input, ScaError::MissingLockfile);
     }
@@ -355,9 +971,16 @@ mod cargo_integrity {
         let archive = load_cargo_archive("missing_workspace.tar.gz");
         let permitted = load_permitted_deps("permitted-dependencies.json");
         let guest_input = GuestInput {
-            src_archive: archive,
+            source: Source::Archive(archive),
             permitted_deps: permitted,
             license_policy: None,
+            member_scope: None,
+            feature_selection: None,
+            override_policy: None,
+            verify_vendored_sources: false,
+            link_policy: LinkPolicy::Reject,
+            toolchain_policy: None,
+            crate_package: None,
         };
         prove_should_fail(guest_input, ScaError::InvalidWorkspaceCount);
     }
@@ -367,9 +990,16 @@ mod cargo_integrity {
         let archive = load_cargo_archive("multi_workspace_single_pkgs.tar.gz");
         let permitted = load_permitted_deps("permitted-dependencies.json");
         let guest_input = GuestInput {
-            src_archive: archive,
+            source: Source::Archive(archive),
             permitted_deps: permitted,
             license_policy: None,
+            member_scope: None,
+            feature_selection: None,
+            override_policy: None,
+            verify_vendored_sources: false,
+            link_policy: LinkPolicy::Reject,
+            toolchain_policy: None,
+            crate_package: None,
         };
         prove_should_fail(guest_input, ScaError::InvalidWorkspaceCount);
     }
@@ -379,9 +1009,16 @@ mod cargo_integrity {
         let archive = load_cargo_archive("multi_workspace_virtual.tar.gz");
         let permitted = load_permitted_deps("permitted-dependencies.json");
         let guest_input = GuestInput {
-            src_archive: archive,
+            source: Source::Archive(archive),
             permitted_deps: permitted,
             license_policy: None,
+            member_scope: None,
+            feature_selection: None,
+            override_policy: None,
+            verify_vendored_sources: false,
+            link_policy: LinkPolicy::Reject,
+            toolchain_policy: None,
+            crate_package: None,
         };
         prove_should_fail(guest_input, ScaError::InvalidWorkspaceCount);
     }
@@ -391,35 +1028,155 @@ mod cargo_integrity {
         let archive = load_cargo_archive("multi_workspace_mixed.tar.gz");
         let permitted = load_permitted_deps("permitted-dependencies.json");
         let guest_input = GuestInput {
-            src_archive: archive,
+            source: Source::Archive(archive),
             permitted_deps: permitted,
             license_policy: None,
+            member_scope: None,
+            feature_selection: None,
+            override_policy: None,
+            verify_vendored_sources: false,
+            link_policy: LinkPolicy::Reject,
+            toolchain_policy: None,
+            crate_package: None,
         };
         prove_should_fail(guest_input, ScaError::InvalidWorkspaceCount);
     }
 
     #[test]
-    fn reject_cargo_lockfile_v1() {
-        let archive = load_cargo_archive("cargo_lock_v1.tar.gz");
+    fn reject_ambiguous_legacy_lockfile_reference() {
+        let archive = load_cargo_archive("ambiguous_lock_reference.tar.gz");
+        let permitted = load_permitted_deps("permitted-dependencies.json");
+        let guest_input = GuestInput {
+            source: Source::Archive(archive),
+            permitted_deps: permitted,
+            license_policy: None,
+            member_scope: None,
+            feature_selection: None,
+            override_policy: None,
+            verify_vendored_sources: false,
+            link_policy: LinkPolicy::Reject,
+            toolchain_policy: None,
+            crate_package: None,
+        };
+        prove_should_fail(guest_input, ScaError::UnresolvableLockfileDependency);
+    }
+
+    #[test]
+    fn reject_crate_package_not_matching_archive() {
+        // The package bytes are those of another source tree.
+        let archive = load_crate_archive("safe-0.1.0.crate");
+        let permitted = load_permitted_deps("permitted-dependencies.json");
+        let guest_input = GuestInput {
+            source: Source::Archive(archive),
+            permitted_deps: permitted,
+            license_policy: None,
+            member_scope: None,
+            feature_selection: None,
+            override_policy: None,
+            verify_vendored_sources: false,
+            link_policy: LinkPolicy::Reject,
+            toolchain_policy: None,
+            crate_package: Some(load_fixture("safe.tar.gz")),
+        };
+        prove_should_fail(guest_input, ScaError::InvalidCratePackage);
+    }
+
+    #[test]
+    fn reject_binary_without_dependency_section() {
+        let guest_input = binary_input("safe.tar.gz");
+        prove_should_fail(guest_input, ScaError::InvalidAuditableBinary);
+    }
+
+    #[test]
+    fn reject_git_tree_with_tampered_lockfile() {
+        let mut guest_input = git_input("safe_git.objects");
+        let Source::GitTree { tree, .. } = &mut guest_input.source else {
+            unreachable!("Git inputs carry a tree");
+        };
+        // The tree still names the original blob, which is now missing.
+        let lockfile = tree
+            .blobs
+            .iter_mut()
+            .find(|blob| blob.starts_with(b"# This file is automatically @generated"))
+            .unwrap();
+        lockfile.extend_from_slice(b"\n");
+        prove_should_fail(guest_input, ScaError::InvalidGitTree);
+    }
+
+    #[test]
+    fn reject_git_tree_missing_a_subtree() {
+        let mut guest_input = git_input("safe_git.objects");
+        // Keep only the root tree, hiding whatever `src/` holds.
+        let Source::GitTree { tree, .. } = &mut guest_input.source else {
+            unreachable!("Git inputs carry a tree");
+        };
+        tree.trees.truncate(1);
+        prove_should_fail(guest_input, ScaError::InvalidGitTree);
+    }
+
+    #[test]
+    fn reject_container_layer_not_matching_diff_id() {
+        let mut guest_input = image_input("safe_image.tar");
+        let Source::ContainerImage { image, .. } = &mut guest_input.source else {
+            unreachable!("image inputs carry an image");
+        };
+        // Flip a byte of the binary in the top layer.
+        image.layers[1][2048] ^= 1;
+        prove_should_fail(guest_input, ScaError::InvalidContainerImage);
+    }
+
+    #[test]
+    fn reject_container_layer_with_bad_header_checksum() {
+        let mut guest_input = image_input("safe_image.tar");
+        let Source::ContainerImage { image, .. } = &mut guest_input.source else {
+            unreachable!("image inputs carry an image");
+        };
+        let mut layer = image.layers[1].clone();
+        // Change the first entry's mtime but not its checksum.
+        layer[136] ^= 1;
+        replace_layer(image, 1, layer);
+        prove_should_fail(guest_input, ScaError::InvalidContainerImage);
+    }
+
+    #[test]
+    fn reject_package_manager_version_outside_toolchain_pin() {
+        // The fixture pins 1.80, but the archive claims Cargo 1.82.0.
+        let archive = load_cargo_archive("pinned_toolchain.tar.gz");
         let permitted = load_permitted_deps("permitted-dependencies.json");
         let guest_input = GuestInput {
-            src_archive: archive,
+            source: Source::Archive(archive),
             permitted_deps: permitted,
             license_policy: None,
+            member_scope: None,
+            feature_selection: None,
+            override_policy: None,
+            verify_vendored_sources: false,
+            link_policy: LinkPolicy::Reject,
+            toolchain_policy: None,
+            crate_package: None,
         };
-        prove_should_fail(guest_input, ScaError::UnsupportedLockfileVersion);
+        prove_should_fail(guest_input, ScaError::PackageManagerVersionMismatch);
     }
 
     #[test]
-    fn reject_cargo_lockfile_v2() {
-        let archive = load_cargo_archive("cargo_lock_v2.tar.gz");
+    fn reject_toolchain_channel_overflowing_a_version() {
+        // The fixture pins the channel `1.18446744073709551615`, whose next
+        // minor release does not fit in a version number.
+        let archive = load_cargo_archive("overflowing_toolchain.tar.gz");
         let permitted = load_permitted_deps("permitted-dependencies.json");
         let guest_input = GuestInput {
-            src_archive: archive,
+            source: Source::Archive(archive),
             permitted_deps: permitted,
             license_policy: None,
+            member_scope: None,
+            feature_selection: None,
+            override_policy: None,
+            verify_vendored_sources: false,
+            link_policy: LinkPolicy::Reject,
+            toolchain_policy: None,
+            crate_package: None,
         };
-        prove_should_fail(guest_input, ScaError::UnsupportedLockfileVersion);
+        prove_should_fail(guest_input, ScaError::ManifestParseError);
     }
 }
 
@@ -432,9 +1189,16 @@ mod policy_enforcement {
         let archive = load_cargo_archive("safe.tar.gz");
         let permitted = load_permitted_deps("permitted-dependencies-minimal.json");
         let guest_input = GuestInput {
-            src_archive: archive,
+            source: Source::Archive(archive),
             permitted_deps: permitted,
             license_policy: None,
+            member_scope: None,
+            feature_selection: None,
+            override_policy: None,
+            verify_vendored_sources: false,
+            link_policy: LinkPolicy::Reject,
+            toolchain_policy: None,
+            crate_package: None,
         };
         prove_should_fail(guest_input, ScaError::DisallowedDependency);
     }
@@ -444,9 +1208,16 @@ mod policy_enforcement {
         let archive = load_cargo_archive("vuln.tar.gz");
         let permitted = load_permitted_deps("permitted-dependencies.json");
         let guest_input = GuestInput {
-            src_archive: archive,
+            source: Source::Archive(archive),
             permitted_deps: permitted,
             license_policy: None,
+            member_scope: None,
+            feature_selection: None,
+            override_policy: None,
+            verify_vendored_sources: false,
+            link_policy: LinkPolicy::Reject,
+            toolchain_policy: None,
+            crate_package: None,
         };
         prove_should_fail(guest_input, ScaError::DisallowedVersion);
     }
@@ -456,9 +1227,16 @@ mod policy_enforcement {
         let archive = load_cargo_archive("virtual_workspace_vuln.tar.gz");
         let permitted = load_permitted_deps("permitted-dependencies.json");
         let guest_input = GuestInput {
-            src_archive: archive,
+            source: Source::Archive(archive),
             permitted_deps: permitted,
             license_policy: None,
+            member_scope: None,
+            feature_selection: None,
+            override_policy: None,
+            verify_vendored_sources: false,
+            link_policy: LinkPolicy::Reject,
+            toolchain_policy: None,
+            crate_package: None,
         };
         prove_should_fail(guest_input, ScaError::DisallowedVersion);
     }
@@ -468,9 +1246,16 @@ mod policy_enforcement {
         let archive = load_cargo_archive("non_virtual_workspace_vuln.tar.gz");
         let permitted = load_permitted_deps("permitted-dependencies.json");
         let guest_input = GuestInput {
-            src_archive: archive,
+            source: Source::Archive(archive),
             permitted_deps: permitted,
             license_policy: None,
+            member_scope: None,
+            feature_selection: None,
+            override_policy: None,
+            verify_vendored_sources: false,
+            link_policy: LinkPolicy::Reject,
+            toolchain_policy: None,
+            crate_package: None,
         };
         prove_should_fail(guest_input, ScaError::DisallowedVersion);
     }
@@ -484,21 +1269,418 @@ mod policy_enforcement {
         let license_policy = serde_json::from_str(&json).ok();
 
         let guest_input = GuestInput {
-            src_archive: archive,
+            source: Source::Archive(archive),
             permitted_deps: permitted,
             license_policy,
+            member_scope: None,
+            feature_selection: None,
+            override_policy: None,
+            verify_vendored_sources: false,
+            link_policy: LinkPolicy::Reject,
+            toolchain_policy: None,
+            crate_package: None,
         };
         prove_should_fail(guest_input, ScaError::DisallowedLicense);
     }
 
+    #[test]
+    fn reject_scoped_member_dev_dependency() {
+        let archive = load_cargo_archive("member_scope.tar.gz");
+        let permitted = load_permitted_deps("permitted-dependencies.json");
+        let scope = MemberScope::try_new(vec!["app".to_owned()]).unwrap();
+        let guest_input = GuestInput {
+            source: Source::Archive(archive),
+            permitted_deps: permitted,
+            license_policy: None,
+            member_scope: Some(scope),
+            feature_selection: None,
+            override_policy: None,
+            verify_vendored_sources: false,
+            link_policy: LinkPolicy::Reject,
+            toolchain_policy: None,
+            crate_package: None,
+        };
+        prove_should_fail(guest_input, ScaError::DisallowedDependency);
+    }
+
+    #[test]
+    fn reject_scoped_member_disallowed_version() {
+        let archive = load_cargo_archive("member_scope.tar.gz");
+        let permitted = load_permitted_deps("permitted-dependencies.json");
+        let scope = MemberScope::try_new(vec!["bench".to_owned()])
+            .unwrap()
+            .with_dev_dependencies_excluded(true);
+        let guest_input = GuestInput {
+            source: Source::Archive(archive),
+            permitted_deps: permitted,
+            license_policy: None,
+            member_scope: Some(scope),
+            feature_selection: None,
+            override_policy: None,
+            verify_vendored_sources: false,
+            link_policy: LinkPolicy::Reject,
+            toolchain_policy: None,
+            crate_package: None,
+        };
+        prove_should_fail(guest_input, ScaError::DisallowedVersion);
+    }
+
+    #[test]
+    fn reject_workspace_without_dev_dependencies_excluded() {
+        let archive = load_cargo_archive("dev_only_violation.tar.gz");
+        let permitted = load_permitted_deps("permitted-dependencies.json");
+        let guest_input = GuestInput {
+            source: Source::Archive(archive),
+            permitted_deps: permitted,
+            license_policy: None,
+            member_scope: None,
+            feature_selection: None,
+            override_policy: None,
+            verify_vendored_sources: false,
+            link_policy: LinkPolicy::Reject,
+            toolchain_policy: None,
+            crate_package: None,
+        };
+        prove_should_fail(guest_input, ScaError::DisallowedDependency);
+    }
+
+    #[test]
+    fn reject_vulnerable_member_with_dev_dependencies_excluded() {
+        let archive = load_cargo_archive("member_scope.tar.gz");
+        let permitted = load_permitted_deps("permitted-dependencies.json");
+        let guest_input = GuestInput {
+            source: Source::Archive(archive),
+            permitted_deps: permitted,
+            license_policy: None,
+            member_scope: Some(MemberScope::workspace().with_dev_dependencies_excluded(true)),
+            feature_selection: None,
+            override_policy: None,
+            verify_vendored_sources: false,
+            link_policy: LinkPolicy::Reject,
+            toolchain_policy: None,
+            crate_package: None,
+        };
+        prove_should_fail(guest_input, ScaError::DisallowedVersion);
+    }
+
+    #[test]
+    fn reject_dependency_activated_by_member_feature() {
+        let archive = load_cargo_archive("optional_features.tar.gz");
+        let permitted = load_permitted_deps("permitted-dependencies.json");
+        let features = FeatureSelection::try_new(vec!["checks".to_owned()], true).unwrap();
+        let guest_input = GuestInput {
+            source: Source::Archive(archive),
+            permitted_deps: permitted,
+            license_policy: None,
+            member_scope: None,
+            feature_selection: Some(features),
+            override_policy: None,
+            verify_vendored_sources: false,
+            link_policy: LinkPolicy::Reject,
+            toolchain_policy: None,
+            crate_package: None,
+        };
+        prove_should_fail(guest_input, ScaError::DisallowedDependency);
+    }
+
+    #[test]
+    fn reject_optional_dependency_without_feature_resolution() {
+        let archive = load_cargo_archive("optional_features.tar.gz");
+        let permitted = load_permitted_deps("permitted-dependencies.json");
+        let guest_input = GuestInput {
+            source: Source::Archive(archive),
+            permitted_deps: permitted,
+            license_policy: None,
+            member_scope: None,
+            feature_selection: None,
+            override_policy: None,
+            verify_vendored_sources: false,
+            link_policy: LinkPolicy::Reject,
+            toolchain_policy: None,
+            crate_package: None,
+        };
+        prove_should_fail(guest_input, ScaError::DisallowedDependency);
+    }
+
+    #[test]
+    fn reject_unknown_feature() {
+        let archive = load_cargo_archive("optional_features.tar.gz");
+        let permitted = load_permitted_deps("permitted-dependencies.json");
+        let features = FeatureSelection::try_new(vec!["not-a-feature".to_owned()], true).unwrap();
+        let guest_input = GuestInput {
+            source: Source::Archive(archive),
+            permitted_deps: permitted,
+            license_policy: None,
+            member_scope: None,
+            feature_selection: Some(features),
+            override_policy: None,
+            verify_vendored_sources: false,
+            link_policy: LinkPolicy::Reject,
+            toolchain_policy: None,
+            crate_package: None,
+        };
+        prove_should_fail(guest_input, ScaError::UnknownFeature);
+    }
+
+    #[test]
+    fn reject_vulnerable_patched_dependency() {
+        let archive = load_cargo_archive("patched_dependency.tar.gz");
+        let permitted = load_permitted_deps("permitted-dependencies.json");
+        let guest_input = GuestInput {
+            source: Source::Archive(archive),
+            permitted_deps: permitted,
+            license_policy: None,
+            member_scope: None,
+            feature_selection: None,
+            override_policy: None,
+            verify_vendored_sources: false,
+            link_policy: LinkPolicy::Reject,
+            toolchain_policy: None,
+            crate_package: None,
+        };
+        prove_should_fail(guest_input, ScaError::DisallowedVersion);
+    }
+
+    #[test]
+    fn reject_patch_under_forbid_policy() {
+        let archive = load_cargo_archive("patched_dependency.tar.gz");
+        let permitted = load_permitted_deps("permitted-dependencies.json");
+        let guest_input = GuestInput {
+            source: Source::Archive(archive),
+            permitted_deps: permitted,
+            license_policy: None,
+            member_scope: None,
+            feature_selection: None,
+            override_policy: Some(OverridePolicy::Forbid),
+            verify_vendored_sources: false,
+            link_policy: LinkPolicy::Reject,
+            toolchain_policy: None,
+            crate_package: None,
+        };
+        prove_should_fail(guest_input, ScaError::ForbiddenSourceOverride);
+    }
+
+    #[test]
+    fn reject_patch_of_unlisted_package() {
+        let policy = OverridePolicy::restrict(vec!["serde".to_owned()]).unwrap();
+        let archive = load_cargo_archive("patched_dependency.tar.gz");
+        let permitted = load_permitted_deps("permitted-dependencies.json");
+        let guest_input = GuestInput {
+            source: Source::Archive(archive),
+            permitted_deps: permitted,
+            license_policy: None,
+            member_scope: None,
+            feature_selection: None,
+            override_policy: Some(policy),
+            verify_vendored_sources: false,
+            link_policy: LinkPolicy::Reject,
+            toolchain_policy: None,
+            crate_package: None,
+        };
+        prove_should_fail(guest_input, ScaError::ForbiddenSourceOverride);
+    }
+
+    #[test]
+    fn reject_source_replacement_under_restrict_policy() {
+        let policy = OverridePolicy::restrict(vec!["regex".to_owned()]).unwrap();
+        let archive = load_cargo_archive("source_replacement.tar.gz");
+        let permitted = load_permitted_deps("permitted-dependencies.json");
+        let guest_input = GuestInput {
+            source: Source::Archive(archive),
+            permitted_deps: permitted,
+            license_policy: None,
+            member_scope: None,
+            feature_selection: None,
+            override_policy: Some(policy),
+            verify_vendored_sources: false,
+            link_policy: LinkPolicy::Reject,
+            toolchain_policy: None,
+            crate_package: None,
+        };
+        prove_should_fail(guest_input, ScaError::ForbiddenSourceOverride);
+    }
+
+    #[test]
+    fn reject_tampered_vendored_source() {
+        let archive = load_cargo_archive_with_vendored_sources("vendored_tampered.tar.gz");
+        let permitted = load_permitted_deps("permitted-dependencies.json");
+        let guest_input = GuestInput {
+            source: Source::Archive(archive),
+            permitted_deps: permitted,
+            license_policy: None,
+            member_scope: None,
+            feature_selection: None,
+            override_policy: None,
+            verify_vendored_sources: true,
+            link_policy: LinkPolicy::Reject,
+            toolchain_policy: None,
+            crate_package: None,
+        };
+        prove_should_fail(guest_input, ScaError::VendoredSourceMismatch);
+    }
+
+    #[test]
+    fn reject_vendored_sources_left_out() {
+        let archive = load_cargo_archive("vendored.tar.gz");
+        let permitted = load_permitted_deps("permitted-dependencies.json");
+        let guest_input = GuestInput {
+            source: Source::Archive(archive),
+            permitted_deps: permitted,
+            license_policy: None,
+            member_scope: None,
+            feature_selection: None,
+            override_policy: None,
+            verify_vendored_sources: true,
+            link_policy: LinkPolicy::Reject,
+            toolchain_policy: None,
+            crate_package: None,
+        };
+        prove_should_fail(guest_input, ScaError::VendoredSourceMismatch);
+    }
+
+    #[test]
+    fn reject_missing_vendor_directory() {
+        let archive = load_cargo_archive_with_vendored_sources("source_replacement.tar.gz");
+        let permitted = load_permitted_deps("permitted-dependencies.json");
+        let guest_input = GuestInput {
+            source: Source::Archive(archive),
+            permitted_deps: permitted,
+            license_policy: None,
+            member_scope: None,
+            feature_selection: None,
+            override_policy: None,
+            verify_vendored_sources: true,
+            link_policy: LinkPolicy::Reject,
+            toolchain_policy: None,
+            crate_package: None,
+        };
+        prove_should_fail(guest_input, ScaError::VendoredSourceMismatch);
+    }
+
+    #[test]
+    fn reject_vendor_directory_holding_member() {
+        // The config names the `tools` member as a vendor directory, which
+        // would hide its manifest from the workspace checks.
+        let archive = load_cargo_archive("vendored_member.tar.gz");
+        let permitted = load_permitted_deps("permitted-dependencies.json");
+        let guest_input = GuestInput {
+            source: Source::Archive(archive),
+            permitted_deps: permitted,
+            license_policy: None,
+            member_scope: None,
+            feature_selection: None,
+            override_policy: None,
+            verify_vendored_sources: false,
+            link_policy: LinkPolicy::Reject,
+            toolchain_policy: None,
+            crate_package: None,
+        };
+        prove_should_fail(guest_input, ScaError::VendoredSourceMismatch);
+    }
+
+    #[test]
+    fn reject_edition_below_toolchain_policy() {
+        // The fixture's only member uses edition 2021.
+        let archive = load_cargo_archive("safe.tar.gz");
+        let permitted = load_permitted_deps("permitted-dependencies.json");
+        let policy = ToolchainPolicy::try_new(None, None, Some(RustEdition::E2024), None).unwrap();
+        let guest_input = GuestInput {
+            source: Source::Archive(archive),
+            permitted_deps: permitted,
+            license_policy: None,
+            member_scope: None,
+            feature_selection: None,
+            override_policy: None,
+            verify_vendored_sources: false,
+            link_policy: LinkPolicy::Reject,
+            toolchain_policy: Some(policy),
+            crate_package: None,
+        };
+        prove_should_fail(guest_input, ScaError::ToolchainPolicyViolation);
+    }
+
+    #[test]
+    fn reject_unpinned_toolchain_under_toolchain_policy() {
+        // No rust-toolchain file pins the fixture's toolchain.
+        let archive = load_cargo_archive("safe.tar.gz");
+        let permitted = load_permitted_deps("permitted-dependencies.json");
+        let policy =
+            ToolchainPolicy::try_new(Some(Version::new(1, 75, 0)), None, None, None).unwrap();
+        let guest_input = GuestInput {
+            source: Source::Archive(archive),
+            permitted_deps: permitted,
+            license_policy: None,
+            member_scope: None,
+            feature_selection: None,
+            override_policy: None,
+            verify_vendored_sources: false,
+            link_policy: LinkPolicy::Reject,
+            toolchain_policy: Some(policy),
+            crate_package: None,
+        };
+        prove_should_fail(guest_input, ScaError::ToolchainPolicyViolation);
+    }
+
+    #[test]
+    fn reject_disallowed_version_in_auditable_binary() {
+        let guest_input = binary_input("vuln_auditable");
+        prove_should_fail(guest_input, ScaError::DisallowedVersion);
+    }
+
+    #[test]
+    fn reject_disallowed_version_in_git_tree() {
+        let guest_input = git_input("vuln_git.objects");
+        prove_should_fail(guest_input, ScaError::DisallowedVersion);
+    }
+
+    #[test]
+    fn reject_vulnerable_binary_in_container_image() {
+        let guest_input = image_input("vuln_image.tar");
+        prove_should_fail(guest_input, ScaError::DisallowedVersion);
+    }
+
+    #[test]
+    fn reject_member_scope_for_auditable_binary() {
+        let mut guest_input = binary_input("safe_auditable");
+        guest_input.member_scope = Some(MemberScope::try_new(vec!["safe".to_owned()]).unwrap());
+        prove_should_fail(guest_input, ScaError::InapplicableOption);
+    }
+
+    #[test]
+    fn reject_unknown_workspace_member() {
+        let archive = load_cargo_archive("member_scope.tar.gz");
+        let permitted = load_permitted_deps("permitted-dependencies.json");
+        let scope = MemberScope::try_new(vec!["not-a-member".to_owned()]).unwrap();
+        let guest_input = GuestInput {
+            source: Source::Archive(archive),
+            permitted_deps: permitted,
+            license_policy: None,
+            member_scope: Some(scope),
+            feature_selection: None,
+            override_policy: None,
+            verify_vendored_sources: false,
+            link_policy: LinkPolicy::Reject,
+            toolchain_policy: None,
+            crate_package: None,
+        };
+        prove_should_fail(guest_input, ScaError::UnknownWorkspaceMember);
+    }
+
     #[test]
     fn reject_undeclared_dep() {
         let archive = load_cargo_archive("undeclared_dep.tar.gz");
         let permitted = load_permitted_deps("permitted-dependencies.json");
         let guest_input = GuestInput {
-            src_archive: archive,
+            source: Source::Archive(archive),
             permitted_deps: permitted,
             license_policy: None,
+            member_scope: None,
+            feature_selection: None,
+            override_policy: None,
+            verify_vendored_sources: false,
+            link_policy: LinkPolicy::Reject,
+            toolchain_policy: None,
+            crate_package: None,
         };
         prove_should_fail(guest_input, ScaError::UndeclaredLockfileDependency);
     }
@@ -1,11 +1,24 @@
 use crate::PackageManagerSpec;
 use alloc::{sync::Arc, vec::Vec};
 
+/// What the gzipped tarball of a [`SourceBundle`] holds.
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum BundleKind {
+    /// An arbitrary source tree, such as a repository checkout.
+    #[default]
+    SourceTree,
+    /// A `.crate` file as published to a registry: a single `<name>-<version>/`
+    /// directory holding the normalized `Cargo.toml` and `Cargo.toml.orig`.
+    CratePackage,
+}
+
 #[non_exhaustive]
 #[derive(Clone, Debug, PartialEq)]
 pub struct SourceBundle {
     tar_gz: Arc<[u8]>,
     resolved_with: PackageManagerSpec,
+    kind: BundleKind,
 }
 
 impl SourceBundle {
@@ -14,9 +27,17 @@ impl SourceBundle {
         Self {
             tar_gz,
             resolved_with,
+            kind: BundleKind::SourceTree,
         }
     }
 
+    /// Mark what the tarball holds. Defaults to [`BundleKind::SourceTree`].
+    #[must_use]
+    pub const fn with_kind(mut self, kind: BundleKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
     #[must_use]
     pub fn from_vec(tar_gz: Vec<u8>, resolved_with: PackageManagerSpec) -> Self {
         Self::new(tar_gz.into(), resolved_with)
@@ -28,9 +49,75 @@ impl SourceBundle {
         &self.tar_gz
     }
 
-    /// The package manager version that resolved dependencies and built the binary.
+    /// The package manager version that resolved dependencies.
     #[must_use]
     pub const fn resolved_with(&self) -> &PackageManagerSpec {
         &self.resolved_with
     }
+
+    #[must_use]
+    pub const fn kind(&self) -> BundleKind {
+        self.kind
+    }
+}
+
+/// What an [`ArtifactBundle`] holds.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ArtifactKind {
+    /// A binary built with `cargo auditable`, whose `.dep-v0` section lists
+    /// the packages it was compiled from.
+    AuditableBinary,
+    /// An OCI image layout packed as a TAR, as `docker save` writes, holding
+    /// exactly one image.
+    ContainerImage,
+    /// The objects of a Git commit as `git cat-file --batch` prints them: the
+    /// commit first, then its trees and the blobs of its Cargo dependency
+    /// files.
+    GitTree,
+}
+
+/// Something analyzed whole, in place of a source tree. No Merkle archive is
+/// built from it, so the journal commits to no archive root.
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ArtifactBundle {
+    bytes: Arc<[u8]>,
+    resolved_with: PackageManagerSpec,
+    kind: ArtifactKind,
+}
+
+impl ArtifactBundle {
+    #[must_use]
+    pub const fn new(
+        bytes: Arc<[u8]>,
+        resolved_with: PackageManagerSpec,
+        kind: ArtifactKind,
+    ) -> Self {
+        Self {
+            bytes,
+            resolved_with,
+            kind,
+        }
+    }
+
+    #[must_use]
+    pub fn from_vec(bytes: Vec<u8>, resolved_with: PackageManagerSpec, kind: ArtifactKind) -> Self {
+        Self::new(bytes.into(), resolved_with, kind)
+    }
+
+    #[must_use]
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// The package manager version that resolved dependencies and built the artifact.
+    #[must_use]
+    pub const fn resolved_with(&self) -> &PackageManagerSpec {
+        &self.resolved_with
+    }
+
+    #[must_use]
+    pub const fn kind(&self) -> ArtifactKind {
+        self.kind
+    }
 }
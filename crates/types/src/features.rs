@@ -0,0 +1,76 @@
+use crate::{TypesError, validate_nonempty_unique};
+use alloc::{format, string::String, vec::Vec};
+
+use serde::{
+    Deserialize, Serialize,
+    de::{Deserializer, Error as DeError},
+};
+
+/// The Cargo features enabled on the attested workspace members.
+///
+/// Mirrors `cargo build --features ... [--no-default-features]`: a plain
+/// `feature` applies to every attested member that defines it, while
+/// `member/feature` applies to that member only.
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct FeatureSelection {
+    features: Vec<String>,
+    default_features: bool,
+}
+
+impl FeatureSelection {
+    /// Every entry of `features` must be unique; an empty list selects only the
+    /// default features, or none at all if `default_features` is `false`.
+    pub fn try_new(features: Vec<String>, default_features: bool) -> Result<Self, TypesError> {
+        if let Some(bad) = features
+            .iter()
+            .find(|f| f.split('/').count() > 2 || f.split('/').any(str::is_empty))
+        {
+            return Err(TypesError::Validation(format!("invalid feature `{bad}`")));
+        }
+        let features = if features.is_empty() {
+            features
+        } else {
+            validate_nonempty_unique(features, String::clone, |dup: &String| {
+                format!("duplicate feature `{dup}`")
+            })
+            .map_err(TypesError::Validation)?
+            .into()
+        };
+        Ok(Self {
+            features,
+            default_features,
+        })
+    }
+
+    /// Requested features, sorted, as `feature` or `member/feature`.
+    #[must_use]
+    pub fn features(&self) -> &[String] {
+        &self.features
+    }
+
+    /// Whether each attested member's `default` feature is enabled.
+    #[must_use]
+    pub const fn default_features(&self) -> bool {
+        self.default_features
+    }
+}
+
+impl<'de> Deserialize<'de> for FeatureSelection {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct Raw {
+            features: Vec<String>,
+            default_features: bool,
+        }
+
+        let Raw {
+            features,
+            default_features,
+        } = Raw::deserialize(deserializer)?;
+        Self::try_new(features, default_features).map_err(DeError::custom)
+    }
+}
@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+
+/// A Git commit, identified by the SHA-1 object IDs Git shows for it.
+#[non_exhaustive]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct GitCommit {
+    id: [u8; 20],
+    tree: [u8; 20],
+}
+
+impl GitCommit {
+    #[must_use]
+    pub const fn new(id: [u8; 20], tree: [u8; 20]) -> Self {
+        Self { id, tree }
+    }
+
+    /// The commit ID, as `git rev-parse` prints it.
+    #[must_use]
+    pub const fn id(&self) -> &[u8; 20] {
+        &self.id
+    }
+
+    /// The ID of the commit's root tree.
+    #[must_use]
+    pub const fn tree(&self) -> &[u8; 20] {
+        &self.tree
+    }
+}
@@ -0,0 +1,80 @@
+use alloc::{string::String, vec::Vec};
+use serde::{Deserialize, Serialize};
+
+/// A file found in one layer of a container image.
+#[non_exhaustive]
+#[derive(Clone, Debug, Deserialize, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct ImageFile {
+    layer: usize,
+    path: String,
+}
+
+impl ImageFile {
+    #[must_use]
+    pub const fn new(layer: usize, path: String) -> Self {
+        Self { layer, path }
+    }
+
+    /// Index of the layer in the image manifest, from the base layer up.
+    #[must_use]
+    pub const fn layer(&self) -> usize {
+        self.layer
+    }
+
+    /// Path within the layer, as stored in its TAR.
+    #[must_use]
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+/// What a container image was shown to hold.
+#[non_exhaustive]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ImageAttestation {
+    manifest_digest: [u8; 32],
+    layer_roots: Vec<[u8; 32]>,
+    lockfiles: Vec<ImageFile>,
+    binaries: Vec<ImageFile>,
+}
+
+impl ImageAttestation {
+    #[must_use]
+    pub const fn new(
+        manifest_digest: [u8; 32],
+        layer_roots: Vec<[u8; 32]>,
+        lockfiles: Vec<ImageFile>,
+        binaries: Vec<ImageFile>,
+    ) -> Self {
+        Self {
+            manifest_digest,
+            layer_roots,
+            lockfiles,
+            binaries,
+        }
+    }
+
+    /// SHA-256 of the image manifest, as in its `sha256:` digest.
+    #[must_use]
+    pub const fn manifest_digest(&self) -> &[u8; 32] {
+        &self.manifest_digest
+    }
+
+    /// The Merkle root of each uncompressed layer TAR, in manifest order.
+    #[must_use]
+    pub fn layer_roots(&self) -> &[[u8; 32]] {
+        &self.layer_roots
+    }
+
+    /// Every `Cargo.lock` whose packages were audited.
+    #[must_use]
+    pub fn lockfiles(&self) -> &[ImageFile] {
+        &self.lockfiles
+    }
+
+    /// Every binary whose `cargo auditable` dependency list was audited.
+    #[must_use]
+    pub fn binaries(&self) -> &[ImageFile] {
+        &self.binaries
+    }
+}
@@ -8,7 +8,7 @@
 extern crate alloc;
 
 mod bundle;
-pub use bundle::SourceBundle;
+pub use bundle::{ArtifactBundle, ArtifactKind, BundleKind, SourceBundle};
 
 mod dependency;
 pub use dependency::{Dependency, PermittedDependencies};
@@ -16,11 +16,35 @@ pub use dependency::{Dependency, PermittedDependencies};
 mod error;
 pub use error::TypesError;
 
+mod features;
+pub use features::FeatureSelection;
+
+mod git;
+pub use git::GitCommit;
+
+mod image;
+pub use image::{ImageAttestation, ImageFile};
+
 mod license;
 pub use license::{LicenseExpr, LicensePolicy};
 
+mod link;
+pub use link::LinkPolicy;
+
+mod merkle;
+pub use merkle::MerkleFormat;
+
+mod overrides;
+pub use overrides::OverridePolicy;
+
 mod package_manager;
-pub use package_manager::{PackageManager, PackageManagerSpec};
+pub use package_manager::{PackageManager, PackageManagerRange, PackageManagerSpec};
+
+mod scope;
+pub use scope::MemberScope;
+
+mod toolchain;
+pub use toolchain::{MemberToolchain, RustEdition, ToolchainAttestation, ToolchainPolicy};
 
 mod validation;
 pub(crate) use validation::validate_nonempty_unique;
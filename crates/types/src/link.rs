@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+
+/// What becomes of Cargo dependency files that the archive stores as symbolic
+/// or hard links.
+///
+/// A link entry carries no data of its own, so read as a file it would look
+/// empty. Under either policy, a dependency file beneath a symbolic link to a
+/// directory is rejected, as it would be extracted somewhere else.
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkPolicy {
+    /// Any dependency file that is a link is rejected.
+    #[default]
+    Reject,
+    /// A dependency file that is a link is read from the regular file it
+    /// leads to, which must be an entry of the same archive.
+    Resolve,
+}
+
+impl LinkPolicy {
+    /// Returns true if links are followed to their targets.
+    #[must_use]
+    pub const fn resolves_links(self) -> bool {
+        matches!(self, Self::Resolve)
+    }
+}
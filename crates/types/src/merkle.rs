@@ -0,0 +1,56 @@
+use crate::TypesError;
+use alloc::format;
+use core::str::FromStr;
+use serde::{Deserialize, Serialize};
+
+/// How the Merkle tree over an archive is hashed.
+///
+/// Leaf 0 of every format describes the tree, so that no header can be left
+/// out. The other leaves are the archive's 512-byte blocks in order, or in
+/// [`MerkleFormat::FileRecords`] one record per entry.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MerkleFormat {
+    /// Leaves are `SHA256(block)` and nodes `SHA256(left || right)`, the last
+    /// node of an odd level being paired with itself. Leaf 0 holds the
+    /// header count in ASCII.
+    #[default]
+    V1,
+    /// Leaves are `SHA256(0x00 || block)` and nodes `SHA256(0x01 || left ||
+    /// right)`, odd levels being padded with an all-zero hash. Leaf 0 is a
+    /// metadata block naming the format version, the hash algorithm, the
+    /// block count and the header count.
+    V2,
+    /// Hashed as v2, but each leaf after the metadata leaf is the record of
+    /// one entry, `SHA256(0x02 || content hash || canonical header)`, so that
+    /// only the contents of dependency files need to be sent in full. Its
+    /// roots differ from those of v1 and v2 for the same archive.
+    FileRecords,
+}
+
+impl MerkleFormat {
+    /// The format's version number, as a metadata leaf records it.
+    #[must_use]
+    pub const fn version(self) -> u32 {
+        match self {
+            Self::V1 => 1,
+            Self::V2 => 2,
+            Self::FileRecords => 3,
+        }
+    }
+}
+
+impl FromStr for MerkleFormat {
+    type Err = TypesError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "1" | "v1" => Ok(Self::V1),
+            "2" | "v2" => Ok(Self::V2),
+            "3" | "v3" | "file-records" => Ok(Self::FileRecords),
+            _ => Err(TypesError::Validation(format!(
+                "unknown Merkle format `{s}`"
+            ))),
+        }
+    }
+}
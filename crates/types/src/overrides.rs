@@ -0,0 +1,76 @@
+use crate::{TypesError, validate_nonempty_unique};
+use alloc::{format, string::String, vec::Vec};
+use nonempty::NonEmpty;
+
+use serde::{
+    Deserialize, Serialize,
+    de::{Deserializer, Error as DeError},
+};
+
+/// Which source overrides an archive may use.
+///
+/// Overrides redirect where Cargo takes dependency sources from: `[patch]`
+/// and `[replace]` in the workspace root manifest, and `[patch]`, `[source]`
+/// replacement and `paths` in `.cargo/config.toml`.
+///
+/// Packages provided through an override are audited like any other
+/// dependency, whichever policy is in force.
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverridePolicy {
+    /// Any override is accepted.
+    Allow,
+    /// Any override is rejected.
+    Forbid,
+    /// Only `[patch]` and `[replace]` entries for these packages are accepted.
+    Restrict(NonEmpty<String>),
+}
+
+impl OverridePolicy {
+    /// `packages` must contain at least one name, and every entry must be unique.
+    pub fn restrict(packages: Vec<String>) -> Result<Self, TypesError> {
+        let non_empty = validate_nonempty_unique(packages, String::clone, |dup: &String| {
+            format!("duplicate overridable package `{dup}`")
+        })
+        .map_err(TypesError::Validation)?;
+        Ok(Self::Restrict(non_empty))
+    }
+
+    /// Returns true if a `[patch]` or `[replace]` entry for `package` is accepted.
+    #[must_use]
+    pub fn permits_package(&self, package: &str) -> bool {
+        match self {
+            Self::Allow => true,
+            Self::Forbid => false,
+            Self::Restrict(packages) => packages.iter().any(|p| p == package),
+        }
+    }
+
+    /// Returns true if whole sources may be redirected, which no package list can bound.
+    #[must_use]
+    pub const fn permits_source_replacement(&self) -> bool {
+        matches!(self, Self::Allow)
+    }
+}
+
+impl<'de> Deserialize<'de> for OverridePolicy {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "snake_case")]
+        enum Raw {
+            Allow,
+            Forbid,
+            Restrict(Vec<String>),
+        }
+
+        match Raw::deserialize(deserializer)? {
+            Raw::Allow => Ok(Self::Allow),
+            Raw::Forbid => Ok(Self::Forbid),
+            Raw::Restrict(packages) => Self::restrict(packages).map_err(DeError::custom),
+        }
+    }
+}
@@ -1,5 +1,9 @@
-use crate::Version;
-use serde::{Deserialize, Serialize};
+use crate::{TypesError, Version};
+use alloc::format;
+use serde::{
+    Deserialize, Serialize,
+    de::{Deserializer, Error as DeError},
+};
 
 #[non_exhaustive]
 #[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -31,3 +35,73 @@ impl PackageManagerSpec {
         &self.version
     }
 }
+
+/// The versions of a package manager that an archive is consistent with.
+///
+/// `min` is inclusive and `max`, if any, exclusive.
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct PackageManagerRange {
+    manager: PackageManager,
+    min: Version,
+    max: Option<Version>,
+}
+
+impl PackageManagerRange {
+    /// `max`, if given, must be greater than `min`.
+    pub fn try_new(
+        manager: PackageManager,
+        min: Version,
+        max: Option<Version>,
+    ) -> Result<Self, TypesError> {
+        if let Some(max) = max.as_ref().filter(|max| **max <= min) {
+            return Err(TypesError::Validation(format!(
+                "empty version range: {min} is not below {max}"
+            )));
+        }
+        Ok(Self { manager, min, max })
+    }
+
+    #[must_use]
+    pub const fn manager(&self) -> PackageManager {
+        self.manager
+    }
+
+    /// Lowest version in the range.
+    #[must_use]
+    pub const fn min(&self) -> &Version {
+        &self.min
+    }
+
+    /// First version above the range. If `None`, the range is unbounded.
+    #[must_use]
+    pub const fn max(&self) -> Option<&Version> {
+        self.max.as_ref()
+    }
+
+    /// Returns true if `spec` names this manager at a version within the range.
+    #[must_use]
+    pub fn contains(&self, spec: &PackageManagerSpec) -> bool {
+        let version = spec.version();
+        spec.manager() == self.manager
+            && *version >= self.min
+            && self.max.as_ref().is_none_or(|max| version < max)
+    }
+}
+
+impl<'de> Deserialize<'de> for PackageManagerRange {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct Raw {
+            manager: PackageManager,
+            min: Version,
+            max: Option<Version>,
+        }
+
+        let Raw { manager, min, max } = Raw::deserialize(deserializer)?;
+        Self::try_new(manager, min, max).map_err(DeError::custom)
+    }
+}
@@ -0,0 +1,81 @@
+use crate::{TypesError, validate_nonempty_unique};
+use alloc::{format, string::String, vec::Vec};
+use nonempty::NonEmpty;
+
+use serde::{
+    Deserialize, Serialize,
+    de::{Deserializer, Error as DeError},
+};
+
+/// Restricts an audit to the packages reachable from selected workspace members.
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct MemberScope {
+    members: Option<NonEmpty<String>>,
+    exclude_dev_dependencies: bool,
+}
+
+impl MemberScope {
+    /// `members` must contain at least one package name, and every entry must be unique.
+    pub fn try_new(members: Vec<String>) -> Result<Self, TypesError> {
+        let non_empty = validate_nonempty_unique(members, String::clone, |dup: &String| {
+            format!("duplicate workspace member `{dup}`")
+        })
+        .map_err(TypesError::Validation)?;
+        Ok(Self {
+            members: Some(non_empty),
+            exclude_dev_dependencies: false,
+        })
+    }
+
+    /// Starts the audit from every workspace member.
+    #[must_use]
+    pub const fn workspace() -> Self {
+        Self {
+            members: None,
+            exclude_dev_dependencies: false,
+        }
+    }
+
+    /// Skip the dev-dependency edges of every workspace member on the way.
+    #[must_use]
+    pub const fn with_dev_dependencies_excluded(mut self, excluded: bool) -> Self {
+        self.exclude_dev_dependencies = excluded;
+        self
+    }
+
+    /// Package names of the workspace members the audit starts from. If
+    /// `None`, it starts from all of them.
+    #[must_use]
+    pub const fn members(&self) -> Option<&NonEmpty<String>> {
+        self.members.as_ref()
+    }
+
+    #[must_use]
+    pub const fn excludes_dev_dependencies(&self) -> bool {
+        self.exclude_dev_dependencies
+    }
+}
+
+impl<'de> Deserialize<'de> for MemberScope {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct Raw {
+            members: Option<Vec<String>>,
+            exclude_dev_dependencies: bool,
+        }
+
+        let Raw {
+            members,
+            exclude_dev_dependencies,
+        } = Raw::deserialize(deserializer)?;
+
+        members
+            .map_or(Ok(Self::workspace()), Self::try_new)
+            .map(|scope| scope.with_dev_dependencies_excluded(exclude_dev_dependencies))
+            .map_err(DeError::custom)
+    }
+}
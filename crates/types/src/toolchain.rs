@@ -0,0 +1,224 @@
+use crate::{TypesError, Version};
+use alloc::{format, string::String, vec::Vec};
+use core::str::FromStr;
+use serde::{
+    Deserialize, Serialize,
+    de::{Deserializer, Error as DeError},
+};
+
+/// A Rust edition, as set by a manifest's `edition` field.
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+pub enum RustEdition {
+    #[serde(rename = "2015")]
+    E2015,
+    #[serde(rename = "2018")]
+    E2018,
+    #[serde(rename = "2021")]
+    E2021,
+    #[serde(rename = "2024")]
+    E2024,
+}
+
+impl FromStr for RustEdition {
+    type Err = TypesError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "2015" => Ok(Self::E2015),
+            "2018" => Ok(Self::E2018),
+            "2021" => Ok(Self::E2021),
+            "2024" => Ok(Self::E2024),
+            _ => Err(TypesError::Validation(format!("unknown edition `{s}`"))),
+        }
+    }
+}
+
+/// Bounds on the Rust toolchain and editions an archive may target.
+///
+/// Toolchain bounds apply to the release that `rust-toolchain.toml` (or the
+/// legacy `rust-toolchain`) pins, with `1.78` counting as 1.78.0; a channel
+/// such as `stable` pins no release and so satisfies none. Edition bounds
+/// apply to every workspace member. All bounds are inclusive.
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ToolchainPolicy {
+    min_toolchain: Option<Version>,
+    max_toolchain: Option<Version>,
+    min_edition: Option<RustEdition>,
+    max_edition: Option<RustEdition>,
+}
+
+impl ToolchainPolicy {
+    /// At least one bound must be given, and no minimum may exceed its maximum.
+    pub fn try_new(
+        min_toolchain: Option<Version>,
+        max_toolchain: Option<Version>,
+        min_edition: Option<RustEdition>,
+        max_edition: Option<RustEdition>,
+    ) -> Result<Self, TypesError> {
+        if min_toolchain.is_none()
+            && max_toolchain.is_none()
+            && min_edition.is_none()
+            && max_edition.is_none()
+        {
+            return Err(TypesError::Validation(
+                "toolchain policy sets no bound".into(),
+            ));
+        }
+        if let (Some(min), Some(max)) = (&min_toolchain, &max_toolchain) {
+            if min > max {
+                return Err(TypesError::Validation(format!(
+                    "minimum toolchain {min} exceeds maximum {max}"
+                )));
+            }
+        }
+        if let (Some(min), Some(max)) = (min_edition, max_edition) {
+            if min > max {
+                return Err(TypesError::Validation(format!(
+                    "minimum edition {min:?} exceeds maximum {max:?}"
+                )));
+            }
+        }
+        Ok(Self {
+            min_toolchain,
+            max_toolchain,
+            min_edition,
+            max_edition,
+        })
+    }
+
+    #[must_use]
+    pub const fn min_toolchain(&self) -> Option<&Version> {
+        self.min_toolchain.as_ref()
+    }
+
+    #[must_use]
+    pub const fn max_toolchain(&self) -> Option<&Version> {
+        self.max_toolchain.as_ref()
+    }
+
+    #[must_use]
+    pub const fn min_edition(&self) -> Option<RustEdition> {
+        self.min_edition
+    }
+
+    #[must_use]
+    pub const fn max_edition(&self) -> Option<RustEdition> {
+        self.max_edition
+    }
+
+    /// Returns true if the policy bounds the toolchain, which then must be pinned.
+    #[must_use]
+    pub const fn bounds_toolchain(&self) -> bool {
+        self.min_toolchain.is_some() || self.max_toolchain.is_some()
+    }
+
+    /// Returns true if a toolchain pinned to `release` is accepted.
+    #[must_use]
+    pub fn permits_toolchain(&self, release: &Version) -> bool {
+        self.min_toolchain.as_ref().is_none_or(|min| release >= min)
+            && self.max_toolchain.as_ref().is_none_or(|max| release <= max)
+    }
+
+    /// Returns true if a member of `edition` is accepted.
+    #[must_use]
+    pub fn permits_edition(&self, edition: RustEdition) -> bool {
+        self.min_edition.is_none_or(|min| edition >= min)
+            && self.max_edition.is_none_or(|max| edition <= max)
+    }
+}
+
+impl<'de> Deserialize<'de> for ToolchainPolicy {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct Raw {
+            #[serde(default)]
+            min_toolchain: Option<Version>,
+            #[serde(default)]
+            max_toolchain: Option<Version>,
+            #[serde(default)]
+            min_edition: Option<RustEdition>,
+            #[serde(default)]
+            max_edition: Option<RustEdition>,
+        }
+
+        let raw = Raw::deserialize(deserializer)?;
+        Self::try_new(
+            raw.min_toolchain,
+            raw.max_toolchain,
+            raw.min_edition,
+            raw.max_edition,
+        )
+        .map_err(DeError::custom)
+    }
+}
+
+/// The edition and minimum supported Rust version of one workspace member.
+#[non_exhaustive]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct MemberToolchain {
+    name: String,
+    edition: RustEdition,
+    rust_version: Option<Version>,
+}
+
+impl MemberToolchain {
+    #[must_use]
+    pub const fn new(name: String, edition: RustEdition, rust_version: Option<Version>) -> Self {
+        Self {
+            name,
+            edition,
+            rust_version,
+        }
+    }
+
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The member's edition, 2015 if the manifest sets none.
+    #[must_use]
+    pub const fn edition(&self) -> RustEdition {
+        self.edition
+    }
+
+    /// The member's `rust-version`. If `None`, the manifest declares none.
+    #[must_use]
+    pub const fn rust_version(&self) -> Option<&Version> {
+        self.rust_version.as_ref()
+    }
+}
+
+/// What an archive shows about the Rust toolchain it targets.
+#[non_exhaustive]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ToolchainAttestation {
+    channel: Option<String>,
+    members: Vec<MemberToolchain>,
+}
+
+impl ToolchainAttestation {
+    /// `members` are kept sorted by name.
+    #[must_use]
+    pub fn new(channel: Option<String>, mut members: Vec<MemberToolchain>) -> Self {
+        members.sort_by(|a, b| a.name.cmp(&b.name));
+        Self { channel, members }
+    }
+
+    /// The channel `rust-toolchain.toml` (or `rust-toolchain`) sets for the
+    /// workspace. If `None`, no such file applies or it sets no channel.
+    #[must_use]
+    pub fn channel(&self) -> Option<&str> {
+        self.channel.as_deref()
+    }
+
+    #[must_use]
+    pub fn members(&self) -> &[MemberToolchain] {
+        &self.members
+    }
+}
@@ -1,6 +1,8 @@
 use zk_sca_types::{
-    Dependency, LicenseExpr, LicensePolicy, PackageManager, PackageManagerSpec,
-    PermittedDependencies, SourceBundle, TypesError, Version,
+    ArtifactBundle, ArtifactKind, BundleKind, Dependency, FeatureSelection, LicenseExpr,
+    LicensePolicy, LinkPolicy, MemberScope, MerkleFormat, OverridePolicy, PackageManager,
+    PackageManagerRange, PackageManagerSpec, PermittedDependencies, RustEdition, SourceBundle,
+    ToolchainPolicy, TypesError, Version,
 };
 
 #[test]
@@ -24,6 +26,19 @@ fn smoke_basic_constructors() {
     let bundle = SourceBundle::from_vec(vec![0u8], spec.clone());
     assert_eq!(bundle.tar_gz(), &[0u8]);
     assert_eq!(bundle.resolved_with().manager(), PackageManager::Cargo);
+    assert_eq!(bundle.kind(), BundleKind::SourceTree);
+    let package = bundle.with_kind(BundleKind::CratePackage);
+    assert_eq!(package.kind(), BundleKind::CratePackage);
+
+    // ArtifactBundle
+    let binary = ArtifactBundle::from_vec(vec![0u8], spec.clone(), ArtifactKind::AuditableBinary);
+    assert_eq!(binary.bytes(), &[0u8]);
+    assert_eq!(binary.resolved_with().manager(), PackageManager::Cargo);
+    assert_eq!(binary.kind(), ArtifactKind::AuditableBinary);
+    let image = ArtifactBundle::from_vec(vec![0u8], spec.clone(), ArtifactKind::ContainerImage);
+    assert_eq!(image.kind(), ArtifactKind::ContainerImage);
+    let git = ArtifactBundle::from_vec(vec![0u8], spec.clone(), ArtifactKind::GitTree);
+    assert_eq!(git.kind(), ArtifactKind::GitTree);
 
     // PermittedDependencies
     let ok = PermittedDependencies::try_new(PackageManager::Cargo, vec![dep.clone()]);
@@ -51,4 +66,88 @@ fn smoke_basic_constructors() {
 
     let dup_pol = LicensePolicy::try_new(vec![req.clone(), req.clone()]);
     assert!(dup_pol.is_err());
+
+    // MemberScope
+    let scope = MemberScope::try_new(vec!["app".into()]).unwrap();
+    assert_eq!(scope.members().unwrap().first(), "app");
+    assert!(!scope.excludes_dev_dependencies());
+    assert!(MemberScope::workspace().members().is_none());
+
+    let dup_scope = MemberScope::try_new(vec!["app".into(), "app".into()]);
+    assert!(dup_scope.is_err());
+    assert!(MemberScope::try_new(Vec::new()).is_err());
+
+    // FeatureSelection
+    let features = FeatureSelection::try_new(vec!["std".into(), "app/cli".into()], false).unwrap();
+    assert_eq!(features.features(), ["app/cli", "std"]);
+    assert!(!features.default_features());
+    assert!(FeatureSelection::try_new(Vec::new(), true).is_ok());
+    assert!(FeatureSelection::try_new(vec!["std".into(), "std".into()], true).is_err());
+    assert!(FeatureSelection::try_new(vec!["app/".into()], true).is_err());
+
+    // OverridePolicy
+    let restrict = OverridePolicy::restrict(vec!["regex".into()]).unwrap();
+    assert!(restrict.permits_package("regex"));
+    assert!(!restrict.permits_package("serde"));
+    assert!(!restrict.permits_source_replacement());
+    assert!(OverridePolicy::Allow.permits_source_replacement());
+    assert!(!OverridePolicy::Forbid.permits_package("regex"));
+    assert!(OverridePolicy::restrict(Vec::new()).is_err());
+
+    // PackageManagerRange
+    let range = PackageManagerRange::try_new(
+        PackageManager::Cargo,
+        Version::new(1, 78, 0),
+        Some(Version::new(1, 80, 0)),
+    )
+    .unwrap();
+    let claim = |v| PackageManagerSpec::new(PackageManager::Cargo, v);
+    assert!(range.contains(&claim(Version::new(1, 79, 3))));
+    assert!(!range.contains(&claim(Version::new(1, 80, 0))));
+    assert!(!range.contains(&claim(Version::new(1, 77, 0))));
+    let empty = PackageManagerRange::try_new(
+        PackageManager::Cargo,
+        Version::new(1, 80, 0),
+        Some(Version::new(1, 80, 0)),
+    );
+    assert!(empty.is_err());
+
+    // ToolchainPolicy
+    let policy = ToolchainPolicy::try_new(
+        Some(Version::new(1, 75, 0)),
+        None,
+        Some(RustEdition::E2021),
+        None,
+    )
+    .unwrap();
+    assert!(policy.bounds_toolchain());
+    assert!(policy.permits_toolchain(&Version::new(1, 80, 0)));
+    assert!(!policy.permits_toolchain(&Version::new(1, 74, 1)));
+    assert!(policy.permits_edition(RustEdition::E2024));
+    assert!(!policy.permits_edition(RustEdition::E2018));
+    assert!(ToolchainPolicy::try_new(None, None, None, None).is_err());
+    let inverted = ToolchainPolicy::try_new(
+        None,
+        None,
+        Some(RustEdition::E2024),
+        Some(RustEdition::E2021),
+    );
+    assert!(inverted.is_err());
+    assert_eq!("2021".parse::<RustEdition>().unwrap(), RustEdition::E2021);
+    assert!("2020".parse::<RustEdition>().is_err());
+
+    // LinkPolicy
+    assert_eq!(LinkPolicy::default(), LinkPolicy::Reject);
+    assert!(LinkPolicy::Resolve.resolves_links());
+    assert!(!LinkPolicy::Reject.resolves_links());
+
+    // MerkleFormat
+    assert_eq!(MerkleFormat::default(), MerkleFormat::V1);
+    assert_eq!("v2".parse::<MerkleFormat>().unwrap(), MerkleFormat::V2);
+    assert_eq!(MerkleFormat::V2.version(), 2);
+    assert_eq!(
+        "file-records".parse::<MerkleFormat>().unwrap(),
+        MerkleFormat::FileRecords
+    );
+    assert!("4".parse::<MerkleFormat>().is_err());
 }
@@ -6,7 +6,11 @@
 
 use risc0_zkvm::{Journal, Receipt, sha::Digest};
 use zk_sca_guest_abi::GuestOutput;
-use zk_sca_types::{LicensePolicy, PermittedDependencies};
+use zk_sca_types::{
+    FeatureSelection, GitCommit, ImageAttestation, LicensePolicy, LinkPolicy, MemberScope,
+    MerkleFormat, OverridePolicy, PackageManagerRange, PermittedDependencies, ToolchainAttestation,
+    ToolchainPolicy,
+};
 
 #[derive(Debug)]
 pub enum VerifierError {
@@ -47,9 +51,44 @@ pub fn verify_receipt(receipt: &Receipt, image_id: Digest) -> Result<(), Verifie
 
 #[derive(Debug)]
 pub struct DecodedJournal {
-    pub root_hash: [u8; 32],
+    /// The root of the analyzed source archive. `None` when the guest checked
+    /// an artifact, such as an auditable binary, rather than an archive.
+    pub root_hash: Option<[u8; 32]>,
+    /// How `root_hash` was hashed. V0 and V1 journals were always hashed in v1.
+    pub merkle_format: Option<MerkleFormat>,
     pub permitted_deps: PermittedDependencies,
     pub license_policy: Option<LicensePolicy>,
+    /// The workspace members that were attested, and whether packages reachable
+    /// only through dev-dependency edges were left out. If `None`, every package
+    /// of the whole workspace was.
+    pub member_scope: Option<MemberScope>,
+    /// The features the claim covers. If `None`, every optional dependency was audited.
+    pub feature_selection: Option<FeatureSelection>,
+    /// The source override policy the analyzed source code was held to.
+    pub override_policy: Option<OverridePolicy>,
+    /// Whether every locked package was shown to be vendored byte-identical to its release.
+    pub verify_vendored_sources: bool,
+    /// How dependency files stored as links were treated. `None` for V0 and
+    /// V1 journals, whose guest read links as empty files.
+    pub link_policy: Option<LinkPolicy>,
+    /// The package manager releases the archive was shown to be consistent with.
+    /// `None` for V0 and V1 journals, which only carry the claimed version.
+    pub package_manager_range: Option<PackageManagerRange>,
+    /// The toolchain policy the analyzed source code was held to.
+    pub toolchain_policy: Option<ToolchainPolicy>,
+    /// The toolchain channel, editions and `rust-version`s the archive declares.
+    /// `None` for V0 and V1 journals.
+    pub toolchain: Option<ToolchainAttestation>,
+    /// SHA-256 of the analyzed `.crate` file, if the source was a published package.
+    pub crate_checksum: Option<[u8; 32]>,
+    /// SHA-256 of the analyzed `cargo auditable` binary, if a binary was analyzed.
+    pub binary_hash: Option<[u8; 32]>,
+    /// The manifest digest, layer roots and audited files of the analyzed
+    /// container image, if an image was analyzed.
+    pub image: Option<ImageAttestation>,
+    /// The Git commit whose tree was analyzed, if the source was read from a
+    /// Git repository.
+    pub git_commit: Option<GitCommit>,
 }
 
 /// Decode and version-check the journal emitted by the guest.
@@ -63,9 +102,58 @@ pub fn decode_journal(journal: &Journal) -> Result<DecodedJournal, VerifierError
 
     match guest_out {
         GuestOutput::V0(v0) => Ok(DecodedJournal {
-            root_hash: v0.root_hash,
+            root_hash: Some(v0.root_hash),
+            merkle_format: Some(MerkleFormat::V1),
             permitted_deps: v0.permitted_deps,
             license_policy: v0.license_policy,
+            member_scope: None,
+            feature_selection: None,
+            override_policy: None,
+            verify_vendored_sources: false,
+            link_policy: None,
+            package_manager_range: None,
+            toolchain_policy: None,
+            toolchain: None,
+            crate_checksum: None,
+            binary_hash: None,
+            image: None,
+            git_commit: None,
+        }),
+        GuestOutput::V1(v1) => Ok(DecodedJournal {
+            root_hash: Some(v1.root_hash),
+            merkle_format: Some(MerkleFormat::V1),
+            permitted_deps: v1.permitted_deps,
+            license_policy: v1.license_policy,
+            member_scope: v1.member_scope,
+            feature_selection: None,
+            override_policy: None,
+            verify_vendored_sources: false,
+            link_policy: None,
+            package_manager_range: None,
+            toolchain_policy: None,
+            toolchain: None,
+            crate_checksum: None,
+            binary_hash: None,
+            image: None,
+            git_commit: None,
+        }),
+        GuestOutput::V2(v2) => Ok(DecodedJournal {
+            root_hash: v2.root_hash,
+            merkle_format: v2.merkle_format,
+            permitted_deps: v2.permitted_deps,
+            license_policy: v2.license_policy,
+            member_scope: v2.member_scope,
+            feature_selection: v2.feature_selection,
+            override_policy: v2.override_policy,
+            verify_vendored_sources: v2.verify_vendored_sources,
+            link_policy: Some(v2.link_policy),
+            package_manager_range: Some(v2.package_manager_range),
+            toolchain_policy: v2.toolchain_policy,
+            toolchain: Some(v2.toolchain),
+            crate_checksum: v2.crate_checksum,
+            binary_hash: v2.binary_hash,
+            image: v2.image,
+            git_commit: v2.git_commit,
         }),
         other => Err(VerifierError::UnsupportedJournalVersion(other.version())),
     }
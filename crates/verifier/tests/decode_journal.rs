@@ -0,0 +1,36 @@
+use risc0_zkvm::Journal;
+use std::{fs, path::Path};
+use zk_sca_guest_abi::{GuestOutput, GuestOutputV1};
+use zk_sca_types::{MemberScope, MerkleFormat, PermittedDependencies};
+use zk_sca_verifier::decode_journal;
+
+fn load_permitted_deps(name: &str) -> PermittedDependencies {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("..")
+        .join("..")
+        .join("fixtures")
+        .join(name);
+    let bytes = fs::read(&path).unwrap_or_else(|e| panic!("read {}: {e}", path.display()));
+    serde_json::from_slice(&bytes).unwrap_or_else(|e| panic!("parse {}: {e}", path.display()))
+}
+
+#[test]
+fn decode_v1_journal() {
+    let member_scope = MemberScope::try_new(vec!["app".to_string()]).unwrap();
+    let out: GuestOutput = GuestOutputV1 {
+        root_hash: [7; 32],
+        permitted_deps: load_permitted_deps("permitted-dependencies.json"),
+        license_policy: None,
+        member_scope: Some(member_scope.clone()),
+    }
+    .into();
+    let words = risc0_zkvm::serde::to_vec(&out).unwrap();
+    let journal = Journal::new(words.iter().flat_map(|w| w.to_le_bytes()).collect());
+
+    let decoded = decode_journal(&journal).unwrap();
+    assert_eq!(decoded.root_hash, Some([7; 32]));
+    assert_eq!(decoded.merkle_format, Some(MerkleFormat::V1));
+    assert_eq!(decoded.member_scope, Some(member_scope));
+    assert!(decoded.link_policy.is_none());
+    assert!(decoded.toolchain.is_none());
+}